@@ -40,7 +40,7 @@ impl ModInfo {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct ModResources {
     pub mods: IndexMap<String, ModInfo>,
     pub loaded_packages: Vec<Package>,
@@ -125,6 +125,14 @@ impl ModPathVisitor {
         }
     }
 
+    pub fn resources(&self) -> &ModResources {
+        &self.resources
+    }
+
+    pub fn into_resources(self) -> ModResources {
+        self.resources
+    }
+
     // TODO: I have an intuition that we could make this into an iterator of some sort to avoid allocating the vector
     /// Enumerate the files  
     fn enumerate_files(
@@ -1,7 +1,12 @@
-use std::{ops::Not, path::PathBuf};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    ops::Not,
+    path::{Path, PathBuf},
+};
 
 use binrw::{BinRead, BinWrite};
-use lsf::{CompressionFlags, CompressionLevel, CompressionMethod};
+use lsf::{decompress::decompress_into, CompressionFlags, CompressionLevel, CompressionMethod};
 
 fn name_len(name: &[u8; 256]) -> usize {
     name.iter().position(|&c| c == 0).unwrap_or(256)
@@ -61,6 +66,24 @@ pub enum PackagedFileContentError {
     Io(std::io::Error),
     /// The file is a deleted file.
     IsDeleted,
+    /// Reading individual files out of solid packages is not yet supported.
+    Solid,
+    Decompress(binrw::Error),
+    /// The decompressed data was not the size the file list said it would be.
+    DifferentSize {
+        expected: u64,
+        actual: u64,
+    },
+}
+impl From<std::io::Error> for PackagedFileContentError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<binrw::Error> for PackagedFileContentError {
+    fn from(e: binrw::Error) -> Self {
+        Self::Decompress(e)
+    }
 }
 
 pub const DELETION_OFFSET: u64 = 0xdeadbeefdeadbeef;
@@ -246,28 +269,50 @@ impl PackagedFileInfo {
         }
     }
 
-    pub fn content(&self) -> Result<(), PackagedFileContentError> {
+    /// Read the (decompressed) contents of the file.  
+    /// `archive_path` is the path to the package file, or the part file if `part > 0`. See
+    /// [`Package::read_file`](super::Package::read_file) which handles that for you.
+    pub fn content(&self, archive_path: &Path) -> Result<Vec<u8>, PackagedFileContentError> {
         if self.is_deletion() {
             return Err(PackagedFileContentError::IsDeleted);
         }
 
-        // TODO: if it has an uncompressed stream, just return that
-
-        if CompressionMethod::None as u32 == (self.flags & 0x0f) && !self.solid {
-            // Use direct stream read for non-compressed files
-            // TODO: it returns uncompresedpackagedfilestream
+        // TODO: solid packages are decompressed as a single frame, which we don't currently keep
+        // around after reading the file list.
+        if self.solid {
+            return Err(PackagedFileContentError::Solid);
         }
 
         if self.size_on_disk > 0x7fffffff {
             panic!("TODO: support large files");
         }
 
-        let mut compressed = vec![0; self.size_on_disk.try_into().unwrap()];
-
-        // TODO: seek in package stream to offset in file
-        // TODO: package stream read size on disk
+        let mut file = BufReader::new(File::open(archive_path)?);
+        file.seek(SeekFrom::Start(self.offset_in_file))?;
+
+        let method = CompressionFlags(self.flags as u8).method();
+        let size = self.size();
+        let compressed = (&mut file).take(self.size_on_disk);
+        // Files in packages are never chunked
+        let mut stream = decompress_into(
+            compressed,
+            self.size_on_disk as u32,
+            size as u32,
+            method,
+            false,
+        )?;
+
+        let mut content = Vec::with_capacity(size as usize);
+        stream.read_to_end(&mut content)?;
+
+        if content.len() as u64 != size {
+            return Err(PackagedFileContentError::DifferentSize {
+                expected: size,
+                actual: content.len() as u64,
+            });
+        }
 
-        todo!()
+        Ok(content)
     }
 }
 impl FileInfoLike for PackagedFileInfo {
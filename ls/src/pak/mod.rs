@@ -11,8 +11,8 @@ use crate::pak::common::{FileEntry15, FILE_ENTRY_15_SIZE};
 
 use self::common::{
     FileEntry13, FileEntry18, FileEntry7, FileInfo, LSPKHeader10, LSPKHeader13, LSPKHeader15,
-    LSPKHeader16, LSPKHeader7, PackagedFileInfo, PackagedFileInfoError, FILE_ENTRY_13_SIZE,
-    FILE_ENTRY_18_SIZE,
+    LSPKHeader16, LSPKHeader7, PackagedFileContentError, PackagedFileInfo, PackagedFileInfoError,
+    FILE_ENTRY_13_SIZE, FILE_ENTRY_18_SIZE,
 };
 
 // TODO: Should we move pak to its own crate?
@@ -488,13 +488,30 @@ impl Package {
     pub fn make_part_filename(&self, part: u32) -> PathBuf {
         make_part_filename(&self.path, part)
     }
+
+    /// Find a file in the package by its path within it, ignoring case.
+    pub fn find_file(&self, name: &str) -> Option<&PackagedFileInfo> {
+        self.files.iter().find_map(|file| match file {
+            FileInfo::Packaged(info) if info.name.eq_ignore_ascii_case(name) => Some(info),
+            _ => None,
+        })
+    }
+
+    /// Read the (decompressed) contents of a file in the package.
+    pub fn read_file(&self, info: &PackagedFileInfo) -> Result<Vec<u8>, PackagedFileContentError> {
+        if info.part == 0 {
+            info.content(&self.path)
+        } else {
+            info.content(&self.make_part_filename(info.part))
+        }
+    }
 }
 
 fn make_part_filename(path: &Path, part: u32) -> PathBuf {
     // TODO: don't unwrap
     let dir = path.parent().unwrap();
-    let base_name = dir.file_stem().unwrap();
-    let extension = dir.extension().unwrap();
+    let base_name = path.file_stem().unwrap();
+    let extension = path.extension().unwrap();
 
     let mut full_name = base_name.to_os_string();
     full_name.push("_");
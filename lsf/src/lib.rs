@@ -12,7 +12,7 @@ use std::{
 
 use attr::TypeId;
 use binrw::{io::TakeSeekExt, meta::ReadEndian, BinRead, BinWrite};
-use util::{until_eof2, PascalStringU16};
+use util::{read_to_end, until_eof2, PascalStringU16};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, BinRead)]
 #[repr(u32)]
@@ -75,6 +75,8 @@ pub struct LSF {
     pub nodes: Nodes,
     #[br(args(base.version, base.metadata.attributes_size_on_disk, base.metadata.attributes_uncompressed_size, base.metadata.compression_flags, base.metadata.has_sibling_data))]
     pub attributes: Attributes,
+    #[br(args(base.version, base.metadata.values_size_on_disk, base.metadata.values_uncompressed_size, base.metadata.compression_flags))]
+    pub values: Values,
 }
 impl LSF {
     // TODO: name_offset makes me wonder whether it is actually an offset or just another index?
@@ -86,6 +88,103 @@ impl LSF {
             .strings
             .get(name_offset as usize)
     }
+
+    /// Indices of the nodes which are regions, which are the roots of the tree.
+    pub fn regions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .nodes
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent_index() == -1)
+            .map(|(i, _)| i)
+    }
+
+    pub fn node_name(&self, node_index: usize) -> Option<&str> {
+        let node = self.nodes.nodes.nodes.get(node_index)?;
+        self.name(node.name_index(), node.name_offset())?
+            .as_str()
+            .ok()
+    }
+
+    pub fn attribute_name(&self, attr_index: usize) -> Option<&str> {
+        let attr = self.attributes.attrs.attrs.get(attr_index)?;
+        self.name(attr.name_index(), attr.name_offset())?
+            .as_str()
+            .ok()
+    }
+
+    /// Indices of the attributes belonging to the node, in order.
+    pub fn node_attributes(&self, node_index: usize) -> Vec<usize> {
+        let attrs = &self.attributes.attrs.attrs;
+        let Some(node) = self.nodes.nodes.nodes.get(node_index) else {
+            return Vec::new();
+        };
+
+        let mut result = Vec::new();
+        let mut index = node.first_attribute_index();
+        while let Some(attr) = usize::try_from(index).ok().and_then(|i| attrs.get(i)) {
+            result.push(index as usize);
+            index = match attr {
+                AttributesEntry::V3(v3) => v3.next_attribute_index,
+                // The attributes of a node are stored together
+                AttributesEntry::V2(v2) => match attrs.get(index as usize + 1) {
+                    Some(AttributesEntry::V2(next)) if next.node_index == v2.node_index => {
+                        index + 1
+                    }
+                    _ => -1,
+                },
+            };
+        }
+
+        result
+    }
+
+    /// Find the attribute of the node with the given name.
+    pub fn find_attribute(&self, node_index: usize, name: &str) -> Option<usize> {
+        self.node_attributes(node_index)
+            .into_iter()
+            .find(|&i| self.attribute_name(i) == Some(name))
+    }
+
+    /// Get the raw bytes of the attribute's value.
+    pub fn attribute_value(&self, attr_index: usize) -> Option<&[u8]> {
+        let attrs = &self.attributes.attrs.attrs;
+        let attr = attrs.get(attr_index)?;
+        let offset = match attr {
+            AttributesEntry::V3(v3) => v3.offset as usize,
+            // V2 values are stored sequentially in the order of the attributes
+            AttributesEntry::V2(_) => attrs[..attr_index]
+                .iter()
+                .map(|attr| attr.length() as usize)
+                .sum(),
+        };
+
+        self.values
+            .values
+            .data
+            .get(offset..offset + attr.length() as usize)
+    }
+
+    /// Get the attribute's value as a string, if it is one of the string types or a guid.
+    pub fn attribute_string(&self, attr_index: usize) -> Option<String> {
+        let attr = self.attributes.attrs.attrs.get(attr_index)?;
+        let value = self.attribute_value(attr_index)?;
+        match attr.type_id()? {
+            TypeId::String
+            | TypeId::Path
+            | TypeId::FixedString
+            | TypeId::LSString
+            | TypeId::WString
+            | TypeId::LSWString => {
+                // Strings are null terminated
+                let value = value.strip_suffix(&[0]).unwrap_or(value);
+                Some(String::from_utf8_lossy(value).into_owned())
+            }
+            TypeId::Guid => util::guid_string(value),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, BinRead)]
@@ -471,6 +570,46 @@ impl Debug for NodeEntryV3 {
     }
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct Values {
+    pub values: Values2,
+}
+impl BinRead for Values {
+    type Args<'a> = (LSFVersion, u32, u32, CompressionFlags);
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        (version, size_on_disk, uncompressed_size, compression_flags): Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        binread_compressed::<R, Values2, ()>(
+            reader,
+            version,
+            size_on_disk,
+            uncompressed_size,
+            compression_flags,
+            true,
+            (),
+        )
+        .map(|values| Values { values })
+    }
+}
+
+/// The raw buffer that attribute values are stored in.
+#[derive(Default, Clone, BinRead)]
+#[br(little)]
+pub struct Values2 {
+    #[br(parse_with = read_to_end)]
+    pub data: Vec<u8>,
+}
+impl Debug for Values2 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Values2")
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Attributes {
     pub attrs: Attributes2,
@@ -640,3 +779,111 @@ impl Debug for AttributeEntryV3 {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed version 4 file with a single `Config` region holding the attributes.  
+    /// `extended` writes the V3 node and attribute entries, which store the offset of each value,
+    /// rather than the V2 ones, whose values are stored in order.
+    fn lsf_bytes(extended: bool, attrs: &[(&str, TypeId, &[u8])]) -> Vec<u8> {
+        // Every name is in its own hash entry, the region name is first.
+        let mut names = Vec::new();
+        names.extend((1 + attrs.len() as u32).to_le_bytes());
+        for name in std::iter::once("Config").chain(attrs.iter().map(|(name, ..)| *name)) {
+            names.extend(1u16.to_le_bytes());
+            names.extend((name.len() as u16).to_le_bytes());
+            names.extend(name.as_bytes());
+        }
+
+        let first_attribute = if attrs.is_empty() { -1 } else { 0 };
+        let node: &[i32] = if extended {
+            // Name, parent, next sibling, first attribute
+            &[0, -1, -1, first_attribute]
+        } else {
+            // Name, first attribute, parent
+            &[0, first_attribute, -1]
+        };
+        let nodes: Vec<u8> = node.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        let mut attributes = Vec::new();
+        let mut values = Vec::new();
+        for (i, (_, type_id, value)) in attrs.iter().enumerate() {
+            attributes.extend((((i as u32) + 1) << 16).to_le_bytes());
+            attributes.extend((type_id.to_id() as u32 | ((value.len() as u32) << 6)).to_le_bytes());
+            if extended {
+                let next = if i + 1 == attrs.len() {
+                    -1
+                } else {
+                    i as i32 + 1
+                };
+                attributes.extend(next.to_le_bytes());
+                attributes.extend((values.len() as u32).to_le_bytes());
+            } else {
+                // Node index
+                attributes.extend(0i32.to_le_bytes());
+            }
+            values.extend(*value);
+        }
+
+        let mut data = b"LSOF".to_vec();
+        data.extend(4u32.to_le_bytes());
+        data.extend(0i32.to_le_bytes());
+        for section in [&names, &nodes, &attributes, &values] {
+            data.extend((section.len() as u32).to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+        }
+        // Compression flags, unknown fields, and has_sibling_data
+        data.extend([0, 0, 0, 0]);
+        data.extend(u32::from(extended).to_le_bytes());
+        for section in [names, nodes, attributes, values] {
+            data.extend(section);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_attributes() {
+        let guid: Vec<u8> = (0..16).collect();
+        let attrs: &[(&str, TypeId, &[u8])] = &[
+            ("Name", TypeId::LSString, b"Hello\0"),
+            ("Count", TypeId::Int32, &7i32.to_le_bytes()),
+            ("UUID", TypeId::Guid, &guid),
+            ("Folder", TypeId::FixedString, b"Mod\0"),
+        ];
+
+        for extended in [true, false] {
+            let lsf = parse_lsf(&lsf_bytes(extended, attrs)).unwrap();
+            assert_eq!(lsf.values.values.data.len(), 6 + 4 + 16 + 4);
+
+            let regions: Vec<usize> = lsf.regions().collect();
+            assert_eq!(regions, [0]);
+            assert_eq!(lsf.node_name(0), Some("Config"));
+            assert_eq!(lsf.node_attributes(0), [0, 1, 2, 3]);
+
+            let find = |name| lsf.find_attribute(0, name);
+            assert_eq!(
+                find("Name").and_then(|i| lsf.attribute_string(i)),
+                Some("Hello".to_string())
+            );
+            assert_eq!(
+                find("Count").and_then(|i| lsf.attribute_value(i)),
+                Some(&7i32.to_le_bytes()[..])
+            );
+            // Only strings and guids are read as strings
+            assert_eq!(find("Count").and_then(|i| lsf.attribute_string(i)), None);
+            assert_eq!(
+                find("UUID").and_then(|i| lsf.attribute_string(i)),
+                Some("03020100-0504-0706-0908-0b0a0d0c0f0e".to_string())
+            );
+            assert_eq!(
+                find("Folder").and_then(|i| lsf.attribute_string(i)),
+                Some("Mod".to_string())
+            );
+            assert_eq!(find("Missing"), None);
+            assert_eq!(lsf.find_attribute(1, "Name"), None);
+        }
+    }
+}
@@ -84,3 +84,53 @@ where
     .fuse()
     .collect()
 }
+
+/// Read the rest of the stream.
+pub(crate) fn read_to_end<R: Read + Seek>(
+    r: &mut R,
+    _e: binrw::Endian,
+    _args: (),
+) -> Result<Vec<u8>, binrw::Error> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Format a guid as it is stored in BG3 files. Returns `None` if it is not 16 bytes.  
+/// The first three groups are little endian as in a C# `Guid`, and BG3 additionally swaps each pair
+/// of bytes in the last eight.
+pub fn guid_string(data: &[u8]) -> Option<String> {
+    let data: &[u8; 16] = data.try_into().ok()?;
+    let mut tail = [0; 8];
+    tail.copy_from_slice(&data[8..]);
+    for pair in tail.chunks_exact_mut(2) {
+        pair.swap(0, 1);
+    }
+
+    let hex = |bytes: &[u8]| -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() };
+    Some(format!(
+        "{:08x}-{:04x}-{:04x}-{}-{}",
+        u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+        u16::from_le_bytes([data[4], data[5]]),
+        u16::from_le_bytes([data[6], data[7]]),
+        hex(&tail[..2]),
+        hex(&tail[2..]),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guid_string() {
+        let data: Vec<u8> = (0..16).collect();
+        // The first three groups are reversed, and the bytes of the last two are swapped in pairs
+        assert_eq!(
+            guid_string(&data).as_deref(),
+            Some("03020100-0504-0706-0908-0b0a0d0c0f0e")
+        );
+        assert_eq!(guid_string(&data[..15]), None);
+        assert_eq!(guid_string(&[]), None);
+    }
+}
@@ -0,0 +1,543 @@
+//! Snapshots of a profile's load order, taken before we write to it, so that it can be restored.
+//! Game patches also regularly reset `modsettings.lsx`, which loses the user's load order.
+//! Each snapshot is a folder in the backups folder holding a copy of `modsettings.lsx` and of
+//! `playerprofiles8.lsf`, which says which profile is active, and a `snapshot.json` with which
+//! profile it was for and the names and MD5s of the paks that were in the mods folder at the time.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    md5_cache::Md5Cache,
+    mod_data::DivinityModDependencyData,
+    mod_settings::{read_mod_settings, ModSettingsError, MOD_SETTINGS_FILE_NAME},
+    mods_folder::find_paks,
+    profiles::{read_active_profile_uuid, Profile, PLAYER_PROFILES_FILE_NAME},
+    settings::settings_dir,
+    util::time::{now_secs, timestamp_name},
+};
+
+pub const BACKUPS_FOLDER_NAME: &str = "Backups";
+const SNAPSHOT_FILE_NAME: &str = "snapshot.json";
+/// The number of snapshots kept, the oldest are removed past this.
+pub const MAX_SNAPSHOTS: usize = 50;
+
+#[derive(Debug)]
+pub enum BackupError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ModSettings(ModSettingsError),
+    /// There is no snapshot with the given id
+    NoSnapshot(String),
+}
+impl std::fmt::Display for BackupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupError::Io(err) => write!(f, "{err}"),
+            BackupError::Json(err) => write!(f, "Failed to parse {SNAPSHOT_FILE_NAME}: {err}"),
+            BackupError::ModSettings(err) => write!(f, "{err}"),
+            BackupError::NoSnapshot(id) => write!(f, "There is no backup named {id:?}"),
+        }
+    }
+}
+impl std::error::Error for BackupError {}
+impl From<std::io::Error> for BackupError {
+    fn from(e: std::io::Error) -> Self {
+        BackupError::Io(e)
+    }
+}
+impl From<serde_json::Error> for BackupError {
+    fn from(e: serde_json::Error) -> Self {
+        BackupError::Json(e)
+    }
+}
+impl From<ModSettingsError> for BackupError {
+    fn from(e: ModSettingsError) -> Self {
+        BackupError::ModSettings(e)
+    }
+}
+
+/// A pak in the mods folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PakHash {
+    /// The file name of the pak
+    pub name: String,
+    pub md5: String,
+}
+
+/// Hash the paks in the mods folder.
+pub fn hash_paks(mods_dir: &Path, md5_cache: &mut Md5Cache) -> std::io::Result<Vec<PakHash>> {
+    let mut paks = Vec::new();
+    for path in find_paks(mods_dir)? {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        paks.push(PakHash {
+            name,
+            md5: md5_cache.hash(&path)?.md5,
+        });
+    }
+
+    Ok(paks)
+}
+
+/// How the paks in the mods folder differ from a snapshot
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PakDiff {
+    /// Paks in the snapshot that are no longer in the mods folder
+    pub missing: Vec<String>,
+    /// Paks that have a different MD5 than in the snapshot
+    pub changed: Vec<String>,
+    /// Paks that were not in the mods folder when the snapshot was taken
+    pub added: Vec<String>,
+}
+impl PakDiff {
+    pub fn compare(saved: &[PakHash], current: &[PakHash]) -> PakDiff {
+        let find = |paks: &[PakHash], name: &str| {
+            paks.iter()
+                .find(|pak| pak.name.eq_ignore_ascii_case(name))
+                .map(|pak| pak.md5.clone())
+        };
+
+        let mut diff = PakDiff::default();
+        for pak in saved {
+            match find(current, &pak.name) {
+                None => diff.missing.push(pak.name.clone()),
+                Some(md5) if !md5.eq_ignore_ascii_case(&pak.md5) => {
+                    diff.changed.push(pak.name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for pak in current {
+            if find(saved, &pak.name).is_none() {
+                diff.added.push(pak.name.clone());
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.changed.is_empty() && self.added.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct SnapshotInfo {
+    /// Seconds since the unix epoch
+    pub created: u64,
+    pub profile_name: String,
+    pub profile_uuid: String,
+    /// Whether the profile had a `modsettings.lsx`. If not, restoring the snapshot removes it.
+    pub has_mod_settings: bool,
+    /// The uuid of the profile that the game had active, empty if it couldn't be read
+    pub active_profile_uuid: String,
+    /// Whether `playerprofiles8.lsf` was backed up. If so, restoring the snapshot also restores
+    /// which profile is active.
+    pub has_player_profiles: bool,
+    pub paks: Vec<PakHash>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The name of the snapshot's folder
+    pub id: String,
+    pub folder: PathBuf,
+    pub info: SnapshotInfo,
+}
+impl Snapshot {
+    fn read(folder: PathBuf) -> Result<Snapshot, BackupError> {
+        let text = std::fs::read_to_string(folder.join(SNAPSHOT_FILE_NAME))?;
+        let info = serde_json::from_str(&text)?;
+        let id = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Snapshot { id, folder, info })
+    }
+
+    pub fn mod_settings_path(&self) -> PathBuf {
+        self.folder.join(MOD_SETTINGS_FILE_NAME)
+    }
+
+    pub fn player_profiles_path(&self) -> PathBuf {
+        self.folder.join(PLAYER_PROFILES_FILE_NAME)
+    }
+
+    /// The load order in the snapshot, which is empty if the profile had no `modsettings.lsx`
+    pub fn read_load_order(&self) -> Result<Vec<DivinityModDependencyData>, BackupError> {
+        if !self.info.has_mod_settings {
+            return Ok(Vec::new());
+        }
+
+        Ok(read_mod_settings(&self.mod_settings_path())?)
+    }
+
+    /// Whether the snapshot was taken of the profile
+    pub fn is_for(&self, profile: &Profile) -> bool {
+        if !self.info.profile_uuid.is_empty() && !profile.uuid.is_empty() {
+            self.info.profile_uuid.eq_ignore_ascii_case(&profile.uuid)
+        } else {
+            self.info.profile_name == profile.name
+        }
+    }
+}
+
+/// What changed when restoring a snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// The snapshot of the load order from before it was restored
+    pub previous: Snapshot,
+    /// How the mods folder differs from when the restored snapshot was taken. Mods in the restored
+    /// load order that are missing need to be reinstalled.
+    pub paks: PakDiff,
+}
+
+/// The folder holding the profile folders, which has `playerprofiles8.lsf`
+fn profiles_dir(profile: &Profile) -> &Path {
+    profile.folder.parent().unwrap_or(&profile.folder)
+}
+
+/// Copy `src` next to `dest`, to be moved over it with [`Staged::commit`]. Copying first means
+/// that `dest` is never left partially written, and that `src` can be removed in between.
+struct Staged {
+    temp: PathBuf,
+    dest: PathBuf,
+}
+impl Staged {
+    fn copy(src: &Path, dest: PathBuf) -> std::io::Result<Staged> {
+        let file_name = dest
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let temp = dest.with_file_name(format!(".{file_name}.restoring"));
+        if let Err(err) = std::fs::copy(src, &temp) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(err);
+        }
+
+        Ok(Staged { temp, dest })
+    }
+
+    fn commit(self) -> std::io::Result<()> {
+        let result = std::fs::rename(&self.temp, &self.dest);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+
+        result
+    }
+
+    fn discard(self) {
+        let _ = std::fs::remove_file(&self.temp);
+    }
+}
+
+/// Copy the snapshot's files next to where they are restored to.
+fn stage_restore(snapshot: &Snapshot, profile: &Profile) -> std::io::Result<Vec<Staged>> {
+    let mut files = Vec::new();
+    if snapshot.info.has_mod_settings {
+        files.push((snapshot.mod_settings_path(), profile.mod_settings_path()));
+    }
+    if snapshot.info.has_player_profiles {
+        files.push((
+            snapshot.player_profiles_path(),
+            profiles_dir(profile).join(PLAYER_PROFILES_FILE_NAME),
+        ));
+    }
+
+    let mut staged = Vec::new();
+    for (src, dest) in files {
+        match Staged::copy(&src, dest) {
+            Ok(file) => staged.push(file),
+            Err(err) => {
+                staged.into_iter().for_each(Staged::discard);
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(staged)
+}
+
+/// The folder of load order snapshots.
+#[derive(Debug, Clone)]
+pub struct Backups {
+    pub dir: PathBuf,
+}
+impl Backups {
+    /// The backups folder in the settings directory
+    pub fn new() -> Backups {
+        Backups::new_in(settings_dir().join(BACKUPS_FOLDER_NAME))
+    }
+
+    pub fn new_in(dir: PathBuf) -> Backups {
+        Backups { dir }
+    }
+
+    /// Take a snapshot of the profile's load order. `paks` are the paks currently in the mods
+    /// folder, see [`hash_paks`].
+    /// The oldest snapshots past [`MAX_SNAPSHOTS`] are removed.
+    pub fn snapshot(&self, profile: &Profile, paks: Vec<PakHash>) -> Result<Snapshot, BackupError> {
+        let created = now_secs();
+        let name = timestamp_name(created);
+        let mut folder = self.dir.join(&name);
+        let mut n = 1;
+        while folder.exists() {
+            n += 1;
+            folder = self.dir.join(format!("{name}_{n}"));
+        }
+        std::fs::create_dir_all(&folder)?;
+
+        let mod_settings = profile.mod_settings_path();
+        let has_mod_settings = mod_settings.is_file();
+        if has_mod_settings {
+            std::fs::copy(&mod_settings, folder.join(MOD_SETTINGS_FILE_NAME))?;
+        }
+
+        let profiles_dir = profiles_dir(profile);
+        let player_profiles = profiles_dir.join(PLAYER_PROFILES_FILE_NAME);
+        let has_player_profiles = player_profiles.is_file();
+        if has_player_profiles {
+            std::fs::copy(&player_profiles, folder.join(PLAYER_PROFILES_FILE_NAME))?;
+        }
+        let active_profile_uuid = if has_player_profiles {
+            read_active_profile_uuid(profiles_dir)
+                .map_err(|err| eprintln!("Failed to read the active profile: {err}"))
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let info = SnapshotInfo {
+            created,
+            profile_name: profile.name.clone(),
+            profile_uuid: profile.uuid.clone(),
+            has_mod_settings,
+            active_profile_uuid,
+            has_player_profiles,
+            paks,
+        };
+        // Written last, so that a snapshot that failed part way isn't listed
+        std::fs::write(
+            folder.join(SNAPSHOT_FILE_NAME),
+            serde_json::to_string_pretty(&info)?,
+        )?;
+        eprintln!("Backed up load order of {} to {folder:?}", profile.title());
+
+        self.prune(MAX_SNAPSHOTS)?;
+
+        Ok(Snapshot {
+            id: folder
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            folder,
+            info,
+        })
+    }
+
+    /// The snapshots, newest first. Snapshots that can't be read are skipped.
+    pub fn list(&self) -> Result<Vec<Snapshot>, BackupError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            match Snapshot::read(entry.path()) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(err) => eprintln!("Skipping backup at {:?}: {err}", entry.path()),
+            }
+        }
+
+        snapshots.sort_by(|a, b| {
+            b.info
+                .created
+                .cmp(&a.info.created)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+
+        Ok(snapshots)
+    }
+
+    pub fn find(&self, id: &str) -> Result<Snapshot, BackupError> {
+        let folder = self.dir.join(id);
+        // Don't let the id point outside the backups folder
+        if Path::new(id).components().count() != 1 || !folder.is_dir() {
+            return Err(BackupError::NoSnapshot(id.to_string()));
+        }
+
+        Snapshot::read(folder)
+    }
+
+    /// Remove the oldest snapshots so that there are at most `keep`.
+    pub fn prune(&self, keep: usize) -> Result<(), BackupError> {
+        for snapshot in self.list()?.into_iter().skip(keep) {
+            std::fs::remove_dir_all(&snapshot.folder)?;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the snapshot's load order and active profile to the profile. The current load
+    /// order is snapshotted first, so the restore can be undone.
+    /// The restored files are moved into place, so the profile is never left with a partially
+    /// written file.
+    pub fn restore(
+        &self,
+        snapshot: &Snapshot,
+        profile: &Profile,
+        current_paks: Vec<PakHash>,
+    ) -> Result<RestoreReport, BackupError> {
+        let paks = PakDiff::compare(&snapshot.info.paks, &current_paks);
+
+        // Copied out before snapshotting the current state, which may prune the snapshot being
+        // restored if it is the oldest
+        let staged = stage_restore(snapshot, profile)?;
+        let previous = match self.snapshot(profile, current_paks) {
+            Ok(previous) => previous,
+            Err(err) => {
+                staged.into_iter().for_each(Staged::discard);
+                return Err(err);
+            }
+        };
+
+        let dest = profile.mod_settings_path();
+        if !snapshot.info.has_mod_settings && dest.exists() {
+            std::fs::remove_file(&dest)?;
+        }
+        for staged in staged {
+            staged.commit()?;
+        }
+        eprintln!(
+            "Restored load order of {} from backup {}",
+            profile.title(),
+            snapshot.id
+        );
+
+        Ok(RestoreReport { previous, paks })
+    }
+}
+impl Default for Backups {
+    fn default() -> Self {
+        Backups::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_profile(profile_dir: PathBuf) -> Profile {
+        Profile {
+            name: "Public".to_string(),
+            display_name: String::new(),
+            uuid: "uuid-profile".to_string(),
+            folder: profile_dir,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let profile_dir = root.join("PlayerProfiles").join("Public");
+        let mods_dir = root.join("Mods");
+        std::fs::create_dir_all(&profile_dir).unwrap();
+        std::fs::create_dir_all(&mods_dir).unwrap();
+        std::fs::write(mods_dir.join("A.pak"), "a").unwrap();
+        std::fs::write(mods_dir.join("B.pak"), "b").unwrap();
+
+        let profile = test_profile(profile_dir);
+        let player_profiles = root.join("PlayerProfiles").join(PLAYER_PROFILES_FILE_NAME);
+        std::fs::write(profile.mod_settings_path(), "first").unwrap();
+        std::fs::write(&player_profiles, "first profiles").unwrap();
+
+        let backups = Backups::new_in(root.join(BACKUPS_FOLDER_NAME));
+        let mut md5_cache = Md5Cache::load_from(root.join("md5_cache.json"));
+        let paks = hash_paks(&mods_dir, &mut md5_cache).unwrap();
+        let first = backups.snapshot(&profile, paks).unwrap();
+        assert!(first.info.has_player_profiles);
+
+        std::fs::write(profile.mod_settings_path(), "second").unwrap();
+        std::fs::write(&player_profiles, "second profiles").unwrap();
+        std::fs::write(mods_dir.join("B.pak"), "changed").unwrap();
+        std::fs::remove_file(mods_dir.join("A.pak")).unwrap();
+
+        let paks = hash_paks(&mods_dir, &mut md5_cache).unwrap();
+        let found = backups.find(&first.id).unwrap();
+        let report = backups.restore(&found, &profile, paks).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(profile.mod_settings_path()).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&player_profiles).unwrap(),
+            "first profiles"
+        );
+        assert_eq!(
+            std::fs::read_to_string(report.previous.mod_settings_path()).unwrap(),
+            "second"
+        );
+        assert_eq!(
+            std::fs::read_to_string(report.previous.player_profiles_path()).unwrap(),
+            "second profiles"
+        );
+        assert!(backups.find("../Public").is_err());
+
+        let listed = backups.list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|snapshot| snapshot.is_for(&profile)));
+        assert_eq!(report.paks.missing, vec!["A.pak".to_string()]);
+        assert_eq!(report.paks.changed, vec!["B.pak".to_string()]);
+        assert!(report.paks.added.is_empty());
+    }
+
+    #[test]
+    fn test_restore_oldest() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let profile = test_profile(root.join("PlayerProfiles").join("Public"));
+        std::fs::create_dir_all(&profile.folder).unwrap();
+        let backups = Backups::new_in(root.join(BACKUPS_FOLDER_NAME));
+
+        std::fs::write(profile.mod_settings_path(), "oldest").unwrap();
+        backups.snapshot(&profile, Vec::new()).unwrap();
+        std::fs::write(profile.mod_settings_path(), "newer").unwrap();
+        for _ in 1..MAX_SNAPSHOTS {
+            backups.snapshot(&profile, Vec::new()).unwrap();
+        }
+
+        let listed = backups.list().unwrap();
+        assert_eq!(listed.len(), MAX_SNAPSHOTS);
+        let oldest = listed.last().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(oldest.mod_settings_path()).unwrap(),
+            "oldest"
+        );
+
+        // Snapshotting the current order prunes the snapshot that is being restored
+        backups.restore(oldest, &profile, Vec::new()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(profile.mod_settings_path()).unwrap(),
+            "oldest"
+        );
+        assert_eq!(backups.list().unwrap().len(), MAX_SNAPSHOTS);
+        assert!(!oldest.folder.exists());
+    }
+}
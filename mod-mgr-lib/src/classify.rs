@@ -0,0 +1,260 @@
+//! Working out what kind of mod a pak is from its contents.
+//! Most mods have a `meta.lsx` and are ordered in the load order. However, there are also
+//! 'override' mods which replace files within the base game's folders (`Gustav`, `GustavDev`,
+//! etc). Those without a `meta.lsx` are always loaded by the game and so can't be ordered.
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use ls::pak::{
+    common::{FileInfo, FileInfoLike},
+    read_package,
+};
+use serde::Serialize;
+
+use crate::{
+    conflicts::{is_base_game_path, BASE_GAME_FOLDERS},
+    extender::{read_extender_config, ScriptExtenderConfig},
+    meta::{file_modified_time, find_meta_file, is_meta_file, parse_meta_lsx, MetaError, ModMeta},
+    mod_data::ModData,
+    MAIN_CAMPAIGN_UUID,
+};
+
+/// Authors used by the game's own modules
+const LARIAN_AUTHORS: &[&str] = &["Larian Studios", "LS"];
+/// Header versions before this are from before the game's release, which the game can no longer
+/// load.
+const RELEASE_HEADER_MAJOR: u8 = 4;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ModClassification {
+    /// Whether the pak has a `Mods/<Folder>/meta.lsx`
+    pub has_meta: bool,
+    pub is_force_loaded: bool,
+    pub is_force_loaded_merged_mod: bool,
+    pub is_classic_mod: bool,
+    pub is_larian_mod: bool,
+    /// The base game folders that the mod has files in, like `Gustav`
+    pub overridden_folders: Vec<String>,
+}
+impl ModClassification {
+    /// The text describing which base game folders the mod overrides. Empty if it doesn't.
+    pub fn override_text(&self) -> String {
+        if self.overridden_folders.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "Overrides base game files in: {}",
+                self.overridden_folders.join(", ")
+            )
+        }
+    }
+
+    pub fn apply(&self, data: &mut ModData) {
+        data.is_force_loaded = self.is_force_loaded;
+        data.is_force_loaded_merged_mod = self.is_force_loaded_merged_mod;
+        data.is_classic_mod = self.is_classic_mod;
+        data.is_larian_mod |= self.is_larian_mod;
+        data.builtin_override_mods_text = self.override_text();
+    }
+}
+
+fn is_base_game_folder(folder: &str) -> bool {
+    BASE_GAME_FOLDERS
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(folder))
+}
+
+/// Classify a mod.
+/// `data` is the information from the mod's `meta.lsx`, or some placeholder if `has_meta` is false.
+/// `files` are the paths of the files within the pak.
+pub fn classify(data: &ModData, has_meta: bool, files: &[String]) -> ModClassification {
+    let mut overridden_folders: Vec<String> = Vec::new();
+    let mut has_own_files = false;
+
+    for path in files {
+        if is_meta_file(path) {
+            continue;
+        }
+
+        if is_base_game_path(path) {
+            // `Public/Gustav/...` -> `Gustav`
+            if let Some(folder) = path.split('/').nth(1) {
+                if !overridden_folders
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(folder))
+                {
+                    overridden_folders.push(folder.to_string());
+                }
+            }
+        } else {
+            has_own_files = true;
+        }
+    }
+    overridden_folders.sort();
+
+    // A mod whose own folder is a base game folder is replacing that module entirely.
+    let replaces_base_module = has_meta && is_base_game_folder(&data.folder);
+
+    let is_force_loaded = !has_meta || replaces_base_module || !overridden_folders.is_empty();
+    // Mods that override base game files, but which also have their own files, can still be
+    // placed in the load order.
+    let is_force_loaded_merged_mod =
+        is_force_loaded && has_meta && !replaces_base_module && has_own_files;
+
+    let is_classic_mod = has_meta
+        && data.header_version != Default::default()
+        && data.header_version.major < RELEASE_HEADER_MAJOR;
+
+    let is_larian_mod = data.uuid == MAIN_CAMPAIGN_UUID
+        || LARIAN_AUTHORS
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(data.author.trim()));
+
+    ModClassification {
+        has_meta,
+        is_force_loaded,
+        is_force_loaded_merged_mod,
+        is_classic_mod,
+        is_larian_mod,
+        overridden_folders,
+    }
+}
+
+/// The information for a pak without a `meta.lsx`. It is identified by its file name.
+pub fn override_mod_data(path: &Path) -> ModData {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    ModData {
+        uuid: stem.clone(),
+        name: stem.clone(),
+        folder: stem,
+        display_file_for_name: true,
+        ..Default::default()
+    }
+}
+
+/// A mod read from a pak, with its classification already applied to the data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PakMod {
+    pub meta: ModMeta,
+    pub classification: ModClassification,
+    /// The mod's `ScriptExtender/Config.json`, if it has one
+    pub extender: Option<ScriptExtenderConfig>,
+    /// The paths of the files in the pak
+    pub files: Vec<String>,
+}
+
+/// Read a pak, using its `meta.lsx` if it has one, and classify it.
+pub fn read_pak_mod(path: &Path) -> Result<PakMod, MetaError> {
+    let file = BufReader::new(File::open(path)?);
+    let package = read_package(file, path, false)?;
+
+    let files: Vec<String> = package
+        .files
+        .iter()
+        .filter(|file| !file.is_deletion())
+        .map(|file: &FileInfo| file.name().to_string())
+        .collect();
+
+    let mut meta = match find_meta_file(&package) {
+        Some(meta_file) => {
+            let content = package.read_file(meta_file)?;
+            parse_meta_lsx(&String::from_utf8_lossy(&content))?
+        }
+        None => ModMeta {
+            data: override_mod_data(path),
+            dependencies: Vec::new(),
+        },
+    };
+
+    let extender = match read_extender_config(&package) {
+        Ok(extender) => extender,
+        Err(err) => {
+            eprintln!("Ignoring Script Extender config in {path:?}: {err}");
+            None
+        }
+    };
+
+    let classification = classify(&meta.data, find_meta_file(&package).is_some(), &files);
+    classification.apply(&mut meta.data);
+
+    meta.data.file_path = path.to_owned();
+    meta.data.last_modified = file_modified_time(path);
+    meta.data.is_user_mod = true;
+
+    Ok(PakMod {
+        meta,
+        classification,
+        extender,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mod_data::ModVersion;
+
+    fn files(files: &[&str]) -> Vec<String> {
+        files.iter().map(|f| f.to_string()).collect()
+    }
+
+    fn with_header(mut data: ModData, major: u8) -> ModData {
+        data.header_version = ModVersion {
+            major,
+            ..Default::default()
+        };
+        data
+    }
+
+    #[test]
+    fn test_classify() {
+        let normal = with_header(
+            ModData::new("A", "uuid-a", "A", 0, "Add-on", "", "Someone", "", ""),
+            4,
+        );
+        let class = classify(
+            &normal,
+            true,
+            &files(&["Mods/A/meta.lsx", "Public/A/Stats/Generated/Data/Spell.txt"]),
+        );
+        assert!(!class.is_force_loaded);
+        assert!(!class.is_classic_mod);
+        assert!(!class.is_larian_mod);
+        assert_eq!(class.override_text(), "");
+
+        let class = classify(
+            &normal,
+            true,
+            &files(&[
+                "Mods/A/meta.lsx",
+                "Public/A/Stats/Generated/Data/Spell.txt",
+                "Public/Gustav/Stats/Generated/Data/Armor.txt",
+                "Public/Shared/Stats/Generated/Data/Armor.txt",
+            ]),
+        );
+        assert!(class.is_force_loaded);
+        assert!(class.is_force_loaded_merged_mod);
+        assert_eq!(class.overridden_folders, vec!["Gustav", "Shared"]);
+        assert_eq!(
+            class.override_text(),
+            "Overrides base game files in: Gustav, Shared"
+        );
+
+        let override_only = override_mod_data(Path::new("Mods/BetterHair.pak"));
+        assert_eq!(override_only.name, "BetterHair");
+        let class = classify(
+            &override_only,
+            false,
+            &files(&["Public/GustavDev/Assets/Hair.gr2"]),
+        );
+        assert!(class.is_force_loaded);
+        assert!(!class.is_force_loaded_merged_mod);
+
+        let classic = with_header(normal.clone(), 3);
+        assert!(classify(&classic, true, &[]).is_classic_mod);
+    }
+}
@@ -0,0 +1,358 @@
+//! Finding files that are provided by more than one mod.
+//! The game loads mods in load order, and when two mods have a file at the same path the one
+//! loaded last is used. This silently breaks the earlier mod, so we report them.
+
+use std::collections::HashMap;
+
+use ls::{
+    mod_resources::{ModPathVisitor, ModResources},
+    pak::{
+        common::{FileInfo, FileInfoLike},
+        PackageError,
+    },
+};
+
+use crate::{meta::is_meta_file, mod_data::ModData};
+
+/// Module folders that belong to the base game. Files within these override the game's own data.
+pub const BASE_GAME_FOLDERS: &[&str] = &["Gustav", "GustavDev", "Shared", "SharedDev", "Engine"];
+
+/// Whether the path within a pak is in one of the base game's module folders, like
+/// `Public/Gustav/...` or `Mods/GustavDev/...`.
+pub fn is_base_game_path(path: &str) -> bool {
+    let mut parts = path.split('/');
+    let (Some(root), Some(folder)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+
+    ["Public", "Mods", "Generated"]
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(root))
+        && BASE_GAME_FOLDERS
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(folder))
+}
+
+/// The files within a mod's pak.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModFiles {
+    pub uuid: String,
+    pub name: String,
+    pub is_force_loaded: bool,
+    pub is_force_loaded_merged_mod: bool,
+    pub files: Vec<String>,
+}
+impl ModFiles {
+    pub fn new(mod_data: &ModData, files: Vec<String>) -> ModFiles {
+        ModFiles {
+            uuid: mod_data.uuid.clone(),
+            name: mod_data.display_name(),
+            is_force_loaded: mod_data.is_force_loaded,
+            is_force_loaded_merged_mod: mod_data.is_force_loaded_merged_mod,
+            files,
+        }
+    }
+}
+
+/// Read the file lists of the mods' paks.
+/// Mods whose paks couldn't be read are returned separately with the error.
+pub fn read_mod_files<'a>(
+    mods: impl IntoIterator<Item = &'a ModData>,
+) -> (Vec<ModFiles>, Vec<(&'a ModData, PackageError)>) {
+    let mut visitor = ModPathVisitor::new(ModResources::default());
+    let mut mod_files = Vec::new();
+    let mut errors = Vec::new();
+
+    for mod_data in mods {
+        if let Err(err) = visitor.discover_package(&mod_data.file_path) {
+            errors.push((mod_data, err));
+            continue;
+        }
+
+        // The package we just discovered is always the last one
+        let package = visitor.resources().loaded_packages.last();
+        let files = package
+            .into_iter()
+            .flat_map(|package| package.files.iter())
+            .filter(|file| !file.is_deletion())
+            .map(|file: &FileInfo| file.name().to_string())
+            .collect();
+
+        mod_files.push(ModFiles::new(mod_data, files));
+    }
+
+    (mod_files, errors)
+}
+
+/// A path that is provided by more than one mod.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileConflict {
+    pub path: String,
+    /// Indices into [`ConflictReport::mods`] of the mods providing the file, in load order.
+    pub providers: Vec<usize>,
+    /// Whether the file is in one of the base game's folders, and so also overrides the game's
+    /// own version of it.
+    pub overrides_base_game: bool,
+}
+impl FileConflict {
+    /// The mod whose version of the file is used
+    pub fn winner(&self) -> usize {
+        *self.providers.last().unwrap()
+    }
+
+    /// The mods whose version of the file is ignored
+    pub fn losers(&self) -> &[usize] {
+        &self.providers[..self.providers.len() - 1]
+    }
+}
+
+/// Information about a mod involved in the conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictMod {
+    pub uuid: String,
+    pub name: String,
+    pub is_force_loaded: bool,
+    pub is_force_loaded_merged_mod: bool,
+    /// The files the mod has in base game folders, whether or not another mod also has them.
+    pub base_game_overrides: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport {
+    /// The mods in load order
+    pub mods: Vec<ConflictMod>,
+    /// Sorted by path
+    pub conflicts: Vec<FileConflict>,
+}
+impl ConflictReport {
+    /// Find the conflicts between the mods.
+    /// `mods` should be in the order they are loaded, the last mod to provide a file is the one
+    /// that the game uses.
+    pub fn analyze(mods: &[ModFiles]) -> ConflictReport {
+        // Paths are case insensitive, so we key on the lowercase path but report the path as the
+        // first mod wrote it.
+        let mut providers: HashMap<String, (String, Vec<usize>)> = HashMap::new();
+        let mut report_mods = Vec::with_capacity(mods.len());
+
+        for (i, mod_files) in mods.iter().enumerate() {
+            let mut base_game_overrides = Vec::new();
+            for path in &mod_files.files {
+                // Every mod has its own meta file
+                if is_meta_file(path) {
+                    continue;
+                }
+
+                if is_base_game_path(path) {
+                    base_game_overrides.push(path.clone());
+                }
+
+                let (_, entry) = providers
+                    .entry(path.to_lowercase())
+                    .or_insert_with(|| (path.clone(), Vec::new()));
+                // A multi-part pak could list a file twice
+                if entry.last() != Some(&i) {
+                    entry.push(i);
+                }
+            }
+
+            report_mods.push(ConflictMod {
+                uuid: mod_files.uuid.clone(),
+                name: mod_files.name.clone(),
+                is_force_loaded: mod_files.is_force_loaded,
+                is_force_loaded_merged_mod: mod_files.is_force_loaded_merged_mod,
+                base_game_overrides,
+            });
+        }
+
+        let mut conflicts: Vec<FileConflict> = providers
+            .into_values()
+            .filter(|(_, providers)| providers.len() > 1)
+            .map(|(path, providers)| FileConflict {
+                overrides_base_game: is_base_game_path(&path),
+                path,
+                providers,
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+
+        ConflictReport {
+            mods: report_mods,
+            conflicts,
+        }
+    }
+
+    pub fn mod_index(&self, uuid: &str) -> Option<usize> {
+        self.mods
+            .iter()
+            .position(|m| m.uuid.eq_ignore_ascii_case(uuid))
+    }
+
+    pub fn summary(&self) -> ConflictSummary {
+        let mut mods: Vec<ModConflictSummary> = self
+            .mods
+            .iter()
+            .map(|m| ModConflictSummary {
+                uuid: m.uuid.clone(),
+                name: m.name.clone(),
+                wins: 0,
+                losses: 0,
+                base_game_overrides: m.base_game_overrides.len(),
+            })
+            .collect();
+
+        for conflict in &self.conflicts {
+            mods[conflict.winner()].wins += 1;
+            for &loser in conflict.losers() {
+                mods[loser].losses += 1;
+            }
+        }
+
+        ConflictSummary {
+            conflicts: self.conflicts.len(),
+            base_game_conflicts: self
+                .conflicts
+                .iter()
+                .filter(|c| c.overrides_base_game)
+                .count(),
+            mods,
+        }
+    }
+
+    /// Get the conflicts that the mod is involved in.
+    pub fn mod_details(&self, uuid: &str) -> Option<ModConflictDetails> {
+        let idx = self.mod_index(uuid)?;
+        let m = &self.mods[idx];
+
+        let mut details = ModConflictDetails {
+            uuid: m.uuid.clone(),
+            name: m.name.clone(),
+            overrides: Vec::new(),
+            overridden_by: Vec::new(),
+            base_game_overrides: m.base_game_overrides.clone(),
+        };
+
+        for conflict in &self.conflicts {
+            if !conflict.providers.contains(&idx) {
+                continue;
+            }
+
+            if conflict.winner() == idx {
+                details.overrides.push(ConflictingFile {
+                    path: conflict.path.clone(),
+                    mods: conflict
+                        .losers()
+                        .iter()
+                        .map(|&i| self.mods[i].uuid.clone())
+                        .collect(),
+                });
+            } else {
+                details.overridden_by.push(ConflictingFile {
+                    path: conflict.path.clone(),
+                    mods: vec![self.mods[conflict.winner()].uuid.clone()],
+                });
+            }
+        }
+
+        Some(details)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictSummary {
+    /// Number of paths provided by more than one mod
+    pub conflicts: usize,
+    /// Number of those that are in base game folders
+    pub base_game_conflicts: usize,
+    /// In load order
+    pub mods: Vec<ModConflictSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflictSummary {
+    pub uuid: String,
+    pub name: String,
+    /// Number of conflicting files where this mod's version is used
+    pub wins: usize,
+    /// Number of conflicting files where another mod's version is used
+    pub losses: usize,
+    pub base_game_overrides: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingFile {
+    pub path: String,
+    /// UUIDs of the other mods
+    pub mods: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflictDetails {
+    pub uuid: String,
+    pub name: String,
+    /// Files where this mod's version is used over the other mods'
+    pub overrides: Vec<ConflictingFile>,
+    /// Files where another mod's version is used over this mod's
+    pub overridden_by: Vec<ConflictingFile>,
+    /// Files in base game folders
+    pub base_game_overrides: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mod_files(uuid: &str, files: &[&str]) -> ModFiles {
+        ModFiles {
+            uuid: uuid.to_string(),
+            name: uuid.to_string(),
+            is_force_loaded: false,
+            is_force_loaded_merged_mod: false,
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mods = [
+            mod_files(
+                "a",
+                &[
+                    "Mods/A/meta.lsx",
+                    "Public/A/Stats/Generated/Data/Spell.txt",
+                    "Public/Gustav/Stats/Generated/Data/Armor.txt",
+                ],
+            ),
+            mod_files(
+                "b",
+                &[
+                    "Mods/B/meta.lsx",
+                    "public/gustav/Stats/Generated/Data/Armor.txt",
+                    "Public/A/Stats/Generated/Data/Spell.txt",
+                ],
+            ),
+            mod_files("c", &["Public/C/Other.txt"]),
+        ];
+
+        let report = ConflictReport::analyze(&mods);
+        assert_eq!(report.conflicts.len(), 2);
+
+        let armor = &report.conflicts[1];
+        assert_eq!(armor.path, "Public/Gustav/Stats/Generated/Data/Armor.txt");
+        assert!(armor.overrides_base_game);
+        assert_eq!(armor.winner(), 1);
+        assert_eq!(armor.losers(), &[0]);
+
+        let summary = report.summary();
+        assert_eq!(summary.conflicts, 2);
+        assert_eq!(summary.base_game_conflicts, 1);
+        assert_eq!(summary.mods[0].losses, 2);
+        assert_eq!(summary.mods[1].wins, 2);
+        assert_eq!(summary.mods[2].wins + summary.mods[2].losses, 0);
+
+        let details = report.mod_details("a").unwrap();
+        assert!(details.overrides.is_empty());
+        assert_eq!(details.overridden_by.len(), 2);
+        assert_eq!(details.overridden_by[0].mods, vec!["b".to_string()]);
+        assert_eq!(details.base_game_overrides.len(), 1);
+    }
+}
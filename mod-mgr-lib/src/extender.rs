@@ -0,0 +1,307 @@
+//! Norbyte's Script Extender, which many mods require.
+//! Mods declare what they need from it in `Mods/<Folder>/ScriptExtender/Config.json`, and the
+//! extender is installed by placing its updater, `DWrite.dll`, in the game's `bin` folder.
+
+use std::{borrow::Cow, path::Path};
+
+use ls::pak::{
+    common::{FileInfo, PackagedFileInfo},
+    Package,
+};
+use serde::Deserialize;
+
+use crate::{meta::MetaError, EXTENDER_MOD_CONFIG, EXTENDER_UPDATER_FILE};
+
+/// The feature flag for mods that use Lua scripting, which requires the extender
+pub const LUA_FEATURE_FLAG: &str = "Lua";
+
+/// A mod's `ScriptExtender/Config.json`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ScriptExtenderConfig {
+    #[serde(rename = "RequiredVersion", alias = "RequiredExtensionVersion")]
+    pub required_extension_version: Option<u32>,
+    /// The name of the mod's table in the Lua `Mods` global
+    pub mod_table: Option<String>,
+    pub feature_flags: Vec<String>,
+}
+impl ScriptExtenderConfig {
+    pub fn parse(text: &str) -> Result<ScriptExtenderConfig, serde_json::Error> {
+        serde_json::from_str(text.trim_start_matches('\u{feff}'))
+    }
+
+    pub fn has_any_settings(&self) -> bool {
+        self.required_extension_version.is_some()
+            || self.mod_table.is_some()
+            || !self.feature_flags.is_empty()
+    }
+
+    pub fn has_feature_flag(&self, flag: &str) -> bool {
+        self.feature_flags
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(flag))
+    }
+
+    /// Whether the mod can't work without the extender, rather than just having optional support
+    /// for it.
+    pub fn requires_extender(&self) -> bool {
+        self.required_extension_version.is_some() || self.has_feature_flag(LUA_FEATURE_FLAG)
+    }
+}
+
+/// Whether the path within a pak is a mod's extender config, `Mods/<Folder>/ScriptExtender/Config.json`
+pub fn is_extender_config_file(name: &str) -> bool {
+    let mut parts = name.split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some(mods), Some(folder), Some(se), Some(config), None)
+            if mods.eq_ignore_ascii_case("Mods")
+                && !folder.is_empty()
+                && se.eq_ignore_ascii_case("ScriptExtender")
+                && config.eq_ignore_ascii_case(EXTENDER_MOD_CONFIG)
+    )
+}
+
+/// Find the extender config within the package.
+pub fn find_extender_config_file(package: &Package) -> Option<&PackagedFileInfo> {
+    package.files.iter().find_map(|file| match file {
+        FileInfo::Packaged(info) if is_extender_config_file(&info.name) => Some(info),
+        _ => None,
+    })
+}
+
+/// Read the extender config from the package, if it has one.
+pub fn read_extender_config(package: &Package) -> Result<Option<ScriptExtenderConfig>, MetaError> {
+    let Some(config_file) = find_extender_config_file(package) else {
+        return Ok(None);
+    };
+
+    let content = package.read_file(config_file)?;
+    Ok(Some(ScriptExtenderConfig::parse(
+        &String::from_utf8_lossy(&content),
+    )?))
+}
+
+/// The file version from a dll's version resource.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DllVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    pub private: u16,
+}
+impl std::fmt::Display for DllVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.private
+        )
+    }
+}
+
+/// Signature of the `VS_FIXEDFILEINFO` structure in a version resource
+const FIXED_FILE_INFO_SIGNATURE: [u8; 4] = 0xFEEF04BDu32.to_le_bytes();
+
+/// Read the file version from the contents of a dll.
+/// Rather than walking the PE resource tree, this finds the `VS_FIXEDFILEINFO` by its signature.
+pub fn dll_file_version(data: &[u8]) -> Option<DllVersion> {
+    let start = data
+        .windows(FIXED_FILE_INFO_SIGNATURE.len())
+        .position(|w| w == FIXED_FILE_INFO_SIGNATURE)?;
+    // Signature, struct version, then the most and least significant halves of the file version
+    let info = data.get(start..start + 16)?;
+    let ms = u32::from_le_bytes(info[8..12].try_into().unwrap());
+    let ls = u32::from_le_bytes(info[12..16].try_into().unwrap());
+
+    Some(DllVersion {
+        major: (ms >> 16) as u16,
+        minor: ms as u16,
+        build: (ls >> 16) as u16,
+        private: ls as u16,
+    })
+}
+
+/// Whether the dll contains the utf16 text. Version resource strings are stored as utf16.
+fn contains_utf16(data: &[u8], text: &str) -> bool {
+    let needle: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    data.windows(needle.len()).any(|w| w == needle)
+}
+
+/// What we know about the installed Script Extender.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtenderInstall {
+    /// The version of the extender's `DWrite.dll`, if it is installed
+    pub updater_version: Option<DllVersion>,
+}
+impl ExtenderInstall {
+    /// Check the game's `bin` folder for the extender.
+    pub fn detect(game_bin_dir: &Path) -> ExtenderInstall {
+        let path = game_bin_dir.join(EXTENDER_UPDATER_FILE);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(_) => return ExtenderInstall::default(),
+        };
+
+        // Other tools also use a `DWrite.dll` to be loaded by the game
+        if !contains_utf16(&data, "Script Extender") {
+            eprintln!("{path:?} is not the Script Extender");
+            return ExtenderInstall::default();
+        }
+
+        ExtenderInstall {
+            updater_version: dll_file_version(&data),
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.updater_version.is_some()
+    }
+
+    /// The version of the extender itself, which mods compare their `RequiredVersion` against.  
+    /// The updater's version is unrelated to it. The extender is downloaded by the updater, and we
+    /// don't read it yet, so this is unknown.
+    pub fn version(&self) -> Option<u32> {
+        None
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DivinityExtenderModStatus {
+    #[default]
+    None,
+    Supports,
+    Required,
+    RequiredOld,
+    RequiredMissing,
+    RequiredDisabled,
+}
+impl DivinityExtenderModStatus {
+    /// Work out the status of a mod with the given extender config.
+    /// `extensions_enabled` is the `EnableExtensions` extender setting.
+    pub fn compute(
+        config: &ScriptExtenderConfig,
+        install: &ExtenderInstall,
+        extensions_enabled: bool,
+    ) -> DivinityExtenderModStatus {
+        use DivinityExtenderModStatus::*;
+
+        if !config.has_any_settings() {
+            return None;
+        }
+
+        if !config.requires_extender() {
+            return Supports;
+        }
+
+        if !install.is_installed() {
+            return RequiredMissing;
+        }
+
+        if !extensions_enabled {
+            RequiredDisabled
+        } else if install
+            .version()
+            .zip(config.required_extension_version)
+            .is_some_and(|(version, required)| version < required)
+        {
+            RequiredOld
+        } else {
+            Required
+        }
+    }
+
+    /// Whether the mod won't work with the currently installed extender
+    pub fn is_problem(self) -> bool {
+        use DivinityExtenderModStatus::*;
+        matches!(self, RequiredOld | RequiredMissing | RequiredDisabled)
+    }
+
+    pub fn tooltip(self, data: &ScriptExtenderConfig) -> Cow<'static, str> {
+        use DivinityExtenderModStatus::*;
+        match self {
+            None => Cow::Borrowed(""),
+            Supports => {
+                if let Some(version) = data.required_extension_version {
+                    Cow::Owned(format!("Supports Script Extender v{version} or higher\n"))
+                } else {
+                    Cow::Borrowed("Supports the Script Extender\n")
+                }
+            }
+            Required | RequiredMissing | RequiredDisabled | RequiredOld => {
+                let prefix = match self {
+                    RequiredMissing => "[MISSING] ",
+                    RequiredDisabled => "[EXTENSIONS DISABLED] ",
+                    RequiredOld => "[OLD] ",
+                    _ => "",
+                };
+
+                let req = if let Some(version) = data.required_extension_version {
+                    Cow::Owned(format!("Requires Script Extender v{version} or higher"))
+                } else {
+                    Cow::Borrowed("Requires the Script Extender")
+                };
+
+                let extra = match self {
+                    RequiredDisabled => " (Enable Extensions in the Script Extender config)",
+                    RequiredOld => " (Update by running the game)",
+                    _ => "",
+                };
+
+                Cow::Owned(format!("{prefix}{req}{extra}\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extender_status() {
+        let config = ScriptExtenderConfig::parse(
+            r#"{ "RequiredVersion": 4, "ModTable": "MyMod", "FeatureFlags": ["Lua"] }"#,
+        )
+        .unwrap();
+        assert_eq!(config.required_extension_version, Some(4));
+        assert_eq!(config.mod_table.as_deref(), Some("MyMod"));
+        assert!(config.requires_extender());
+
+        let missing = ExtenderInstall::default();
+        let mut dll = b"MZ....".to_vec();
+        dll.extend(FIXED_FILE_INFO_SIGNATURE);
+        dll.extend(0x0001_0000u32.to_le_bytes());
+        dll.extend(((3u32 << 16) | 2).to_le_bytes());
+        dll.extend(((1u32 << 16) | 9).to_le_bytes());
+        let version = dll_file_version(&dll).unwrap();
+        assert_eq!(version.to_string(), "3.2.1.9");
+        let installed = ExtenderInstall {
+            updater_version: Some(version),
+        };
+
+        use DivinityExtenderModStatus as Status;
+        assert_eq!(
+            Status::compute(&config, &missing, true),
+            Status::RequiredMissing
+        );
+        assert_eq!(
+            Status::compute(&config, &installed, false),
+            Status::RequiredDisabled
+        );
+        // The updater's version says nothing about the extender's, so it isn't seen as old
+        assert_eq!(Status::compute(&config, &installed, true), Status::Required);
+
+        let optional = ScriptExtenderConfig::parse(r#"{ "ModTable": "MyMod" }"#).unwrap();
+        assert_eq!(Status::compute(&optional, &missing, true), Status::Supports);
+        assert_eq!(
+            Status::compute(&Default::default(), &missing, true),
+            Status::None
+        );
+
+        assert!(is_extender_config_file(
+            "Mods/MyMod/ScriptExtender/Config.json"
+        ));
+        assert!(!is_extender_config_file("Mods/MyMod/Config.json"));
+    }
+}
@@ -0,0 +1,614 @@
+//! Installing mods into the user's `Mods` folder.
+//! Mods are distributed as either a bare `.pak`, or a `.zip` holding one or more paks and
+//! possibly an `info.json` describing them.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    classify::{read_pak_mod, PakMod},
+    extender::ScriptExtenderConfig,
+    meta::{MetaError, ModInfoJson, ModMeta},
+    mod_data::{ModData, ModVersion},
+};
+
+const INFO_JSON_NAME: &str = "info.json";
+
+#[derive(Debug)]
+pub enum InstallError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    /// The file is not a `.pak` or `.zip`
+    UnsupportedFileType(PathBuf),
+    /// The archive did not contain any paks
+    NoMods(PathBuf),
+    /// The mods folder does not exist
+    NoModsFolder(PathBuf),
+    /// We could not read the information about a pak
+    Meta {
+        name: String,
+        error: MetaError,
+    },
+    /// A pak in a zip failed after others in it had been handled. `installed` are the results for
+    /// those.
+    Partial {
+        installed: Vec<InstalledMod>,
+        error: Box<InstallError>,
+    },
+}
+impl std::fmt::Display for InstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallError::Io(err) => write!(f, "{err}"),
+            InstallError::Zip(err) => write!(f, "Failed to read zip: {err}"),
+            InstallError::UnsupportedFileType(path) => {
+                write!(f, "Can't install {path:?}, expected a .pak or .zip")
+            }
+            InstallError::NoMods(path) => write!(f, "No paks found in {path:?}"),
+            InstallError::NoModsFolder(path) => {
+                write!(f, "Mods folder does not exist at {path:?}")
+            }
+            InstallError::Meta { name, error } => write!(f, "{name}: {error}"),
+            InstallError::Partial { installed, error } => write!(
+                f,
+                "{error}, after {} of the paks had been handled",
+                installed.len()
+            ),
+        }
+    }
+}
+impl std::error::Error for InstallError {}
+impl From<std::io::Error> for InstallError {
+    fn from(e: std::io::Error) -> Self {
+        InstallError::Io(e)
+    }
+}
+impl From<zip::result::ZipError> for InstallError {
+    fn from(e: zip::result::ZipError) -> Self {
+        InstallError::Zip(e)
+    }
+}
+
+/// What happened when installing a single pak
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallOutcome {
+    /// The mod was not installed before
+    Installed,
+    /// An older version of the mod was replaced
+    Updated { previous: ModVersion },
+    /// The same version of the mod is already installed, so nothing was done
+    AlreadyInstalled,
+    /// A newer version of the mod is already installed, so nothing was done
+    NewerInstalled { installed: ModVersion },
+    /// There is already a different mod with the same file name, so nothing was done
+    FileConflict { existing: PathBuf },
+}
+impl InstallOutcome {
+    /// Whether the pak was copied into the mods folder
+    pub fn is_installed(&self) -> bool {
+        matches!(
+            self,
+            InstallOutcome::Installed | InstallOutcome::Updated { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledMod {
+    /// The mod information. If it was installed, the file path points to the installed pak.
+    pub meta: ModMeta,
+    /// The mod's `ScriptExtender/Config.json`, if it has one
+    pub extender: Option<ScriptExtenderConfig>,
+    pub outcome: InstallOutcome,
+}
+
+/// Install a `.pak` or a `.zip` of paks into `mods_dir`.
+/// `installed` are the mods that are currently known, which is used to detect duplicates and
+/// outdated versions.
+/// Nothing is overwritten unless it is an older version of the same mod.
+pub fn install_mod(
+    source: &Path,
+    mods_dir: &Path,
+    installed: &[ModData],
+) -> Result<Vec<InstalledMod>, InstallError> {
+    if !mods_dir.is_dir() {
+        return Err(InstallError::NoModsFolder(mods_dir.to_owned()));
+    }
+
+    let ext = source
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match ext.as_deref() {
+        Some("pak") => {
+            let name = file_name(source);
+            let pak_mod = read_pak_mod(source).map_err(|error| InstallError::Meta {
+                name: name.clone(),
+                error,
+            })?;
+
+            Ok(vec![install_pak(
+                source,
+                &name,
+                pak_mod.meta,
+                pak_mod.extender,
+                mods_dir,
+                installed,
+                false,
+            )?])
+        }
+        Some("zip") => install_zip(source, mods_dir, installed),
+        _ => Err(InstallError::UnsupportedFileType(source.to_owned())),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Whether the two paths are the same file, such as when installing a pak that is already in the
+/// mods folder
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Decide what to do with the mod given what is already installed.
+fn install_outcome(meta: &ModMeta, dest: &Path, installed: &[ModData]) -> InstallOutcome {
+    let existing = installed
+        .iter()
+        .find(|m| m.uuid.eq_ignore_ascii_case(&meta.data.uuid));
+
+    if let Some(existing) = existing {
+        if existing.version > meta.data.version {
+            return InstallOutcome::NewerInstalled {
+                installed: existing.version,
+            };
+        } else if existing.version == meta.data.version && !meta.data.is_newer_than_mod(existing) {
+            return InstallOutcome::AlreadyInstalled;
+        }
+    }
+
+    // A pak with the same name that isn't the version of this mod being replaced.
+    let replaces_existing =
+        existing.is_some_and(|existing| is_same_file(&existing.file_path, dest));
+    if dest.exists() && !replaces_existing {
+        return InstallOutcome::FileConflict {
+            existing: dest.to_owned(),
+        };
+    }
+
+    match existing {
+        Some(existing) => InstallOutcome::Updated {
+            previous: existing.version,
+        },
+        None => InstallOutcome::Installed,
+    }
+}
+
+/// Where a pak is written before it is renamed into place, so that nothing reads it half-written
+fn staging_path(mods_dir: &Path, name: &str) -> PathBuf {
+    mods_dir.join(format!(".{name}.installing"))
+}
+
+/// Install a single pak at `source` with the file name `name`.
+/// If `move_source` is true then the source is a temporary file which we can move rather than
+/// copy, and which is removed if it isn't installed.
+fn install_pak(
+    source: &Path,
+    name: &str,
+    mut meta: ModMeta,
+    extender: Option<ScriptExtenderConfig>,
+    mods_dir: &Path,
+    installed: &[ModData],
+    move_source: bool,
+) -> Result<InstalledMod, InstallError> {
+    let dest = mods_dir.join(name);
+    if !move_source && is_same_file(source, &dest) {
+        // Copying it onto itself would truncate it
+        meta.data.file_path = dest;
+        return Ok(InstalledMod {
+            meta,
+            extender,
+            outcome: InstallOutcome::AlreadyInstalled,
+        });
+    }
+
+    let outcome = install_outcome(&meta, &dest, installed);
+
+    if !outcome.is_installed() {
+        if move_source {
+            std::fs::remove_file(source)?;
+        }
+
+        return Ok(InstalledMod {
+            meta,
+            extender,
+            outcome,
+        });
+    }
+
+    if move_source {
+        std::fs::rename(source, &dest)?;
+    } else {
+        let temp_path = staging_path(mods_dir, name);
+        let copied =
+            std::fs::copy(source, &temp_path).and_then(|_| std::fs::rename(&temp_path, &dest));
+        if let Err(err) = copied {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+    }
+    eprintln!("Installed {name} to {dest:?}");
+
+    // Remove the old version if it was under a different name. We only touch paks within the mods
+    // folder.
+    if matches!(outcome, InstallOutcome::Updated { .. }) {
+        let old = installed
+            .iter()
+            .find(|m| m.uuid.eq_ignore_ascii_case(&meta.data.uuid))
+            .map(|m| &m.file_path);
+        if let Some(old) = old {
+            if old != &dest && old.parent() == Some(mods_dir) && old.is_file() {
+                eprintln!("Removing old version of mod at {old:?}");
+                std::fs::remove_file(old)?;
+            }
+        }
+    }
+
+    meta.data.file_path = dest;
+    Ok(InstalledMod {
+        meta,
+        extender,
+        outcome,
+    })
+}
+
+fn install_zip(
+    source: &Path,
+    mods_dir: &Path,
+    installed: &[ModData],
+) -> Result<Vec<InstalledMod>, InstallError> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(File::open(source)?))?;
+
+    let mut info_json = None;
+    let mut paks = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        // Use the name of the file itself, so that paks in subfolders of the zip are installed
+        // directly into the mods folder.
+        let Some(name) = entry
+            .enclosed_name()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+
+        if name.to_lowercase().ends_with(".pak") {
+            paks.push((i, name));
+        } else if name.eq_ignore_ascii_case(INFO_JSON_NAME) {
+            let text = std::io::read_to_string(entry)?;
+            match ModInfoJson::parse(&text) {
+                Ok(info) => info_json = Some(info),
+                Err(err) => eprintln!("Ignoring {INFO_JSON_NAME} in {source:?}: {err}"),
+            }
+        }
+    }
+
+    if paks.is_empty() {
+        return Err(InstallError::NoMods(source.to_owned()));
+    }
+
+    let mut results = Vec::with_capacity(paks.len());
+    for (i, name) in paks {
+        let result = install_zip_pak(
+            &mut archive,
+            i,
+            name,
+            info_json.as_ref(),
+            mods_dir,
+            installed,
+        );
+        match result {
+            Ok(result) => results.push(result),
+            Err(error) if results.is_empty() => return Err(error),
+            Err(error) => {
+                return Err(InstallError::Partial {
+                    installed: results,
+                    error: Box::new(error),
+                })
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Extract and install the pak at index `i` of the zip.
+fn install_zip_pak(
+    archive: &mut zip::ZipArchive<BufReader<File>>,
+    i: usize,
+    name: String,
+    info_json: Option<&ModInfoJson>,
+    mods_dir: &Path,
+    installed: &[ModData],
+) -> Result<InstalledMod, InstallError> {
+    let mut entry = archive.by_index(i)?;
+    let last_modified = entry.last_modified();
+
+    // Extract next to where it will be installed so that it can just be renamed into place.
+    let temp_path = staging_path(mods_dir, &name);
+    {
+        let mut temp = File::create(&temp_path)?;
+        if let Err(err) = std::io::copy(&mut entry, &mut temp) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+    }
+
+    let meta = read_pak_mod(&temp_path).map(|pak_mod| {
+        let PakMod {
+            meta,
+            classification,
+            extender,
+            ..
+        } = pak_mod;
+        if classification.has_meta {
+            return (meta, extender);
+        }
+
+        // Paks without a meta.lsx, such as mods that only override files, can be described
+        // by the info.json
+        let info_entry = info_json.and_then(|info| info.find_for_pak(&name));
+        match info_entry {
+            Some(entry) => {
+                let mut info_meta = entry.to_meta();
+                info_meta.data.file_path = meta.data.file_path;
+                classification.apply(&mut info_meta.data);
+                (info_meta, extender)
+            }
+            None => (meta, extender),
+        }
+    });
+    let (mut meta, extender) = match meta {
+        Ok(meta) => meta,
+        Err(error) => {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(InstallError::Meta { name, error });
+        }
+    };
+    // The extracted file was just modified, so use the time from the zip instead
+    meta.data.last_modified = zip_time_secs(last_modified);
+
+    install_pak(&temp_path, &name, meta, extender, mods_dir, installed, true)
+}
+
+/// Convert a zip's (timezone-less) date time to seconds since the unix epoch, treating it as UTC.
+fn zip_time_secs(time: zip::DateTime) -> Option<u64> {
+    // Days from civil algorithm, from http://howardhinnant.github.io/date_algorithms.html
+    let y = time.year() as i64 - if time.month() <= 2 { 1 } else { 0 };
+    let m = time.month() as i64;
+    let d = time.day() as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let secs =
+        days * 86400 + time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64;
+
+    u64::try_from(secs).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::util::empty_pak;
+
+    fn meta(uuid: &str, version: u64, last_modified: u64) -> ModMeta {
+        ModMeta {
+            data: ModData {
+                uuid: uuid.to_string(),
+                version: ModVersion::from(version),
+                last_modified: Some(last_modified),
+                ..Default::default()
+            },
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_install_outcome() {
+        let dest = Path::new("/nonexistent/Mods/MyMod.pak");
+        let installed = vec![meta("a", 5, 100).data];
+
+        assert_eq!(
+            install_outcome(&meta("b", 1, 0), dest, &installed),
+            InstallOutcome::Installed
+        );
+        assert_eq!(
+            install_outcome(&meta("A", 5, 100), dest, &installed),
+            InstallOutcome::AlreadyInstalled
+        );
+        assert_eq!(
+            install_outcome(&meta("a", 5, 200), dest, &installed),
+            InstallOutcome::Updated {
+                previous: ModVersion::from(5)
+            }
+        );
+        assert_eq!(
+            install_outcome(&meta("a", 6, 0), dest, &installed),
+            InstallOutcome::Updated {
+                previous: ModVersion::from(5)
+            }
+        );
+        assert_eq!(
+            install_outcome(&meta("a", 4, 200), dest, &installed),
+            InstallOutcome::NewerInstalled {
+                installed: ModVersion::from(5)
+            }
+        );
+    }
+
+    fn installed_mod(uuid: &str, version: &str, path: &Path) -> ModData {
+        ModData {
+            uuid: uuid.to_string(),
+            version: version.parse().unwrap(),
+            file_path: path.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn write_zip(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn info_json(version: &str) -> String {
+        format!(
+            r#"{{"Mods": [{{"Name": "Spells", "Folder": "Spells", "UUID": "spells", "Version": "{version}"}}], "MD5": ""}}"#
+        )
+    }
+
+    #[test]
+    fn test_install_mod() {
+        let temp = tempfile::tempdir().unwrap();
+        let mods_dir = temp.path().join("Mods");
+        let downloads = temp.path().join("Downloads");
+        std::fs::create_dir(&mods_dir).unwrap();
+        std::fs::create_dir(&downloads).unwrap();
+
+        // A pak without a meta.lsx is named after its file
+        let pak = downloads.join("Armor.pak");
+        std::fs::write(&pak, empty_pak()).unwrap();
+        let result = install_mod(&pak, &mods_dir, &[]).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].outcome, InstallOutcome::Installed);
+        assert_eq!(result[0].meta.data.uuid, "Armor");
+        assert_eq!(result[0].meta.data.file_path, mods_dir.join("Armor.pak"));
+        assert_eq!(
+            std::fs::read(mods_dir.join("Armor.pak")).unwrap(),
+            empty_pak()
+        );
+        assert!(pak.exists());
+
+        // Installing the pak that is already in the mods folder leaves it alone
+        let result = install_mod(&mods_dir.join("Armor.pak"), &mods_dir, &[]).unwrap();
+        assert_eq!(result[0].outcome, InstallOutcome::AlreadyInstalled);
+        assert_eq!(
+            std::fs::read(mods_dir.join("Armor.pak")).unwrap(),
+            empty_pak()
+        );
+
+        // A zip with the pak in a folder, described by the info.json
+        let zip_v1 = downloads.join("Spells_v1.zip");
+        write_zip(
+            &zip_v1,
+            &[
+                ("Spells/Spells_v1.pak", &empty_pak()),
+                ("info.json", info_json("1.0.0.0").as_bytes()),
+            ],
+        );
+        let result = install_mod(&zip_v1, &mods_dir, &[]).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].outcome, InstallOutcome::Installed);
+        assert_eq!(result[0].meta.data.uuid, "spells");
+        assert_eq!(result[0].meta.data.version, "1.0.0.0".parse().unwrap());
+        assert_eq!(
+            result[0].meta.data.file_path,
+            mods_dir.join("Spells_v1.pak")
+        );
+
+        // An update under a new name removes the old version
+        let installed = vec![
+            installed_mod("Armor", "0", &mods_dir.join("Armor.pak")),
+            installed_mod("spells", "1.0.0.0", &mods_dir.join("Spells_v1.pak")),
+        ];
+        let zip_v2 = downloads.join("Spells_v2.zip");
+        write_zip(
+            &zip_v2,
+            &[
+                ("Spells_v2.pak", &empty_pak()),
+                ("info.json", info_json("2.0.0.0").as_bytes()),
+            ],
+        );
+        let result = install_mod(&zip_v2, &mods_dir, &installed).unwrap();
+        assert_eq!(
+            result[0].outcome,
+            InstallOutcome::Updated {
+                previous: "1.0.0.0".parse().unwrap()
+            }
+        );
+        assert!(!mods_dir.join("Spells_v1.pak").exists());
+        assert!(mods_dir.join("Spells_v2.pak").exists());
+
+        // An update isn't written over a different mod's pak
+        let installed = vec![
+            installed_mod("Armor", "0", &mods_dir.join("Armor.pak")),
+            installed_mod("spells", "2.0.0.0", &mods_dir.join("Spells_v2.pak")),
+        ];
+        let zip_v3 = downloads.join("Spells_v3.zip");
+        write_zip(
+            &zip_v3,
+            &[
+                ("Armor.pak", &empty_pak()),
+                ("info.json", info_json("3.0.0.0").as_bytes()),
+            ],
+        );
+        let result = install_mod(&zip_v3, &mods_dir, &installed).unwrap();
+        assert_eq!(
+            result[0].outcome,
+            InstallOutcome::FileConflict {
+                existing: mods_dir.join("Armor.pak")
+            }
+        );
+        assert!(mods_dir.join("Spells_v2.pak").exists());
+
+        // Nor is a new mod
+        std::fs::write(mods_dir.join("Armor.pak"), "a different mod").unwrap();
+        let installed = vec![installed_mod("other", "0", &mods_dir.join("Armor.pak"))];
+        let result = install_mod(&pak, &mods_dir, &installed).unwrap();
+        assert!(matches!(
+            result[0].outcome,
+            InstallOutcome::FileConflict { .. }
+        ));
+        assert_eq!(
+            std::fs::read_to_string(mods_dir.join("Armor.pak")).unwrap(),
+            "a different mod"
+        );
+
+        // Nothing is left half-installed
+        let mut names: Vec<_> = std::fs::read_dir(&mods_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Armor.pak", "Spells_v2.pak"]);
+    }
+
+    #[test]
+    fn test_zip_time_secs() {
+        let time = zip::DateTime::from_date_and_time(2023, 8, 3, 12, 30, 10).unwrap();
+        assert_eq!(zip_time_secs(time), Some(1691065810));
+    }
+}
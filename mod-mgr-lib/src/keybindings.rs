@@ -0,0 +1,435 @@
+//! The keyboard shortcuts for the mod manager's actions.
+//! The user's changes to the default bindings are kept in `keybindings.json` in the settings
+//! directory, which maps action names to their keys:
+//! ```json
+//! {
+//!     "SaveOrder": ["Ctrl+S"],
+//!     "LaunchGame": []
+//! }
+//! ```
+//! Actions that aren't in the file use their default bindings. Actions and keys in the file that we
+//! don't know, such as ones from a newer version, are skipped with a warning.
+//!
+//! Some actions have a second default binding without modifiers, such as `PageUp` to move mods up.
+//! Controllers, like the Steam Deck's, can't press key combinations easily, but Steam Input can
+//! bind a button to a single key.
+
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::settings_dir;
+
+pub const KEYBINDINGS_FILE_NAME: &str = "keybindings.json";
+
+#[derive(Debug)]
+pub enum KeyBindingsError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+impl std::fmt::Display for KeyBindingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyBindingsError::Io(err) => write!(f, "{err}"),
+            KeyBindingsError::Json(err) => {
+                write!(f, "Failed to parse {KEYBINDINGS_FILE_NAME}: {err}")
+            }
+        }
+    }
+}
+impl std::error::Error for KeyBindingsError {}
+impl From<std::io::Error> for KeyBindingsError {
+    fn from(e: std::io::Error) -> Self {
+        KeyBindingsError::Io(e)
+    }
+}
+impl From<serde_json::Error> for KeyBindingsError {
+    fn from(e: serde_json::Error) -> Self {
+        KeyBindingsError::Json(e)
+    }
+}
+
+/// Something the user can bind a key to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AppAction {
+    SaveOrder,
+    ExportOrder,
+    InstallMod,
+    LaunchGame,
+    /// Move the selected mods to the other list
+    SwapLists,
+    MoveUp,
+    MoveDown,
+    Undo,
+    Redo,
+    FocusSearch,
+    OpenSettings,
+    OpenModsFolder,
+    CheckForUpdates,
+    ExportDiagnostics,
+}
+impl AppAction {
+    pub const ALL: [AppAction; 14] = [
+        AppAction::SaveOrder,
+        AppAction::ExportOrder,
+        AppAction::InstallMod,
+        AppAction::LaunchGame,
+        AppAction::SwapLists,
+        AppAction::MoveUp,
+        AppAction::MoveDown,
+        AppAction::Undo,
+        AppAction::Redo,
+        AppAction::FocusSearch,
+        AppAction::OpenSettings,
+        AppAction::OpenModsFolder,
+        AppAction::CheckForUpdates,
+        AppAction::ExportDiagnostics,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AppAction::SaveOrder => "Save Load Order",
+            AppAction::ExportOrder => "Export Load Order",
+            AppAction::InstallMod => "Install Mod",
+            AppAction::LaunchGame => "Launch Game",
+            AppAction::SwapLists => "Move Selected Mods to Opposite List",
+            AppAction::MoveUp => "Move Selected Mods Up",
+            AppAction::MoveDown => "Move Selected Mods Down",
+            AppAction::Undo => "Undo",
+            AppAction::Redo => "Redo",
+            AppAction::FocusSearch => "Search Mods",
+            AppAction::OpenSettings => "Open Settings",
+            AppAction::OpenModsFolder => "Open Mods Folder",
+            AppAction::CheckForUpdates => "Check for Mod Updates",
+            AppAction::ExportDiagnostics => "Export Diagnostics",
+        }
+    }
+
+    pub fn default_bindings(&self) -> Vec<KeyCombo> {
+        let keys: &[&str] = match self {
+            AppAction::SaveOrder => &["Ctrl+S", "F2"],
+            AppAction::ExportOrder => &["Ctrl+E"],
+            AppAction::InstallMod => &["Ctrl+O"],
+            AppAction::LaunchGame => &["F5"],
+            AppAction::SwapLists => &["Enter"],
+            AppAction::MoveUp => &["Ctrl+Up", "PageUp"],
+            AppAction::MoveDown => &["Ctrl+Down", "PageDown"],
+            AppAction::Undo => &["Ctrl+Z"],
+            AppAction::Redo => &["Ctrl+Y", "Ctrl+Shift+Z"],
+            AppAction::FocusSearch => &["Ctrl+F", "F3"],
+            AppAction::OpenSettings => &["Ctrl+P", "F10"],
+            AppAction::OpenModsFolder => &["Ctrl+1"],
+            AppAction::CheckForUpdates => &["Ctrl+U"],
+            AppAction::ExportDiagnostics => &[],
+        };
+        keys.iter()
+            .map(|key| key.parse().expect("invalid default key binding"))
+            .collect()
+    }
+}
+
+/// Keys with names, rather than being a single character
+const NAMED_KEYS: &[&str] = &[
+    "Enter",
+    "Escape",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Tab",
+    "Space",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "PageUp",
+    "PageDown",
+    "Home",
+    "End",
+];
+
+/// A key along with the modifiers held with it, written like `Ctrl+Shift+S`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// An uppercase character, a name from [`NAMED_KEYS`] or a function key like `F5`
+    pub key: String,
+}
+impl KeyCombo {
+    /// Returns `None` if the key isn't one we support binding.
+    pub fn new(key: &str, ctrl: bool, shift: bool, alt: bool) -> Option<KeyCombo> {
+        Some(KeyCombo {
+            ctrl,
+            shift,
+            alt,
+            key: normalize_key(key)?,
+        })
+    }
+}
+impl std::fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+        f.write_str(&self.key)
+    }
+}
+impl FromStr for KeyCombo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // `Ctrl++` binds the plus key
+        let (modifiers, key) = match parts.as_slice() {
+            [modifiers @ .., "", ""] if !modifiers.is_empty() => (modifiers, "+"),
+            [modifiers @ .., key] => (modifiers, *key),
+            [] => unreachable!("split always returns at least one part"),
+        };
+
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        for modifier in modifiers {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" => ctrl = true,
+                "shift" => shift = true,
+                "alt" | "option" => alt = true,
+                _ => return Err(format!("Unknown modifier {modifier:?} in {s:?}")),
+            }
+        }
+
+        KeyCombo::new(key, ctrl, shift, alt).ok_or_else(|| format!("Unknown key {key:?} in {s:?}"))
+    }
+}
+impl Serialize for KeyCombo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for KeyCombo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn normalize_key(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c {
+            ' ' => Some("Space".to_string()),
+            c if c.is_control() => None,
+            c => Some(c.to_uppercase().collect()),
+        };
+    }
+
+    if let Some(named) = NAMED_KEYS.iter().find(|k| k.eq_ignore_ascii_case(key)) {
+        return Some(named.to_string());
+    }
+
+    let number = key.strip_prefix(['F', 'f'])?.parse::<u8>().ok()?;
+    (1..=24).contains(&number).then(|| format!("F{number}"))
+}
+
+/// Keys that are bound to more than one action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConflict {
+    pub combo: KeyCombo,
+    pub actions: Vec<AppAction>,
+}
+
+/// The user's changes to the default bindings
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct KeyBindings {
+    overrides: BTreeMap<AppAction, Vec<KeyCombo>>,
+}
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, Vec<String>>::deserialize(deserializer)?;
+
+        let mut overrides = BTreeMap::new();
+        for (name, keys) in entries {
+            let action = match serde_json::from_value(serde_json::Value::String(name.clone())) {
+                Ok(action) => action,
+                Err(_) => {
+                    eprintln!("Skipping key bindings for unknown action {name:?}");
+                    continue;
+                }
+            };
+            let combos = keys
+                .iter()
+                .filter_map(|key| {
+                    key.parse()
+                        .map_err(|err| eprintln!("Skipping key binding for {name}: {err}"))
+                        .ok()
+                })
+                .collect();
+            overrides.insert(action, combos);
+        }
+
+        Ok(KeyBindings { overrides })
+    }
+}
+impl KeyBindings {
+    /// Load the bindings from the settings directory.
+    /// If they can't be read then the defaults are used.
+    pub fn load() -> KeyBindings {
+        let path = settings_dir().join(KEYBINDINGS_FILE_NAME);
+        match KeyBindings::load_from(&path) {
+            Ok(bindings) => bindings,
+            Err(KeyBindingsError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                KeyBindings::default()
+            }
+            Err(err) => {
+                eprintln!("Using the default key bindings, as {path:?} could not be read: {err}");
+                KeyBindings::default()
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<KeyBindings, KeyBindingsError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Save the bindings to the settings directory.
+    pub fn save(&self) -> Result<(), KeyBindingsError> {
+        let dir = settings_dir();
+        std::fs::create_dir_all(&dir)?;
+        self.save_to(&dir.join(KEYBINDINGS_FILE_NAME))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), KeyBindingsError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn bindings(&self, action: AppAction) -> Vec<KeyCombo> {
+        self.overrides
+            .get(&action)
+            .cloned()
+            .unwrap_or_else(|| action.default_bindings())
+    }
+
+    /// Whether the action's bindings have been changed from the defaults
+    pub fn is_overridden(&self, action: AppAction) -> bool {
+        self.overrides.contains_key(&action)
+    }
+
+    pub fn set(&mut self, action: AppAction, combos: Vec<KeyCombo>) {
+        if combos == action.default_bindings() {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, combos);
+        }
+    }
+
+    /// Add a binding to the action, if it doesn't already have it.
+    pub fn add(&mut self, action: AppAction, combo: KeyCombo) {
+        let mut combos = self.bindings(action);
+        if !combos.contains(&combo) {
+            combos.push(combo);
+            self.set(action, combos);
+        }
+    }
+
+    /// Go back to the default bindings for the action.
+    pub fn reset(&mut self, action: AppAction) {
+        self.overrides.remove(&action);
+    }
+
+    pub fn reset_all(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// The action that the key runs. If the key is bound to multiple actions, the first one in
+    /// [`AppAction::ALL`] is used.
+    pub fn action_for(&self, combo: &KeyCombo) -> Option<AppAction> {
+        AppAction::ALL
+            .into_iter()
+            .find(|action| self.bindings(*action).contains(combo))
+    }
+
+    pub fn conflicts(&self) -> Vec<KeyConflict> {
+        let mut actions_by_key: BTreeMap<KeyCombo, Vec<AppAction>> = BTreeMap::new();
+        for action in AppAction::ALL {
+            for combo in self.bindings(action) {
+                let actions = actions_by_key.entry(combo).or_default();
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+        }
+
+        actions_by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(combo, actions)| KeyConflict { combo, actions })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings() {
+        let combo: KeyCombo = "ctrl + shift+s".parse().unwrap();
+        assert_eq!(combo, KeyCombo::new("s", true, true, false).unwrap());
+        assert_eq!(combo.to_string(), "Ctrl+Shift+S");
+        assert_eq!("Ctrl++".parse::<KeyCombo>().unwrap().key, "+");
+        assert_eq!("pagedown".parse::<KeyCombo>().unwrap().key, "PageDown");
+        assert!("Ctrl+F25".parse::<KeyCombo>().is_err());
+        assert!("Super+S".parse::<KeyCombo>().is_err());
+
+        // Every default binding parses, and none of them conflict
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.conflicts().is_empty());
+
+        let f5: KeyCombo = "F5".parse().unwrap();
+        assert_eq!(bindings.action_for(&f5), Some(AppAction::LaunchGame));
+        bindings.add(AppAction::ExportDiagnostics, f5.clone());
+        assert_eq!(
+            bindings.conflicts(),
+            vec![KeyConflict {
+                combo: f5.clone(),
+                actions: vec![AppAction::LaunchGame, AppAction::ExportDiagnostics],
+            }]
+        );
+
+        bindings.set(AppAction::LaunchGame, Vec::new());
+        assert_eq!(bindings.action_for(&f5), Some(AppAction::ExportDiagnostics));
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(json, r#"{"LaunchGame":[],"ExportDiagnostics":["F5"]}"#);
+        let loaded: KeyBindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, bindings);
+
+        // Only the entries that can't be understood are skipped
+        let loaded: KeyBindings = serde_json::from_str(
+            r#"{"Nonsense":["F1"],"LaunchGame":["F6","Super+Q"],"Undo":["Ctrl+U"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            loaded.bindings(AppAction::LaunchGame),
+            vec!["F6".parse().unwrap()]
+        );
+        assert_eq!(
+            loaded.bindings(AppAction::Undo),
+            vec!["Ctrl+U".parse().unwrap()]
+        );
+        assert_eq!(loaded.action_for(&"F1".parse().unwrap()), None);
+
+        bindings.reset(AppAction::LaunchGame);
+        bindings.set(AppAction::ExportDiagnostics, Vec::new());
+        assert_eq!(bindings, KeyBindings::default());
+    }
+}
@@ -0,0 +1,542 @@
+//! Launching the game.
+//! The C# version only ever runs the executable directly, which doesn't work on Linux since the
+//! game has to be run through Proton. So there's several ways of launching it, and we build the
+//! command to run separately from running it so that it can be checked without launching anything.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    settings::Settings,
+    util::{
+        game_paths::{self, steam_roots},
+        steam_vdf::all_library_folders,
+    },
+    BG3_STEAM_ID,
+};
+
+/// The Flatpak id of Steam
+const STEAM_FLATPAK_ID: &str = "com.valvesoftware.Steam";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaunchMethod {
+    /// Run the game executable directly. This is what the C# version does, and only really works
+    /// on Windows.
+    Direct,
+    /// Open `steam://rungameid/1086940`. This can't pass any arguments to the game, but works
+    /// wherever Steam is installed.
+    SteamUrl,
+    /// Run `steam -applaunch 1086940 <args>`
+    SteamApplaunch,
+    /// Run the game executable through a specific Proton version ourselves, using the game's
+    /// Proton prefix.
+    Proton,
+}
+impl LaunchMethod {
+    pub fn default_for_platform() -> LaunchMethod {
+        if cfg!(windows) {
+            LaunchMethod::Direct
+        } else {
+            LaunchMethod::SteamApplaunch
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            LaunchMethod::Direct => "Run Executable",
+            LaunchMethod::SteamUrl => "Steam (URL)",
+            LaunchMethod::SteamApplaunch => "Steam",
+            LaunchMethod::Proton => "Proton",
+        }
+    }
+}
+impl Default for LaunchMethod {
+    fn default() -> Self {
+        LaunchMethod::default_for_platform()
+    }
+}
+
+#[derive(Debug)]
+pub enum LaunchError {
+    /// The game executable path is not set
+    NoExecutable,
+    /// The game executable does not exist
+    ExecutableNotFound(PathBuf),
+    /// The Proton launch method was chosen without picking a Proton version
+    NoCompatTool,
+    /// The chosen Proton version does not have a `proton` script
+    CompatToolNotFound(PathBuf),
+    /// We could not find the Proton prefix for the game, which Steam creates the first time the
+    /// game is run.
+    NoCompatData,
+    /// We don't know how to run Steam on this system
+    NoSteam,
+    /// The launch parameters could not be parsed
+    InvalidLaunchParams(String),
+    Io(std::io::Error),
+}
+impl std::fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchError::NoExecutable => write!(f, "No game executable path set"),
+            LaunchError::ExecutableNotFound(path) => {
+                write!(f, "Failed to find game executable at: {path:?}")
+            }
+            LaunchError::NoCompatTool => write!(f, "No Proton version chosen"),
+            LaunchError::CompatToolNotFound(path) => {
+                write!(f, "Failed to find Proton at: {path:?}")
+            }
+            LaunchError::NoCompatData => write!(
+                f,
+                "Failed to find the game's Proton prefix. Launch the game through Steam once first"
+            ),
+            LaunchError::NoSteam => write!(f, "Failed to find Steam"),
+            LaunchError::InvalidLaunchParams(params) => {
+                write!(f, "Failed to parse launch parameters: {params}")
+            }
+            LaunchError::Io(err) => write!(f, "Failed to launch game: {err}"),
+        }
+    }
+}
+impl std::error::Error for LaunchError {}
+impl From<std::io::Error> for LaunchError {
+    fn from(e: std::io::Error) -> Self {
+        LaunchError::Io(e)
+    }
+}
+
+/// Something the user asked for that the chosen launch method can't do. The game can still be
+/// launched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchWarning {
+    /// Steam launches whatever executable the game's launch options in Steam say to, so we can't
+    /// pick the DirectX 11 one.
+    Dx11Unsupported,
+    /// The `steam://` url can't pass arguments to the game.
+    ArgsUnsupported,
+}
+impl std::fmt::Display for LaunchWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchWarning::Dx11Unsupported => write!(
+                f,
+                "DirectX 11 mode can't be chosen when launching through Steam. Pick it in Steam's launch dialog instead"
+            ),
+            LaunchWarning::ArgsUnsupported => write!(
+                f,
+                "Launch parameters can't be passed when launching through a steam:// url"
+            ),
+        }
+    }
+}
+
+/// A command that would launch the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchCommand {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    /// Extra environment variables to set
+    pub env: Vec<(OsString, OsString)>,
+    pub current_dir: Option<PathBuf>,
+    pub warnings: Vec<LaunchWarning>,
+}
+impl LaunchCommand {
+    fn new(program: impl Into<OsString>) -> LaunchCommand {
+        LaunchCommand {
+            program: program.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            current_dir: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn arg(mut self, arg: impl Into<OsString>) -> LaunchCommand {
+        self.args.push(arg.into());
+        self
+    }
+
+    fn args<I: Into<OsString>>(mut self, args: impl IntoIterator<Item = I>) -> LaunchCommand {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> LaunchCommand {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn to_command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.program);
+        command.args(&self.args);
+        command.envs(self.env.iter().map(|(k, v)| (k, v)));
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+
+        command
+    }
+
+    /// Run the command, not waiting for the game to exit.
+    pub fn spawn(&self) -> Result<std::process::Child, LaunchError> {
+        Ok(self.to_command().spawn()?)
+    }
+}
+impl std::fmt::Display for LaunchCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in &self.env {
+            write!(f, "{}={:?} ", key.to_string_lossy(), value)?;
+        }
+        write!(f, "{:?}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Information about the system that is needed to build a [`LaunchCommand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchContext {
+    /// How to run Steam. i.e. `["steam"]` or `["flatpak", "run", "com.valvesoftware.Steam"]`
+    /// Empty if we don't know.
+    pub steam_command: Vec<OsString>,
+    /// The Steam install folder. Used as the Steam client path for Proton.
+    pub steam_root: Option<PathBuf>,
+    /// How to open urls. i.e. `["xdg-open"]`
+    pub open_command: Vec<OsString>,
+}
+impl LaunchContext {
+    /// Detect how to run things on this system.
+    pub fn detect() -> LaunchContext {
+        let steam_root = steam_roots().into_iter().next();
+        let is_flatpak = steam_root
+            .as_ref()
+            .is_some_and(|root| root.components().any(|c| c.as_os_str() == STEAM_FLATPAK_ID));
+
+        let steam_command: Vec<OsString> = if cfg!(windows) {
+            steam_root
+                .as_ref()
+                .map(|root| vec![root.join("steam.exe").into()])
+                .unwrap_or_default()
+        } else if cfg!(target_os = "macos") {
+            vec!["/Applications/Steam.app/Contents/MacOS/steam_osx".into()]
+        } else if is_flatpak {
+            vec!["flatpak".into(), "run".into(), STEAM_FLATPAK_ID.into()]
+        } else {
+            vec!["steam".into()]
+        };
+
+        let open_command: Vec<OsString> = if cfg!(windows) {
+            vec!["explorer".into()]
+        } else if cfg!(target_os = "macos") {
+            vec!["open".into()]
+        } else {
+            vec!["xdg-open".into()]
+        };
+
+        LaunchContext {
+            steam_command,
+            steam_root,
+            open_command,
+        }
+    }
+}
+
+/// Get the arguments to pass to the game from the settings.
+/// This adds the story log and skip launcher arguments if they're enabled and not already in the
+/// user's launch parameters.
+pub fn game_launch_args(settings: &Settings) -> Result<Vec<String>, LaunchError> {
+    // We have to parse the arguments because Rust's command does not let you directly run
+    // it.
+    // We could manually pass it to system specific bash/cmd/whatever, but that is just
+    // harder and has issues of escaping the parameters properly.
+    //
+    // We could store the parameters as a vector, but that would break settings file
+    // compatibility with C# BG3ModManager. There's other methods but they have their own
+    // issues. This is simple and more than fast enough.
+    let mut args = shlex::split(&settings.game_launch_params)
+        .ok_or_else(|| LaunchError::InvalidLaunchParams(settings.game_launch_params.clone()))?;
+
+    if settings.game_story_log_enabled && !settings.game_launch_params.contains("storylog") {
+        args.push("-storylog".to_string());
+        args.push("1".to_string());
+    }
+
+    if settings.skip_launcher && !settings.game_launch_params.contains("skip-launcher") {
+        args.insert(0, "--skip-launcher".to_string());
+    }
+
+    Ok(args)
+}
+
+/// Get the executable to run, checking that it exists.
+/// If DX11 is enabled and there is a DX11 executable next to the normal one, that is used instead.
+pub fn game_executable(settings: &Settings) -> Result<PathBuf, LaunchError> {
+    let exe_path = &settings.game_executable_path;
+    if exe_path.as_os_str().is_empty() {
+        return Err(LaunchError::NoExecutable);
+    } else if !exe_path.is_file() {
+        return Err(LaunchError::ExecutableNotFound(exe_path.clone()));
+    }
+
+    if settings.launch_dx11 {
+        if let Some(exe_dir) = exe_path.parent() {
+            let dx11_exe = exe_dir.join("bg3_dx11.exe");
+            if dx11_exe.is_file() {
+                return Ok(dx11_exe);
+            }
+        }
+    }
+
+    Ok(exe_path.clone())
+}
+
+/// Get the `proton` script within a compatibility tool folder. The path to the script itself is
+/// also accepted.
+pub fn proton_script(compat_tool: &Path) -> PathBuf {
+    if compat_tool.is_dir() {
+        compat_tool.join("proton")
+    } else {
+        compat_tool.to_owned()
+    }
+}
+
+/// Find the Proton versions that are installed. These are the official ones in the Steam
+/// libraries, and custom ones (like GE-Proton) in `compatibilitytools.d`.
+pub fn find_compat_tools() -> Vec<PathBuf> {
+    let official = all_library_folders()
+        .into_iter()
+        .map(|library| library.steamapps().join("common"));
+    let custom = steam_roots()
+        .into_iter()
+        .map(|root| root.join("compatibilitytools.d"));
+
+    let mut tools = Vec::new();
+    for dir in official.chain(custom) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.join("proton").is_file() && !tools.contains(&path) {
+                tools.push(path);
+            }
+        }
+    }
+
+    tools.sort();
+    tools
+}
+
+/// Build the command to launch the game with the method chosen in the settings.
+pub fn build_launch_command(
+    settings: &Settings,
+    ctx: &LaunchContext,
+) -> Result<LaunchCommand, LaunchError> {
+    let args = game_launch_args(settings)?;
+
+    match settings.launch_method {
+        LaunchMethod::Direct => {
+            let exe_path = game_executable(settings)?;
+            let mut command = LaunchCommand::new(&exe_path).args(args);
+            command.current_dir = exe_path.parent().map(Path::to_path_buf);
+
+            Ok(command)
+        }
+        LaunchMethod::SteamUrl => {
+            let (program, open_args) =
+                ctx.open_command.split_first().ok_or(LaunchError::NoSteam)?;
+            let mut command = LaunchCommand::new(program)
+                .args(open_args.iter().cloned())
+                .arg(format!("steam://rungameid/{BG3_STEAM_ID}"));
+
+            if !args.is_empty() {
+                command.warnings.push(LaunchWarning::ArgsUnsupported);
+            }
+            if settings.launch_dx11 {
+                command.warnings.push(LaunchWarning::Dx11Unsupported);
+            }
+
+            Ok(command)
+        }
+        LaunchMethod::SteamApplaunch => {
+            let (program, steam_args) = ctx
+                .steam_command
+                .split_first()
+                .ok_or(LaunchError::NoSteam)?;
+            let mut command = LaunchCommand::new(program)
+                .args(steam_args.iter().cloned())
+                .arg("-applaunch")
+                .arg(BG3_STEAM_ID.to_string())
+                .args(args);
+
+            if settings.launch_dx11 {
+                command.warnings.push(LaunchWarning::Dx11Unsupported);
+            }
+
+            Ok(command)
+        }
+        LaunchMethod::Proton => {
+            if settings.proton_path.as_os_str().is_empty() {
+                return Err(LaunchError::NoCompatTool);
+            }
+
+            let proton = proton_script(&settings.proton_path);
+            if !proton.is_file() {
+                return Err(LaunchError::CompatToolNotFound(proton));
+            }
+
+            let exe_path = game_executable(settings)?;
+
+            // `bin/bg3.exe` -> `steamapps/common/Baldurs Gate 3` -> `steamapps`
+            let compat_data = exe_path
+                .parent()
+                .and_then(Path::parent)
+                .and_then(game_paths::steamapps_folder_of_install)
+                .map(|steamapps| steamapps.join("compatdata").join(BG3_STEAM_ID.to_string()))
+                .filter(|compat_data| compat_data.is_dir())
+                .ok_or(LaunchError::NoCompatData)?;
+
+            let steam_root = ctx.steam_root.clone().ok_or(LaunchError::NoSteam)?;
+
+            let mut command = LaunchCommand::new(proton)
+                .arg("run")
+                .arg(&exe_path)
+                .args(args)
+                .env("STEAM_COMPAT_DATA_PATH", compat_data)
+                .env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_root)
+                .env("SteamAppId", BG3_STEAM_ID.to_string())
+                .env("SteamGameId", BG3_STEAM_ID.to_string());
+            command.current_dir = exe_path.parent().map(Path::to_path_buf);
+
+            Ok(command)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> LaunchContext {
+        LaunchContext {
+            steam_command: vec!["flatpak".into(), "run".into(), STEAM_FLATPAK_ID.into()],
+            steam_root: Some(PathBuf::from("/home/deck/.local/share/Steam")),
+            open_command: vec!["xdg-open".into()],
+        }
+    }
+
+    fn os(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_launch_args() {
+        let settings = Settings {
+            game_launch_params: "-continueGame \"--some arg\"".to_string(),
+            game_story_log_enabled: true,
+            skip_launcher: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            game_launch_args(&settings).unwrap(),
+            vec![
+                "--skip-launcher",
+                "-continueGame",
+                "--some arg",
+                "-storylog",
+                "1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_steam_commands() {
+        let mut settings = Settings {
+            game_launch_params: "-continueGame".to_string(),
+            skip_launcher: true,
+            launch_dx11: true,
+            launch_method: LaunchMethod::SteamApplaunch,
+            ..Default::default()
+        };
+
+        let command = build_launch_command(&settings, &ctx()).unwrap();
+        assert_eq!(command.program, "flatpak");
+        assert_eq!(
+            command.args,
+            os(&[
+                "run",
+                STEAM_FLATPAK_ID,
+                "-applaunch",
+                "1086940",
+                "--skip-launcher",
+                "-continueGame"
+            ])
+        );
+        assert_eq!(command.warnings, vec![LaunchWarning::Dx11Unsupported]);
+
+        settings.launch_method = LaunchMethod::SteamUrl;
+        let command = build_launch_command(&settings, &ctx()).unwrap();
+        assert_eq!(command.program, "xdg-open");
+        assert_eq!(command.args, os(&["steam://rungameid/1086940"]));
+        assert_eq!(
+            command.warnings,
+            vec![
+                LaunchWarning::ArgsUnsupported,
+                LaunchWarning::Dx11Unsupported
+            ]
+        );
+    }
+
+    #[test]
+    fn test_proton_command() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let proton_dir = root.join("compatibilitytools.d/GE-Proton8-16");
+        let bin = root.join("steamapps/common/Baldurs Gate 3/bin");
+        let compat_data = root.join("steamapps/compatdata/1086940");
+        std::fs::create_dir_all(&proton_dir).unwrap();
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(&compat_data).unwrap();
+        std::fs::write(proton_dir.join("proton"), "").unwrap();
+        std::fs::write(bin.join("bg3.exe"), "").unwrap();
+        std::fs::write(bin.join("bg3_dx11.exe"), "").unwrap();
+
+        let mut settings = Settings {
+            game_executable_path: bin.join("bg3.exe"),
+            skip_launcher: true,
+            launch_dx11: true,
+            launch_method: LaunchMethod::Proton,
+            ..Default::default()
+        };
+
+        let no_tool = build_launch_command(&settings, &ctx());
+        settings.proton_path = proton_dir.clone();
+        let command = build_launch_command(&settings, &ctx());
+
+        assert!(matches!(no_tool, Err(LaunchError::NoCompatTool)));
+
+        let command = command.unwrap();
+        assert_eq!(command.program, proton_dir.join("proton"));
+        assert_eq!(
+            command.args,
+            vec![
+                OsString::from("run"),
+                bin.join("bg3_dx11.exe").into(),
+                OsString::from("--skip-launcher"),
+            ]
+        );
+        assert!(command
+            .env
+            .contains(&("STEAM_COMPAT_DATA_PATH".into(), compat_data.into())));
+        assert!(command.warnings.is_empty());
+    }
+}
@@ -1,6 +1,30 @@
+pub mod backups;
+pub mod classify;
+pub mod conflicts;
+pub mod diagnostics;
+pub mod extender;
+pub mod install;
+pub mod keybindings;
+pub mod launch;
+pub mod load_order;
+pub mod md5_cache;
+pub mod meta;
 pub mod mod_data;
+pub mod mod_details;
+pub mod mod_filter;
+pub mod mod_loader;
+pub mod mod_settings;
+pub mod mod_updates;
+pub mod mods_folder;
+pub mod mods_watcher;
+pub mod order_edit;
+pub mod order_file;
+pub mod pager;
+pub mod pathway;
+pub mod profiles;
 pub mod settings;
 pub mod util;
+pub mod validate;
 
 pub const BG3_STEAM_ID: u32 = 1086940;
 
@@ -11,7 +35,7 @@ pub const ORIGINAL_URL_REPO: &str = "https://github.com/LaughingLeader/BG3ModMan
 
 pub fn xml_mod_order_module(uuid: &str) -> String {
     format!(
-        r#"<node id=""Module""><attribute id=""UUID"" value=""{}"" type=""FixedString""/></node>"#,
+        r#"<node id="Module"><attribute id="UUID" value="{}" type="FixedString"/></node>"#,
         uuid
     )
 }
@@ -24,19 +48,21 @@ pub fn xml_module_short_desc(
     version64: i64,
 ) -> String {
     format!(
-        r#"<node id=""ModuleShortDesc""><attribute id=""Folder"" value=""{}"" type=""LSString""/><attribute id=""MD5"" value=""{}"" type=""LSString""/><attribute id=""Name"" value=""{}"" type=""LSString""/><attribute id=""UUID"" value=""{}"" type=""FixedString"" /><attribute id=""Version64"" value=""{}"" type=""int64""/></node>"#,
+        r#"<node id="ModuleShortDesc"><attribute id="Folder" value="{}" type="LSString"/><attribute id="MD5" value="{}" type="LSString"/><attribute id="Name" value="{}" type="LSString"/><attribute id="UUID" value="{}" type="FixedString" /><attribute id="Version64" value="{}" type="int64"/></node>"#,
         folder, md5, name, uuid, version64
     )
 }
 
 pub fn xml_mod_settings_template(mod_order: &str, mods: &str) -> String {
     format!(
-        r#"<?xml version=""1.0"" encoding=""UTF-8""?><save><version major=""4"" minor=""0"" revision=""9"" build=""331""/><region id=""ModuleSettings""><node id=""root""><children><node id=""ModOrder""><children>{}</children></node><node id=""Mods""><children>{}</children></node></children></node></region></save>"#,
+        r#"<?xml version="1.0" encoding="UTF-8"?><save><version major="4" minor="0" revision="9" build="331"/><region id="ModuleSettings"><node id="root"><children><node id="ModOrder"><children>{}</children></node><node id="Mods"><children>{}</children></node></children></node></region></save>"#,
         mod_order, mods
     )
 }
 
 pub const MAIN_CAMPAIGN_UUID: &str = "28ac9ce2-2aba-8cda-b3b5-6e922f71b6b8";
+pub const MAIN_CAMPAIGN_FOLDER: &str = "GustavDev";
+pub const MAIN_CAMPAIGN_VERSION: u64 = 144115617576214574;
 pub const GAMEMASTER_UUID: &str = "NotYetAvailableInBG3";
 
 pub const EXTENDER_REPO_URL: &str = "Norbyte/bg3se";
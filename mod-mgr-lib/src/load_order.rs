@@ -0,0 +1,368 @@
+//! Editing the load order: the active mods in the order they're loaded, and the inactive mods.
+//! Mods are identified by their uuid. The edits here don't know anything about the UI, which turns
+//! drags, clicks and key presses into them, so that they can be tested on their own.
+
+/// One of the two lists of mods
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ModList {
+    #[default]
+    Active,
+    Inactive,
+}
+impl ModList {
+    pub fn other(self) -> ModList {
+        match self {
+            ModList::Active => ModList::Inactive,
+            ModList::Inactive => ModList::Active,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LoadOrder {
+    pub active: Vec<String>,
+    pub inactive: Vec<String>,
+}
+impl LoadOrder {
+    pub fn list(&self, list: ModList) -> &[String] {
+        match list {
+            ModList::Active => &self.active,
+            ModList::Inactive => &self.inactive,
+        }
+    }
+
+    fn list_mut(&mut self, list: ModList) -> &mut Vec<String> {
+        match list {
+            ModList::Active => &mut self.active,
+            ModList::Inactive => &mut self.inactive,
+        }
+    }
+
+    /// The list the mod is in, and its index in that list
+    pub fn find(&self, uuid: &str) -> Option<(ModList, usize)> {
+        [ModList::Active, ModList::Inactive]
+            .into_iter()
+            .find_map(|list| Some((list, self.list(list).iter().position(|u| u == uuid)?)))
+    }
+
+    /// The mods that are in the order, sorted by where they are in it.
+    fn sorted(&self, uuids: &[String]) -> Vec<(ModList, usize, String)> {
+        let mut found: Vec<_> = uuids
+            .iter()
+            .filter_map(|uuid| {
+                let (list, idx) = self.find(uuid)?;
+                Some((list, idx, uuid.clone()))
+            })
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Move the mods to `index` in the list, keeping them in the order they were in.
+    /// `index` is a position in the list as it is before the move.
+    pub fn move_mods(&mut self, uuids: &[String], to: ModList, index: usize) {
+        let moving = self.sorted(uuids);
+        let before = moving
+            .iter()
+            .filter(|(list, idx, _)| *list == to && *idx < index)
+            .count();
+
+        for (list, _, uuid) in &moving {
+            self.list_mut(*list).retain(|u| u != uuid);
+        }
+
+        let target = self.list_mut(to);
+        let index = index.saturating_sub(before).min(target.len());
+        target.splice(index..index, moving.into_iter().map(|(_, _, uuid)| uuid));
+    }
+
+    /// Move the mods to where `onto` is, as when they're dragged and dropped onto it.
+    /// Mods dragged down the same list go after it, otherwise they go before it.
+    pub fn drop_mods(&mut self, uuids: &[String], onto: &str) {
+        if uuids.iter().any(|uuid| uuid == onto) {
+            return;
+        }
+        let Some((to, target)) = self.find(onto) else {
+            return;
+        };
+
+        let moving_down = self
+            .sorted(uuids)
+            .first()
+            .is_some_and(|(list, idx, _)| *list == to && *idx < target);
+        let index = if moving_down { target + 1 } else { target };
+        self.move_mods(uuids, to, index);
+    }
+
+    /// Move the mods one place up or down within their list.
+    /// Mods that are already at the edge of the list stay there, along with any directly next to
+    /// them.
+    pub fn shift_mods(&mut self, uuids: &[String], up: bool) {
+        for list in [ModList::Active, ModList::Inactive] {
+            let list = self.list_mut(list);
+            let moving = |uuid: &String| uuids.contains(uuid);
+            if up {
+                for idx in 1..list.len() {
+                    if moving(&list[idx]) && !moving(&list[idx - 1]) {
+                        list.swap(idx, idx - 1);
+                    }
+                }
+            } else {
+                for idx in (0..list.len().saturating_sub(1)).rev() {
+                    if moving(&list[idx]) && !moving(&list[idx + 1]) {
+                        list.swap(idx, idx + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move the mods to the end of the other list, activating inactive mods and deactivating
+    /// active ones.
+    pub fn swap_lists(&mut self, uuids: &[String]) {
+        for (list, _, uuid) in self.sorted(uuids) {
+            self.list_mut(list).retain(|u| *u != uuid);
+            self.list_mut(list.other()).push(uuid);
+        }
+    }
+}
+
+/// The most changes that can be undone
+pub const MAX_UNDO: usize = 100;
+
+/// The previous orders, for undo and redo
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrderHistory {
+    undo: Vec<LoadOrder>,
+    redo: Vec<LoadOrder>,
+}
+impl OrderHistory {
+    /// Change the order, remembering what it was so that the change can be undone.
+    /// Returns whether the order changed.
+    pub fn apply(&mut self, order: &mut LoadOrder, change: impl FnOnce(&mut LoadOrder)) -> bool {
+        let previous = order.clone();
+        change(order);
+        if *order == previous {
+            return false;
+        }
+
+        self.undo.push(previous);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+
+        true
+    }
+
+    /// Returns whether there was anything to undo
+    pub fn undo(&mut self, order: &mut LoadOrder) -> bool {
+        let Some(previous) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(std::mem::replace(order, previous));
+        true
+    }
+
+    /// Returns whether there was anything to redo
+    pub fn redo(&mut self, order: &mut LoadOrder) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(std::mem::replace(order, next));
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget the history, such as when a different load order is loaded.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// The selected mods, which are all in the same list.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub list: ModList,
+    pub selected: Vec<String>,
+    /// Where shift-click ranges start from
+    anchor: Option<String>,
+}
+impl Selection {
+    pub fn is_selected(&self, uuid: &str) -> bool {
+        self.selected.iter().any(|u| u == uuid)
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+
+    /// Unselect the mod, such as when it is no longer installed.
+    pub fn deselect(&mut self, uuid: &str) {
+        self.selected.retain(|u| u != uuid);
+        if self.anchor.as_deref() == Some(uuid) {
+            self.anchor = None;
+        }
+    }
+
+    /// Select just the mods, in the list.
+    pub fn select(&mut self, list: ModList, uuids: Vec<String>) {
+        self.list = list;
+        self.anchor = uuids.first().cloned();
+        self.selected = uuids;
+    }
+
+    /// Update the selection for a click on a mod.
+    /// `shift` selects the range from the last clicked mod, `ctrl` toggles the mod. Clicking a mod
+    /// in the other list starts a new selection there.
+    pub fn click(&mut self, order: &LoadOrder, uuid: &str, shift: bool, ctrl: bool) {
+        let Some((list, idx)) = order.find(uuid) else {
+            return;
+        };
+        if list != self.list {
+            self.select(list, vec![uuid.to_string()]);
+            return;
+        }
+
+        let anchor_idx = self
+            .anchor
+            .as_deref()
+            .and_then(|anchor| order.find(anchor))
+            .filter(|(anchor_list, _)| *anchor_list == list)
+            .map(|(_, anchor_idx)| anchor_idx);
+
+        match anchor_idx {
+            Some(anchor_idx) if shift => {
+                let range = &order.list(list)[anchor_idx.min(idx)..=anchor_idx.max(idx)];
+                if !ctrl {
+                    self.selected.clear();
+                }
+                for uuid in range {
+                    if !self.is_selected(uuid) {
+                        self.selected.push(uuid.clone());
+                    }
+                }
+            }
+            _ if ctrl => {
+                if self.is_selected(uuid) {
+                    self.selected.retain(|u| u != uuid);
+                } else {
+                    self.selected.push(uuid.to_string());
+                }
+                self.anchor = Some(uuid.to_string());
+            }
+            _ => self.select(list, vec![uuid.to_string()]),
+        }
+    }
+
+    /// The selected mods that are in the selection's list, in the order they are in it.
+    pub fn ordered(&self, order: &LoadOrder) -> Vec<String> {
+        order
+            .list(self.list)
+            .iter()
+            .filter(|uuid| self.is_selected(uuid))
+            .cloned()
+            .collect()
+    }
+
+    /// Update the selection after the selected mods were swapped to the other list, with `index`
+    /// being where the first of them was.
+    /// If `follow` then the selection moves with them, otherwise the mod that took their place is
+    /// selected, so that swapping again continues down the list.
+    pub fn swapped(&mut self, order: &LoadOrder, index: usize, follow: bool) {
+        if follow {
+            self.list = self.list.other();
+            return;
+        }
+
+        let list = order.list(self.list);
+        let next = list.get(index).or_else(|| list.last()).cloned();
+        self.select(self.list, next.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &str) -> Vec<String> {
+        ids.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_load_order_edits() {
+        let mut order = LoadOrder {
+            active: ids("a b c d"),
+            inactive: ids("x y"),
+        };
+        let mut history = OrderHistory::default();
+
+        history.apply(&mut order, |o| o.drop_mods(&ids("a b"), "c"));
+        assert_eq!(order.active, ids("c a b d"));
+        history.apply(&mut order, |o| o.drop_mods(&ids("d"), "c"));
+        assert_eq!(order.active, ids("d c a b"));
+        history.apply(&mut order, |o| o.drop_mods(&ids("y"), "a"));
+        assert_eq!(order.active, ids("d c y a b"));
+        assert_eq!(order.inactive, ids("x"));
+
+        history.apply(&mut order, |o| o.shift_mods(&ids("d y"), true));
+        assert_eq!(order.active, ids("d y c a b"));
+        history.apply(&mut order, |o| o.shift_mods(&ids("a b"), false));
+        assert_eq!(order.active, ids("d y c a b"));
+        assert!(!history.apply(&mut order, |o| o.shift_mods(&ids("d"), true)));
+
+        history.apply(&mut order, |o| o.swap_lists(&ids("c x")));
+        assert_eq!(order.active, ids("d y a b x"));
+        assert_eq!(order.inactive, ids("c"));
+
+        assert!(history.undo(&mut order));
+        assert!(history.undo(&mut order));
+        assert_eq!(order.active, ids("d c y a b"));
+        assert!(history.redo(&mut order));
+        assert_eq!(order.active, ids("d y c a b"));
+        history.apply(&mut order, |o| o.move_mods(&ids("b"), ModList::Inactive, 0));
+        assert!(!history.can_redo());
+        assert_eq!(order.inactive, ids("b x"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut order = LoadOrder {
+            active: ids("a b c d e"),
+            inactive: ids("x y"),
+        };
+        let mut selection = Selection::default();
+
+        selection.click(&order, "b", false, false);
+        selection.click(&order, "d", true, false);
+        assert_eq!(selection.ordered(&order), ids("b c d"));
+        selection.click(&order, "c", false, true);
+        selection.click(&order, "a", false, true);
+        assert_eq!(selection.ordered(&order), ids("a b d"));
+        selection.click(&order, "y", true, false);
+        assert_eq!(selection.list, ModList::Inactive);
+        assert_eq!(selection.ordered(&order), ids("y"));
+
+        selection.select(ModList::Active, ids("b c"));
+        order.swap_lists(&selection.ordered(&order));
+        selection.swapped(&order, 1, false);
+        assert_eq!(selection.ordered(&order), ids("d"));
+        order.swap_lists(&selection.ordered(&order));
+        selection.swapped(&order, 1, true);
+        assert_eq!(selection.list, ModList::Inactive);
+        assert_eq!(selection.ordered(&order), ids("d"));
+
+        selection.deselect("d");
+        assert!(selection.ordered(&order).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! MD5 hashes of mod paks.
+//! The game stores the MD5 of each mod in `modsettings.lsx`, and uses it when checking that
+//! everyone has the same mods in multiplayer. Hashing every pak on each startup is slow for large
+//! mods, so the hashes are cached by file size and modification time.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    meta::file_modified_time,
+    mod_data::ModData,
+    settings::settings_dir,
+};
+
+pub const MD5_CACHE_FILE_NAME: &str = "md5_cache.json";
+
+/// Compute the MD5 of a file as a lowercase hex string.
+pub fn compute_md5(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Md5::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let len = file.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..len]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    /// Seconds since the unix epoch
+    modified: u64,
+    md5: String,
+}
+
+/// The hash of a file, and when it was last modified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    pub md5: String,
+    pub last_modified: Option<u64>,
+}
+
+/// Index of file hashes, stored in the settings directory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Md5Cache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    dirty: bool,
+}
+impl Md5Cache {
+    /// Load the cache from the settings directory. If it does not exist or is invalid, then an
+    /// empty cache is returned.
+    pub fn load() -> Md5Cache {
+        Md5Cache::load_from(settings_dir().join(MD5_CACHE_FILE_NAME))
+    }
+
+    pub fn load_from(path: PathBuf) -> Md5Cache {
+        let mut cache = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid MD5 cache at {path:?}: {err}");
+                Md5Cache::default()
+            }),
+            Err(_) => Md5Cache::default(),
+        };
+        cache.path = path;
+
+        cache
+    }
+
+    /// Save the cache if it has changed since it was loaded.
+    pub fn save(&mut self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let text = serde_json::to_string(self)?;
+        std::fs::write(&self.path, text)?;
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    /// Get the hash of the file, only hashing it if it has changed since it was last hashed.
+    pub fn hash(&mut self, path: &Path) -> std::io::Result<FileHash> {
+        let size = path.metadata()?.len();
+        let last_modified = file_modified_time(path);
+
+        if let Some(entry) = self.entries.get(path) {
+            if entry.size == size && Some(entry.modified) == last_modified {
+                return Ok(FileHash {
+                    md5: entry.md5.clone(),
+                    last_modified,
+                });
+            }
+        }
+
+        let md5 = compute_md5(path)?;
+        self.entries.insert(
+            path.to_owned(),
+            CacheEntry {
+                size,
+                modified: last_modified.unwrap_or(0),
+                md5: md5.clone(),
+            },
+        );
+        self.dirty = true;
+
+        Ok(FileHash { md5, last_modified })
+    }
+
+    /// Fill in the `md5` and `last_modified` of the mod from its pak.
+    pub fn fill_mod_data(&mut self, data: &mut ModData) -> std::io::Result<()> {
+        let hash = self.hash(&data.file_path)?;
+        data.md5 = hash.md5;
+        data.last_modified = hash.last_modified;
+
+        Ok(())
+    }
+
+    /// Remove entries for files that no longer exist.
+    pub fn prune(&mut self) {
+        let len = self.entries.len();
+        self.entries.retain(|path, _| path.is_file());
+        if self.entries.len() != len {
+            self.dirty = true;
+        }
+    }
+}
+
+/// How a mod's pak compares to when the load order was saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PakChange {
+    Unchanged,
+    /// The pak has been replaced or modified since the load order was saved
+    Changed {
+        saved: String,
+        current: String,
+    },
+    /// The load order did not have a hash for the mod, which is common for mod lists that weren't
+    /// saved by a mod manager.
+    NoSavedHash,
+}
+
+pub fn check_pak_change(saved_md5: &str, current_md5: &str) -> PakChange {
+    if saved_md5.is_empty() {
+        PakChange::NoSavedHash
+    } else if saved_md5.eq_ignore_ascii_case(current_md5) {
+        PakChange::Unchanged
+    } else {
+        PakChange::Changed {
+            saved: saved_md5.to_string(),
+            current: current_md5.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mod_settings::{mod_settings_xml, parse_mod_settings};
+
+    #[test]
+    fn test_md5_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let pak = dir.join("Test.pak");
+        std::fs::write(&pak, "hello").unwrap();
+        let modified = pak.metadata().unwrap().modified().unwrap();
+
+        let mut cache = Md5Cache::load_from(dir.join(MD5_CACHE_FILE_NAME));
+        let hash = cache.hash(&pak).unwrap();
+        assert_eq!(hash.md5, "5d41402abc4b2a76b9719d911017c592");
+        cache.save().unwrap();
+
+        // Cached by size and time, so a same size change with the same modification time is not
+        // noticed, but a size change is.
+        let mut cache = Md5Cache::load_from(dir.join(MD5_CACHE_FILE_NAME));
+        assert!(cache.entries.contains_key(&pak));
+        std::fs::write(&pak, "jello").unwrap();
+        File::options()
+            .write(true)
+            .open(&pak)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(cache.hash(&pak).unwrap().md5, hash.md5);
+
+        std::fs::write(&pak, "hello world").unwrap();
+        let hash = cache.hash(&pak).unwrap();
+        assert_eq!(hash.md5, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    }
+
+    #[test]
+    fn test_check_pak_change() {
+        let mut a = ModData::new("A", "uuid-a", "A", 1, "Add-on", "", "", "", "");
+        a.md5 = "aaaa".to_string();
+        let mut b = ModData::new("B & Co", "uuid-b", "B", 2, "Add-on", "", "", "", "");
+        b.md5 = "bbbb".to_string();
+        let mods = vec![a, b];
+
+        let saved = parse_mod_settings(&mod_settings_xml(&mods)).unwrap();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved[1].name, "B & Co");
+        assert_eq!(
+            check_pak_change(&saved[0].md5, "AAAA"),
+            PakChange::Unchanged
+        );
+        assert_eq!(
+            check_pak_change(&saved[1].md5, "cccc"),
+            PakChange::Changed {
+                saved: "bbbb".to_string(),
+                current: "cccc".to_string()
+            }
+        );
+        assert_eq!(check_pak_change("", "cccc"), PakChange::NoSavedHash);
+    }
+}
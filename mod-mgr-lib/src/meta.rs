@@ -0,0 +1,411 @@
+//! Reading the `meta.lsx` file of a mod, which holds its UUID, name, version, dependencies, etc.
+//! In a pak it is at `Mods/<Folder>/meta.lsx`.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ls::pak::{
+    common::{PackagedFileContentError, PackagedFileInfo},
+    read_package, Package, PackageError,
+};
+use lsf::lsx::{parse_lsx, Node};
+use serde::Deserialize;
+
+use crate::mod_data::{DivinityModDependencyData, ModData, ModVersion};
+
+pub const META_FILE_NAME: &str = "meta.lsx";
+
+#[derive(Debug)]
+pub enum MetaError {
+    Io(std::io::Error),
+    Package(PackageError),
+    Content(PackagedFileContentError),
+    /// The pak does not have a `Mods/<Folder>/meta.lsx` file
+    NoMeta,
+    Xml(String),
+    Json(serde_json::Error),
+    /// The meta file does not have a `ModuleInfo` node
+    NoModuleInfo,
+    /// The `ModuleInfo` does not have a UUID
+    NoUuid,
+}
+impl std::fmt::Display for MetaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaError::Io(err) => write!(f, "{err}"),
+            MetaError::Package(err) => write!(f, "Failed to read pak: {err:?}"),
+            MetaError::Content(err) => write!(f, "Failed to read {META_FILE_NAME}: {err:?}"),
+            MetaError::NoMeta => write!(f, "No {META_FILE_NAME} found"),
+            MetaError::Xml(err) => write!(f, "Failed to parse {META_FILE_NAME}: {err}"),
+            MetaError::Json(err) => write!(f, "Failed to parse mod info: {err}"),
+            MetaError::NoModuleInfo => write!(f, "{META_FILE_NAME} has no ModuleInfo"),
+            MetaError::NoUuid => write!(f, "{META_FILE_NAME} has no UUID"),
+        }
+    }
+}
+impl std::error::Error for MetaError {}
+impl From<std::io::Error> for MetaError {
+    fn from(e: std::io::Error) -> Self {
+        MetaError::Io(e)
+    }
+}
+impl From<PackageError> for MetaError {
+    fn from(e: PackageError) -> Self {
+        MetaError::Package(e)
+    }
+}
+impl From<PackagedFileContentError> for MetaError {
+    fn from(e: PackagedFileContentError) -> Self {
+        MetaError::Content(e)
+    }
+}
+impl From<serde_json::Error> for MetaError {
+    fn from(e: serde_json::Error) -> Self {
+        MetaError::Json(e)
+    }
+}
+
+/// The information in a mod's `meta.lsx`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModMeta {
+    pub data: ModData,
+    pub dependencies: Vec<DivinityModDependencyData>,
+}
+
+/// Find the value of an attribute on the node
+fn attr<'a>(node: &'a Node<'_>, id: &str) -> Option<&'a str> {
+    node.attrs
+        .iter()
+        .find(|attr| attr.id == id)
+        .and_then(|attr| attr.value.as_deref())
+}
+
+fn children<'a, 'b>(node: &'a Node<'b>) -> impl Iterator<Item = &'a Node<'b>> {
+    node.children
+        .iter()
+        .flat_map(|children| children.elems.iter().flatten())
+}
+
+fn child<'a, 'b>(node: &'a Node<'b>, id: &str) -> Option<&'a Node<'b>> {
+    children(node).find(|child| child.id == id)
+}
+
+/// Get the version of the module, preferring `Version64` over the older 32-bit `Version`.
+fn node_version(node: &Node<'_>) -> ModVersion {
+    let version = attr(node, "Version64")
+        .or_else(|| attr(node, "Version"))
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+
+    ModVersion::from(version as u64)
+}
+
+/// Parse the text of a `meta.lsx` file.
+pub fn parse_meta_lsx(text: &str) -> Result<ModMeta, MetaError> {
+    let text = text.trim_start_matches('\u{feff}');
+    let save = parse_lsx(text).map_err(|err| MetaError::Xml(err.to_string()))?;
+
+    let root = &save.region.node;
+    let module_info = child(root, "ModuleInfo").ok_or(MetaError::NoModuleInfo)?;
+
+    let uuid = attr(module_info, "UUID").ok_or(MetaError::NoUuid)?;
+    let mut data = ModData {
+        uuid: uuid.to_string(),
+        name: attr(module_info, "Name").unwrap_or_default().to_string(),
+        folder: attr(module_info, "Folder").unwrap_or_default().to_string(),
+        description: attr(module_info, "Description")
+            .unwrap_or_default()
+            .to_string(),
+        author: attr(module_info, "Author").unwrap_or_default().to_string(),
+        md5: attr(module_info, "MD5").unwrap_or_default().to_string(),
+        mod_type: attr(module_info, "Type").unwrap_or_default().to_string(),
+        version: node_version(module_info),
+        header_version: ModVersion {
+            major: save.version.major as u8,
+            minor: save.version.minor as u8,
+            revision: save.version.revision as u16,
+            build: save.version.build,
+        },
+        ..Default::default()
+    };
+
+    if let Some(publish_version) = child(module_info, "PublishVersion") {
+        data.publish_version = node_version(publish_version);
+    }
+
+    if let Some(target_modes) = child(module_info, "TargetModes") {
+        let targets: Vec<&str> = children(target_modes)
+            .filter_map(|target| attr(target, "Object"))
+            .collect();
+        data.targets = targets.join(";");
+    }
+
+    if let Some(tags) = attr(module_info, "Tags") {
+        data.add_tags(tags.split(';').map(|tag| tag.trim().to_string()));
+    }
+
+    let dependencies = child(root, "Dependencies")
+        .into_iter()
+        .flat_map(children)
+        .filter(|dep| dep.id == "ModuleShortDesc")
+        .filter_map(|dep| {
+            Some(DivinityModDependencyData {
+                uuid: attr(dep, "UUID")?.to_string(),
+                name: attr(dep, "Name").unwrap_or_default().to_string(),
+                folder: attr(dep, "Folder").unwrap_or_default().to_string(),
+                md5: attr(dep, "MD5").unwrap_or_default().to_string(),
+                version: node_version(dep),
+            })
+        })
+        .collect();
+
+    Ok(ModMeta { data, dependencies })
+}
+
+/// Whether the path within a pak is a mod's meta file, `Mods/<Folder>/meta.lsx`
+pub fn is_meta_file(name: &str) -> bool {
+    let mut parts = name.split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some(mods), Some(folder), Some(meta), None)
+            if mods.eq_ignore_ascii_case("Mods")
+                && !folder.is_empty()
+                && meta.eq_ignore_ascii_case(META_FILE_NAME)
+    )
+}
+
+/// Find the `meta.lsx` within the package.
+pub fn find_meta_file(package: &Package) -> Option<&PackagedFileInfo> {
+    package.files.iter().find_map(|file| match file {
+        ls::pak::common::FileInfo::Packaged(info) if is_meta_file(&info.name) => Some(info),
+        _ => None,
+    })
+}
+
+/// Get the modification time of the file as seconds since the unix epoch.
+pub fn file_modified_time(path: &Path) -> Option<u64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    system_time_secs(modified)
+}
+
+pub fn system_time_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Read the mod information from the `meta.lsx` within a pak.
+pub fn read_pak_meta(path: &Path) -> Result<ModMeta, MetaError> {
+    let file = BufReader::new(File::open(path)?);
+    let package = read_package(file, path, false)?;
+
+    let meta_file = find_meta_file(&package).ok_or(MetaError::NoMeta)?;
+    let content = package.read_file(meta_file)?;
+    let text = String::from_utf8_lossy(&content);
+
+    let mut meta = parse_meta_lsx(&text)?;
+    meta.data.file_path = path.to_owned();
+    meta.data.last_modified = file_modified_time(path);
+    meta.data.is_user_mod = true;
+
+    Ok(meta)
+}
+
+/// The `info.json` that mods are commonly distributed with, which is generated by BG3 Mod
+/// Manager's export.
+/// This is used for mods that don't have a `meta.lsx`, like mods that only override game files.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ModInfoJson {
+    pub mods: Vec<ModInfoJsonEntry>,
+    #[serde(rename = "MD5")]
+    pub md5: String,
+}
+impl ModInfoJson {
+    pub fn parse(text: &str) -> Result<ModInfoJson, MetaError> {
+        Ok(serde_json::from_str(text.trim_start_matches('\u{feff}'))?)
+    }
+
+    /// Find the entry for the pak with the given file name, by matching it with the folder or
+    /// name of the mod.
+    /// If there is only one entry, that is used.
+    pub fn find_for_pak(&self, pak_name: &str) -> Option<&ModInfoJsonEntry> {
+        let stem = Path::new(pak_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+
+        self.mods
+            .iter()
+            .find(|entry| {
+                entry.folder.eq_ignore_ascii_case(&stem) || entry.name.eq_ignore_ascii_case(&stem)
+            })
+            .or_else(|| {
+                if self.mods.len() == 1 {
+                    self.mods.first()
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ModInfoJsonEntry {
+    pub author: String,
+    pub name: String,
+    pub folder: String,
+    /// This is sometimes the int64 version and sometimes a version string like `1.0.0.0`
+    pub version: Option<serde_json::Value>,
+    pub description: String,
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+}
+impl ModInfoJsonEntry {
+    pub fn version(&self) -> ModVersion {
+        match &self.version {
+            Some(serde_json::Value::Number(n)) => ModVersion::from(n.as_u64().unwrap_or(0)),
+            Some(serde_json::Value::String(s)) => {
+                if let Ok(v) = s.parse::<u64>() {
+                    return ModVersion::from(v);
+                }
+
+                // Parts too large to be packed are clamped rather than wrapped
+                let mut parts = s.split('.').map(|p| p.trim().parse::<u32>().unwrap_or(0));
+                let mut next = |max: u32| {
+                    let part = parts.next().unwrap_or(0);
+                    if part > max {
+                        eprintln!("Version {s:?} of {} has a part over {max}", self.name);
+                    }
+                    part.min(max)
+                };
+                ModVersion {
+                    major: next(u8::MAX.into()) as u8,
+                    minor: next(u8::MAX.into()) as u8,
+                    revision: next(u16::MAX.into()) as u16,
+                    build: next(ModVersion::MAX_BUILD),
+                }
+            }
+            _ => ModVersion::default(),
+        }
+    }
+
+    pub fn to_meta(&self) -> ModMeta {
+        ModMeta {
+            data: ModData {
+                uuid: self.uuid.clone(),
+                name: self.name.clone(),
+                folder: self.folder.clone(),
+                description: self.description.clone(),
+                author: self.author.clone(),
+                version: self.version(),
+                is_user_mod: true,
+                ..Default::default()
+            },
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meta_lsx() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<save>
+    <version major="4" minor="0" revision="9" build="328"/>
+    <region id="Config">
+        <node id="root">
+            <children>
+                <node id="Dependencies">
+                    <children>
+                        <node id="ModuleShortDesc">
+                            <attribute id="Folder" type="LSString" value="Other"/>
+                            <attribute id="MD5" type="LSString" value=""/>
+                            <attribute id="Name" type="LSString" value="Other Mod"/>
+                            <attribute id="UUID" type="FixedString" value="11111111-2222-3333-4444-555555555555"/>
+                            <attribute id="Version64" type="int64" value="36028797018963968"/>
+                        </node>
+                    </children>
+                </node>
+                <node id="ModuleInfo">
+                    <attribute id="Author" type="LSString" value="Someone"/>
+                    <attribute id="Description" type="LSString" value="Does things"/>
+                    <attribute id="Folder" type="LSString" value="MyMod"/>
+                    <attribute id="Name" type="LSString" value="My Mod"/>
+                    <attribute id="Tags" type="LSString" value="Spells;Classes"/>
+                    <attribute id="Type" type="FixedString" value="Add-on"/>
+                    <attribute id="UUID" type="FixedString" value="aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee"/>
+                    <attribute id="Version64" type="int64" value="36169534507319297"/>
+                    <children>
+                        <node id="PublishVersion">
+                            <attribute id="Version64" type="int64" value="36028797018963968"/>
+                        </node>
+                        <node id="TargetModes">
+                            <children>
+                                <node id="Target">
+                                    <attribute id="Object" type="FixedString" value="Story"/>
+                                </node>
+                            </children>
+                        </node>
+                    </children>
+                </node>
+            </children>
+        </node>
+    </region>
+</save>"#;
+
+        let meta = parse_meta_lsx(text).unwrap();
+        assert_eq!(meta.data.uuid, "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee");
+        assert_eq!(meta.data.name, "My Mod");
+        assert_eq!(meta.data.folder, "MyMod");
+        assert_eq!(meta.data.author, "Someone");
+        assert_eq!(meta.data.mod_type, "Add-on");
+        assert_eq!(meta.data.targets, "Story");
+        assert_eq!(meta.data.tags, vec!["Classes", "Spells"]);
+        assert_eq!(meta.data.version.to_string(), "1.1.0.1");
+        assert_eq!(meta.data.publish_version.to_string(), "1.0.0.0");
+        assert_eq!(meta.data.header_version.major, 4);
+
+        assert_eq!(meta.dependencies.len(), 1);
+        assert_eq!(meta.dependencies[0].folder, "Other");
+    }
+
+    #[test]
+    fn test_info_json_version() {
+        let entry = |version: serde_json::Value| ModInfoJsonEntry {
+            version: Some(version),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            entry(serde_json::json!("1.2.3.4")).version().to_string(),
+            "1.2.3.4"
+        );
+        assert_eq!(
+            entry(serde_json::json!(36028797018963968u64))
+                .version()
+                .to_string(),
+            "1.0.0.0"
+        );
+        assert_eq!(
+            entry(serde_json::json!("1.300.70000.4294967295"))
+                .version()
+                .to_string(),
+            "1.255.65535.2147483647"
+        );
+    }
+
+    #[test]
+    fn test_is_meta_file() {
+        assert!(is_meta_file("Mods/MyMod/meta.lsx"));
+        assert!(is_meta_file("mods/MyMod/Meta.lsx"));
+        assert!(!is_meta_file("Mods/MyMod/Other/meta.lsx"));
+        assert!(!is_meta_file("Public/MyMod/meta.lsx"));
+    }
+}
@@ -179,19 +179,25 @@ impl ModData {
 }
 
 // C#'s DivinityModVersion2
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Packed into a `u64` as LSLib does: 8 bits of major at bit 55, 8 of minor at 47, 16 of revision
+/// at 31 and 31 of build.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ModVersion {
     pub major: u8,
     pub minor: u8,
     pub revision: u16,
     pub build: u32,
 }
+impl ModVersion {
+    /// The largest build that fits in its 31 bits
+    pub const MAX_BUILD: u32 = 0x7FFFFFFF;
+}
 impl From<u64> for ModVersion {
     fn from(v: u64) -> Self {
         let major = (v >> 55) as u8;
         let minor = ((v >> 47) & 0xff) as u8;
-        let revision = ((v >> 32) & 0xffff) as u16;
-        let build = (v & 0x7FFFFFFF) as u32;
+        let revision = ((v >> 31) & 0xffff) as u16;
+        let build = (v & ModVersion::MAX_BUILD as u64) as u32;
 
         Self {
             major,
@@ -206,11 +212,40 @@ impl From<ModVersion> for u64 {
         let mut res = 0u64;
         res |= (v.major as u64) << 55;
         res |= (v.minor as u64) << 47;
-        res |= (v.revision as u64) << 32;
+        res |= (v.revision as u64) << 31;
         res |= v.build as u64;
         res
     }
 }
+/// Parse a version written as `major.minor.revision.build`, where missing trailing parts are 0, or
+/// as the packed 64 bit integer used in `meta.lsx`.
+impl std::str::FromStr for ModVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.contains('.') {
+            return Ok(ModVersion::from(s.parse::<u64>()?));
+        }
+
+        let mut parts = s.split('.');
+        let mut next = || parts.next().unwrap_or("0");
+        let version = ModVersion {
+            major: next().parse()?,
+            minor: next().parse()?,
+            revision: next().parse()?,
+            build: next().parse()?,
+        };
+        if parts.next().is_some() {
+            return Err(ParseVersionError::TooManyParts);
+        }
+        if version.build > ModVersion::MAX_BUILD {
+            return Err(ParseVersionError::BuildTooLarge);
+        }
+
+        Ok(version)
+    }
+}
 impl std::fmt::Display for ModVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -221,6 +256,37 @@ impl std::fmt::Display for ModVersion {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseVersionError {
+    Int(std::num::ParseIntError),
+    /// There were more parts than `major.minor.revision.build`
+    TooManyParts,
+    /// The build was larger than [`ModVersion::MAX_BUILD`]
+    BuildTooLarge,
+}
+impl std::fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseVersionError::Int(err) => write!(f, "Invalid version number: {err}"),
+            ParseVersionError::TooManyParts => {
+                write!(
+                    f,
+                    "Versions have at most 4 parts, major.minor.revision.build"
+                )
+            }
+            ParseVersionError::BuildTooLarge => {
+                write!(f, "The build can be at most {}", ModVersion::MAX_BUILD)
+            }
+        }
+    }
+}
+impl std::error::Error for ParseVersionError {}
+impl From<std::num::ParseIntError> for ParseVersionError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        ParseVersionError::Int(e)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Visibility {
     #[default]
@@ -0,0 +1,138 @@
+//! What the details panel shows about a mod beyond its [`ModData`](crate::mod_data::ModData):
+//! whether its dependencies are met, and the order to check it for conflicts in.
+
+use crate::mod_data::DivinityModDependencyData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyState {
+    /// Enabled, and loaded before the mod if it is enabled
+    Satisfied,
+    /// One of the game's own modules, which are always loaded
+    BaseGame,
+    /// Enabled, but loaded after the mod
+    LoadedAfter,
+    /// Installed but not enabled
+    NotEnabled,
+    NotInstalled,
+}
+impl DependencyState {
+    pub fn is_satisfied(self) -> bool {
+        matches!(self, DependencyState::Satisfied | DependencyState::BaseGame)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            DependencyState::Satisfied => "Enabled",
+            DependencyState::BaseGame => "Part of the game",
+            DependencyState::LoadedAfter => "Loaded after this mod",
+            DependencyState::NotEnabled => "Not enabled",
+            DependencyState::NotInstalled => "Not installed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyStatus {
+    pub dependency: DivinityModDependencyData,
+    pub state: DependencyState,
+}
+
+fn position(list: &[String], uuid: &str) -> Option<usize> {
+    list.iter().position(|u| u.eq_ignore_ascii_case(uuid))
+}
+
+/// Check each of the mod's dependencies against the load order.
+/// `active` are the uuids of the enabled mods in load order, `installed` the uuids of the mods in
+/// the mods folder, and `ignored` the uuids of the game's own modules.
+pub fn dependency_statuses(
+    uuid: &str,
+    dependencies: &[DivinityModDependencyData],
+    active: &[String],
+    installed: &[String],
+    ignored: &[String],
+) -> Vec<DependencyStatus> {
+    let mod_idx = position(active, uuid);
+    dependencies
+        .iter()
+        .map(|dependency| {
+            let state = if position(ignored, &dependency.uuid).is_some() {
+                DependencyState::BaseGame
+            } else {
+                match (position(active, &dependency.uuid), mod_idx) {
+                    (Some(dep_idx), Some(mod_idx)) if dep_idx > mod_idx => {
+                        DependencyState::LoadedAfter
+                    }
+                    (Some(_), _) => DependencyState::Satisfied,
+                    (None, _) if position(installed, &dependency.uuid).is_some() => {
+                        DependencyState::NotEnabled
+                    }
+                    (None, _) => DependencyState::NotInstalled,
+                }
+            };
+
+            DependencyStatus {
+                dependency: dependency.clone(),
+                state,
+            }
+        })
+        .collect()
+}
+
+/// The mods to check for conflicts with the mod, in load order.
+/// A mod that isn't enabled is checked as if it were enabled last, to show what it would override.
+pub fn conflict_order(uuid: &str, active: &[String]) -> Vec<String> {
+    let mut order = active.to_vec();
+    if position(active, uuid).is_none() {
+        order.push(uuid.to_string());
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &str) -> Vec<String> {
+        ids.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_dependency_statuses() {
+        let dependencies: Vec<_> = ["base", "a", "c", "x", "y"]
+            .iter()
+            .map(|uuid| DivinityModDependencyData {
+                uuid: uuid.to_string(),
+                name: uuid.to_string(),
+                folder: uuid.to_string(),
+                md5: String::new(),
+                version: Default::default(),
+            })
+            .collect();
+        let active = ids("a b c");
+        let installed = ids("a b c x");
+        let ignored = ids("base");
+
+        let states: Vec<_> = dependency_statuses("b", &dependencies, &active, &installed, &ignored)
+            .into_iter()
+            .map(|status| status.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                DependencyState::BaseGame,
+                DependencyState::Satisfied,
+                DependencyState::LoadedAfter,
+                DependencyState::NotEnabled,
+                DependencyState::NotInstalled,
+            ]
+        );
+
+        // Not enabled, so the order doesn't matter
+        let states = dependency_statuses("x", &dependencies, &active, &installed, &ignored);
+        assert_eq!(states[2].state, DependencyState::Satisfied);
+
+        assert_eq!(conflict_order("b", &active), ids("a b c"));
+        assert_eq!(conflict_order("x", &active), ids("a b c x"));
+    }
+}
@@ -0,0 +1,230 @@
+//! The search box filter for the mod lists.
+//! A filter is a list of space separated terms, all of which have to match:
+//! - `text` matches the mod's name, author or description
+//! - `tag:text` matches one of the mod's tags
+//! - `author:text` matches the mod's author
+//! - `uuid:text` matches the mod's uuid
+//! - `se:yes` / `se:no` matches mods that do / don't require the Script Extender
+//!
+//! Matching ignores case, and values can be quoted to include spaces, such as `author:"Some One"`.
+
+use std::ops::Range;
+
+use crate::mod_data::ModData;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterTerm {
+    Text(String),
+    Tag(String),
+    Author(String),
+    Uuid(String),
+    /// Whether the mod requires the Script Extender
+    Extender(bool),
+}
+
+/// The fields of a mod that are shown, and so can have their matches highlighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Name,
+    Author,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModFilter {
+    pub terms: Vec<FilterTerm>,
+}
+impl ModFilter {
+    pub fn parse(query: &str) -> ModFilter {
+        let terms = split_terms(query)
+            .into_iter()
+            .filter_map(|token| parse_term(&token))
+            .collect();
+
+        ModFilter { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether the mod matches every term of the filter.
+    pub fn matches(&self, data: &ModData, requires_extender: bool) -> bool {
+        self.terms.iter().all(|term| match term {
+            FilterTerm::Text(text) => {
+                contains(&data.display_name(), text)
+                    || contains(&data.author, text)
+                    || contains(&data.description, text)
+            }
+            FilterTerm::Tag(tag) => data.tags.iter().any(|t| contains(t, tag)),
+            FilterTerm::Author(author) => contains(&data.author, author),
+            FilterTerm::Uuid(uuid) => contains(&data.uuid, uuid),
+            FilterTerm::Extender(required) => requires_extender == *required,
+        })
+    }
+
+    /// The byte ranges of `text` that matched the filter, sorted and without overlaps.
+    pub fn highlights(&self, field: FilterField, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        for term in &self.terms {
+            let needle = match (term, field) {
+                (FilterTerm::Text(needle), _) => needle,
+                (FilterTerm::Author(needle), FilterField::Author) => needle,
+                _ => continue,
+            };
+            ranges.extend(find_all(text, needle));
+        }
+
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+}
+
+/// Split the text into the parts that are inside and outside of the highlighted ranges, such as
+/// from [`ModFilter::highlights`].
+pub fn split_highlights<'a>(text: &'a str, ranges: &[Range<usize>]) -> Vec<(&'a str, bool)> {
+    let mut parts = Vec::new();
+    let mut pos = 0;
+    for range in ranges {
+        if range.start > pos {
+            parts.push((&text[pos..range.start], false));
+        }
+        parts.push((&text[range.clone()], true));
+        pos = range.end;
+    }
+    if pos < text.len() {
+        parts.push((&text[pos..], false));
+    }
+
+    parts
+}
+
+/// Split the query on spaces outside of quotes, removing the quotes.
+fn split_terms(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn parse_term(token: &str) -> Option<FilterTerm> {
+    let token = token.to_ascii_lowercase();
+    let Some((key, value)) = token.split_once(':') else {
+        return Some(FilterTerm::Text(token));
+    };
+
+    let value = value.to_string();
+    let term = match key {
+        "tag" => FilterTerm::Tag(value),
+        "author" => FilterTerm::Author(value),
+        "uuid" => FilterTerm::Uuid(value),
+        "se" => match value.as_str() {
+            "" | "yes" | "y" | "true" | "1" => FilterTerm::Extender(true),
+            "no" | "n" | "false" | "0" => FilterTerm::Extender(false),
+            _ => return Some(FilterTerm::Text(token)),
+        },
+        // Not a filter key, such as a name with a colon in it
+        _ => return Some(FilterTerm::Text(token)),
+    };
+
+    // A key without a value, such as while typing, filters nothing
+    match &term {
+        FilterTerm::Tag(v) | FilterTerm::Author(v) | FilterTerm::Uuid(v) if v.is_empty() => None,
+        _ => Some(term),
+    }
+}
+
+/// Case insensitive `contains`. The needle should already be lowercase.
+fn contains(text: &str, needle: &str) -> bool {
+    text.to_ascii_lowercase().contains(needle)
+}
+
+/// The ranges of every match of the lowercase needle in the text.
+/// Only ascii is lowercased, so the byte offsets are the same as in the original text.
+fn find_all(text: &str, needle: &str) -> Vec<Range<usize>> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let haystack = text.to_ascii_lowercase();
+    haystack
+        .match_indices(needle)
+        .map(|(start, _)| start..start + needle.len())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_filter() {
+        let filter = ModFilter::parse(r#"short  author:"log os" tag:rest se:no uuid:"#);
+        assert_eq!(
+            filter.terms,
+            vec![
+                FilterTerm::Text("short".to_string()),
+                FilterTerm::Author("log os".to_string()),
+                FilterTerm::Tag("rest".to_string()),
+                FilterTerm::Extender(false),
+            ]
+        );
+
+        let mut data = ModData::new(
+            "8 More Short Rests",
+            "uuid-a",
+            "ShortRests",
+            0,
+            "Add-on",
+            "",
+            "Log Os",
+            "Adds more short rests",
+            "Rest",
+        );
+        assert!(filter.matches(&data, false));
+        assert!(!filter.matches(&data, true));
+        assert!(ModFilter::parse("rests se:").matches(&data, true));
+        assert!(!ModFilter::parse("rests se:").matches(&data, false));
+        assert!(ModFilter::parse("UUID:A").matches(&data, false));
+        assert!(!ModFilter::parse("tag:combat").matches(&data, false));
+        assert!(ModFilter::parse("").matches(&data, false));
+
+        data.name = "Short rests, shorter".to_string();
+        let filter = ModFilter::parse("short ts,");
+        let ranges = filter.highlights(FilterField::Name, &data.name);
+        assert_eq!(ranges, vec![0..5, 9..12, 13..18]);
+        assert_eq!(
+            split_highlights(&data.name, &ranges),
+            vec![
+                ("Short", true),
+                (" res", false),
+                ("ts,", true),
+                (" ", false),
+                ("short", true),
+                ("er", false)
+            ]
+        );
+    }
+}
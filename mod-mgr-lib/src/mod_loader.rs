@@ -0,0 +1,365 @@
+//! Reading the game's modules and the user's mods on a background thread, so that the window
+//! stays responsive while the paks are read. The thread reports its progress over a channel.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use ls::{
+    mod_resources::{ModPathVisitor, ModResources},
+    pak::{common::FileInfo, PackageError},
+};
+
+use crate::{
+    classify::{read_pak_mod, PakMod},
+    meta::{file_modified_time, is_meta_file, parse_meta_lsx, MetaError, ModMeta},
+    mods_folder::find_paks,
+};
+
+/// How long loading may go without progress before it is given up on, as the original does
+pub const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct LoadPaths {
+    /// The game's `Data` folder, holding its own modules
+    pub game_data_path: PathBuf,
+    /// The user's `Mods` folder
+    pub mods_path: PathBuf,
+}
+
+/// Something that couldn't be read. Loading carries on without it.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The game's paks couldn't be read, so none of its modules are known
+    BaseGame(PackageError),
+    /// A `meta.lsx` within one of the game's paks
+    BaseGameMeta {
+        pak: PathBuf,
+        error: MetaError,
+    },
+    ModsFolder(std::io::Error),
+    Pak {
+        pak: PathBuf,
+        error: MetaError,
+    },
+}
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::BaseGame(err) => write!(f, "Failed to read the game's paks: {err:?}"),
+            LoadError::BaseGameMeta { pak, error } => write!(f, "{pak:?}: {error}"),
+            LoadError::ModsFolder(err) => write!(f, "Failed to read the mods folder: {err}"),
+            LoadError::Pak { pak, error } => write!(f, "{pak:?}: {error}"),
+        }
+    }
+}
+impl std::error::Error for LoadError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProgress {
+    pub message: Cow<'static, str>,
+    /// How many of the steps' items have been read
+    pub done: usize,
+    pub total: usize,
+}
+impl LoadProgress {
+    pub fn new(message: impl Into<Cow<'static, str>>, done: usize, total: usize) -> LoadProgress {
+        LoadProgress {
+            message: message.into(),
+            done,
+            total,
+        }
+    }
+
+    /// From `0.0` to `1.0`
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+}
+
+/// The mods that were read
+#[derive(Debug, Default)]
+pub struct LoadedMods {
+    /// The modules in the game's own paks, like `Gustav`
+    pub base_mods: Vec<ModMeta>,
+    pub user_mods: Vec<PakMod>,
+}
+
+#[derive(Debug)]
+pub enum LoadModMsg {
+    Progress(LoadProgress),
+    Error(LoadError),
+    Finished(LoadedMods),
+    /// Stopped by [`ModLoader::cancel`]
+    Cancelled,
+    /// Went longer than the timeout without progress. The thread is told to stop, but may still be
+    /// reading a pak.
+    TimedOut,
+}
+impl LoadModMsg {
+    /// Whether no more messages will follow it
+    pub fn is_last(&self) -> bool {
+        matches!(
+            self,
+            LoadModMsg::Finished(_) | LoadModMsg::Cancelled | LoadModMsg::TimedOut
+        )
+    }
+}
+
+/// Loading on a background thread. Its messages are read with [`ModLoader::poll`].
+#[derive(Debug)]
+pub struct ModLoader {
+    rx: Receiver<LoadModMsg>,
+    cancel: Arc<AtomicBool>,
+    timeout: Duration,
+    /// Moved back whenever there is progress
+    deadline: Instant,
+    done: bool,
+}
+impl ModLoader {
+    /// Start loading. It is stopped if it goes `timeout` without progress, such as when a pak
+    /// takes too long to read.
+    pub fn start(paths: LoadPaths, timeout: Duration) -> ModLoader {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let thread_cancel = cancel.clone();
+        std::thread::spawn(move || {
+            // The receiver is gone if the loader was dropped, in which case nobody cares
+            load_mods(&paths, &thread_cancel, |msg| {
+                let _ = tx.send(msg);
+            });
+        });
+
+        ModLoader {
+            rx,
+            cancel,
+            timeout,
+            deadline: Instant::now() + timeout,
+            done: false,
+        }
+    }
+
+    /// Ask the thread to stop. It checks between paks, so this may take a moment.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The messages sent since the last poll. Once one that [`LoadModMsg::is_last`] is returned,
+    /// no more will be.
+    pub fn poll(&mut self) -> Vec<LoadModMsg> {
+        self.poll_at(Instant::now())
+    }
+
+    /// [`ModLoader::poll`], checking the deadline against `now`
+    fn poll_at(&mut self, now: Instant) -> Vec<LoadModMsg> {
+        let mut msgs = Vec::new();
+        while !self.done {
+            match self.rx.try_recv() {
+                Ok(msg) => {
+                    if matches!(msg, LoadModMsg::Progress(_)) {
+                        self.deadline = now + self.timeout;
+                    }
+                    self.done = msg.is_last();
+                    msgs.push(msg);
+                }
+                Err(TryRecvError::Empty) => {
+                    if now >= self.deadline {
+                        self.cancel();
+                        self.done = true;
+                        msgs.push(LoadModMsg::TimedOut);
+                    }
+                    break;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    // The thread panicked
+                    self.done = true;
+                    msgs.push(LoadModMsg::Cancelled);
+                }
+            }
+        }
+
+        msgs
+    }
+}
+
+/// Read the game's modules and then the user's mods, sending what happens to `send`.
+/// Always ends with a message that [`LoadModMsg::is_last`].
+fn load_mods(paths: &LoadPaths, cancel: &AtomicBool, mut send: impl FnMut(LoadModMsg)) {
+    let cancelled = || cancel.load(Ordering::Relaxed);
+    let mut loaded = LoadedMods::default();
+
+    if !paths.game_data_path.as_os_str().is_empty() && paths.game_data_path.is_dir() {
+        send(LoadModMsg::Progress(LoadProgress::new(
+            "Loading base game mods from data folder...",
+            0,
+            1,
+        )));
+        let mut visitor = ModPathVisitor::new(ModResources::default());
+        match visitor.discover_builtin_packages(&paths.game_data_path) {
+            Ok(()) => loaded.base_mods = read_base_metas(&visitor.into_resources(), &mut send),
+            Err(err) => send(LoadModMsg::Error(LoadError::BaseGame(err))),
+        }
+    }
+
+    if cancelled() {
+        send(LoadModMsg::Cancelled);
+        return;
+    }
+
+    let paks = match find_paks(&paths.mods_path) {
+        Ok(paks) => paks,
+        Err(err) => {
+            send(LoadModMsg::Error(LoadError::ModsFolder(err)));
+            Vec::new()
+        }
+    };
+    for (idx, pak) in paks.iter().enumerate() {
+        if cancelled() {
+            send(LoadModMsg::Cancelled);
+            return;
+        }
+
+        send(LoadModMsg::Progress(LoadProgress::new(
+            format!("Loading {}...", pak_name(pak)),
+            idx,
+            paks.len(),
+        )));
+        match read_pak_mod(pak) {
+            Ok(pak_mod) => loaded.user_mods.push(pak_mod),
+            Err(error) => send(LoadModMsg::Error(LoadError::Pak {
+                pak: pak.clone(),
+                error,
+            })),
+        }
+    }
+
+    send(LoadModMsg::Finished(loaded));
+}
+
+/// Parse the `meta.lsx` of each module within the game's paks.
+fn read_base_metas(resources: &ModResources, send: &mut impl FnMut(LoadModMsg)) -> Vec<ModMeta> {
+    let mut metas = Vec::new();
+    let total = resources.loaded_packages.len();
+    for (idx, package) in resources.loaded_packages.iter().enumerate() {
+        send(LoadModMsg::Progress(LoadProgress::new(
+            format!("Loading {}...", pak_name(&package.path)),
+            idx,
+            total,
+        )));
+
+        for file in &package.files {
+            let FileInfo::Packaged(info) = file else {
+                continue;
+            };
+            if !is_meta_file(&info.name) {
+                continue;
+            }
+
+            let meta = package
+                .read_file(info)
+                .map_err(MetaError::from)
+                .and_then(|content| parse_meta_lsx(&String::from_utf8_lossy(&content)));
+            match meta {
+                Ok(mut meta) => {
+                    meta.data.file_path = package.path.clone();
+                    meta.data.last_modified = file_modified_time(&package.path);
+                    metas.push(meta);
+                }
+                Err(error) => send(LoadModMsg::Error(LoadError::BaseGameMeta {
+                    pak: package.path.clone(),
+                    error,
+                })),
+            }
+        }
+    }
+
+    metas
+}
+
+fn pak_name(path: &Path) -> Cow<'_, str> {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_mods() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let mods_path = root.join("Mods");
+        std::fs::create_dir_all(&mods_path).unwrap();
+        std::fs::write(mods_path.join("Broken.pak"), b"not a pak").unwrap();
+        let paths = LoadPaths {
+            game_data_path: root.join("Data"),
+            mods_path,
+        };
+
+        let mut msgs = Vec::new();
+        load_mods(&paths, &AtomicBool::new(false), |msg| msgs.push(msg));
+        assert!(matches!(
+            &msgs[0],
+            LoadModMsg::Progress(progress) if progress.message == "Loading Broken.pak..."
+        ));
+        assert!(matches!(&msgs[1], LoadModMsg::Error(LoadError::Pak { .. })));
+        assert!(matches!(&msgs[2], LoadModMsg::Finished(loaded) if loaded.user_mods.is_empty()));
+        assert_eq!(msgs.len(), 3);
+
+        let mut msgs = Vec::new();
+        load_mods(&paths, &AtomicBool::new(true), |msg| msgs.push(msg));
+        assert!(matches!(msgs.as_slice(), [LoadModMsg::Cancelled]));
+
+        let mut loader = ModLoader::start(paths, Duration::ZERO);
+        let msgs = loader.poll();
+        assert!(msgs.last().is_some_and(LoadModMsg::is_last));
+        assert!(loader.is_done());
+        assert!(loader.poll().is_empty());
+    }
+
+    #[test]
+    fn test_timeout_after_progress() {
+        let (tx, rx) = mpsc::channel();
+        let start = Instant::now();
+        let timeout = Duration::from_secs(60);
+        let secs = |secs| start + Duration::from_secs(secs);
+        let mut loader = ModLoader {
+            rx,
+            cancel: Arc::new(AtomicBool::new(false)),
+            timeout,
+            deadline: start + timeout,
+            done: false,
+        };
+
+        // Progress before the deadline moves it back
+        tx.send(LoadModMsg::Progress(LoadProgress::new("", 0, 1)))
+            .unwrap();
+        assert_eq!(loader.poll_at(secs(45)).len(), 1);
+        assert!(loader.poll_at(secs(90)).is_empty());
+        assert!(!loader.is_done());
+
+        assert!(matches!(
+            loader.poll_at(secs(105)).as_slice(),
+            [LoadModMsg::TimedOut]
+        ));
+        assert!(loader.cancel.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,145 @@
+//! Reading and writing `modsettings.lsx`, which is the load order that the game uses.
+//! It is in `PlayerProfiles/<Profile>/modsettings.lsx` within the game's documents folder.
+
+use std::path::Path;
+
+use lsf::lsx::{parse_lsx, Node};
+
+use crate::{
+    mod_data::{DivinityModDependencyData, ModData, ModVersion},
+    xml_mod_order_module, xml_mod_settings_template, xml_module_short_desc,
+};
+
+pub const MOD_SETTINGS_FILE_NAME: &str = "modsettings.lsx";
+
+#[derive(Debug)]
+pub enum ModSettingsError {
+    Io(std::io::Error),
+    Xml(String),
+}
+impl std::fmt::Display for ModSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModSettingsError::Io(err) => write!(f, "{err}"),
+            ModSettingsError::Xml(err) => {
+                write!(f, "Failed to parse {MOD_SETTINGS_FILE_NAME}: {err}")
+            }
+        }
+    }
+}
+impl std::error::Error for ModSettingsError {}
+impl From<std::io::Error> for ModSettingsError {
+    fn from(e: std::io::Error) -> Self {
+        ModSettingsError::Io(e)
+    }
+}
+
+fn attr<'a>(node: &'a Node<'_>, id: &str) -> Option<&'a str> {
+    node.attrs
+        .iter()
+        .find(|attr| attr.id == id)
+        .and_then(|attr| attr.value.as_deref())
+}
+
+fn children<'a, 'b>(node: &'a Node<'b>) -> impl Iterator<Item = &'a Node<'b>> {
+    node.children
+        .iter()
+        .flat_map(|children| children.elems.iter().flatten())
+}
+
+/// Parse the text of a `modsettings.lsx`, returning the mods in load order.
+pub fn parse_mod_settings(text: &str) -> Result<Vec<DivinityModDependencyData>, ModSettingsError> {
+    let text = text.trim_start_matches('\u{feff}');
+    let save = parse_lsx(text).map_err(|err| ModSettingsError::Xml(err.to_string()))?;
+
+    let root = &save.region.node;
+    let mods = children(root)
+        .filter(|node| node.id == "Mods")
+        .flat_map(children)
+        .filter(|node| node.id == "ModuleShortDesc")
+        .filter_map(|node| {
+            let version = attr(node, "Version64")
+                .or_else(|| attr(node, "Version"))
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .unwrap_or(0);
+
+            Some(DivinityModDependencyData {
+                uuid: attr(node, "UUID")?.to_string(),
+                name: attr(node, "Name").unwrap_or_default().to_string(),
+                folder: attr(node, "Folder").unwrap_or_default().to_string(),
+                md5: attr(node, "MD5").unwrap_or_default().to_string(),
+                version: ModVersion::from(version as u64),
+            })
+        })
+        .collect();
+
+    Ok(mods)
+}
+
+pub fn read_mod_settings(path: &Path) -> Result<Vec<DivinityModDependencyData>, ModSettingsError> {
+    let text = std::fs::read_to_string(path)?;
+    parse_mod_settings(&text)
+}
+
+/// Escape text for use in an xml attribute
+fn escape_attr(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Create the text of a `modsettings.lsx` with the mods in the given load order.
+/// The mods' `md5` should be filled in, see [`crate::md5_cache`].
+pub fn mod_settings_xml<'a>(mods: impl IntoIterator<Item = &'a ModData>) -> String {
+    let order: Vec<DivinityModDependencyData> = mods
+        .into_iter()
+        .map(DivinityModDependencyData::from_mod_data)
+        .collect();
+    order_settings_xml(&order)
+}
+
+/// Create the text of a `modsettings.lsx` from a load order, such as one that was read from
+/// another `modsettings.lsx`.
+pub fn order_settings_xml(order: &[DivinityModDependencyData]) -> String {
+    let mut mod_order = String::new();
+    let mut short_descs = String::new();
+    for entry in order {
+        let uuid = escape_attr(&entry.uuid);
+        mod_order.push_str(&xml_mod_order_module(&uuid));
+        short_descs.push_str(&xml_module_short_desc(
+            &escape_attr(&entry.folder),
+            &escape_attr(&entry.md5),
+            &escape_attr(&entry.name),
+            &uuid,
+            u64::from(entry.version) as i64,
+        ));
+    }
+
+    xml_mod_settings_template(&mod_order, &short_descs)
+}
+
+pub fn write_mod_settings<'a>(
+    path: &Path,
+    mods: impl IntoIterator<Item = &'a ModData>,
+) -> Result<(), ModSettingsError> {
+    std::fs::write(path, mod_settings_xml(mods))?;
+    Ok(())
+}
+
+pub fn write_order_settings(
+    path: &Path,
+    order: &[DivinityModDependencyData],
+) -> Result<(), ModSettingsError> {
+    std::fs::write(path, order_settings_xml(order))?;
+    Ok(())
+}
@@ -0,0 +1,288 @@
+//! Checking installed mods for updates against a manifest of the latest versions.
+//! The manifest is a json file mapping each mod's uuid to its latest version and where to download
+//! it:
+//! ```json
+//! {
+//!     "Mods": {
+//!         "<uuid>": {
+//!             "Name": "My Mod",
+//!             "Version": "1.2.0.0",
+//!             "PublishVersion": "1.2.0.0",
+//!             "DownloadUrl": "https://example.com/MyMod.zip"
+//!         }
+//!     }
+//! }
+//! ```
+//! Versions can be written as `major.minor.revision.build` or as the 64 bit integer from
+//! `meta.lsx`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::mod_data::{ModData, ModVersion};
+
+#[derive(Debug)]
+pub enum UpdateError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The manifest could not be downloaded
+    Fetch(String),
+}
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Io(err) => write!(f, "{err}"),
+            UpdateError::Json(err) => write!(f, "Failed to parse update manifest: {err}"),
+            UpdateError::Fetch(err) => write!(f, "Failed to download update manifest: {err}"),
+        }
+    }
+}
+impl std::error::Error for UpdateError {}
+impl From<std::io::Error> for UpdateError {
+    fn from(e: std::io::Error) -> Self {
+        UpdateError::Io(e)
+    }
+}
+impl From<serde_json::Error> for UpdateError {
+    fn from(e: serde_json::Error) -> Self {
+        UpdateError::Json(e)
+    }
+}
+
+fn deserialize_version<'de, D: Deserializer<'de>>(de: D) -> Result<ModVersion, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Version {
+        Int(u64),
+        Text(String),
+    }
+
+    match Version::deserialize(de)? {
+        Version::Int(v) => Ok(ModVersion::from(v)),
+        Version::Text(v) => v.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_opt_version<'de, D: Deserializer<'de>>(
+    de: D,
+) -> Result<Option<ModVersion>, D::Error> {
+    deserialize_version(de).map(Some)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ManifestEntry {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version: ModVersion,
+    #[serde(deserialize_with = "deserialize_opt_version")]
+    pub publish_version: Option<ModVersion>,
+    pub download_url: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct UpdateManifest {
+    /// The latest version of each mod, by uuid
+    pub mods: HashMap<String, ManifestEntry>,
+}
+impl UpdateManifest {
+    pub fn parse(text: &str) -> Result<UpdateManifest, serde_json::Error> {
+        serde_json::from_str(text.trim_start_matches('\u{feff}'))
+    }
+
+    pub fn get(&self, uuid: &str) -> Option<&ManifestEntry> {
+        self.mods.get(uuid).or_else(|| {
+            self.mods
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(uuid))
+                .map(|(_, entry)| entry)
+        })
+    }
+}
+
+/// Somewhere to get the update manifest from.
+/// This is a trait so that the network can be swapped out, such as for a local file in tests.
+pub trait ManifestSource {
+    fn fetch(&self) -> Result<String, UpdateError>;
+}
+
+/// A manifest in a local file
+#[derive(Debug, Clone)]
+pub struct FileSource(pub PathBuf);
+impl ManifestSource for FileSource {
+    fn fetch(&self) -> Result<String, UpdateError> {
+        Ok(std::fs::read_to_string(&self.0)?)
+    }
+}
+
+/// A manifest downloaded with `curl`, which is available on Linux, MacOS and modern Windows, so we
+/// don't have to pull in an http client for a single request.
+#[derive(Debug, Clone)]
+pub struct UrlSource(pub String);
+impl ManifestSource for UrlSource {
+    fn fetch(&self) -> Result<String, UpdateError> {
+        let output = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--location"])
+            .arg(&self.0)
+            .output()
+            .map_err(|err| UpdateError::Fetch(format!("Failed to run curl: {err}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(UpdateError::Fetch(stderr.trim().to_string()));
+        }
+
+        String::from_utf8(output.stdout)
+            .map_err(|_| UpdateError::Fetch("Manifest is not valid utf8".to_string()))
+    }
+}
+
+/// The source for a manifest location from the settings, which is either a url or a file path.
+pub fn manifest_source(location: &str) -> Box<dyn ManifestSource> {
+    let location = location.trim();
+    if location.starts_with("https://") || location.starts_with("http://") {
+        Box::new(UrlSource(location.to_string()))
+    } else {
+        Box::new(FileSource(Path::new(location).to_path_buf()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateKind {
+    /// The manifest has a newer version of the mod
+    NewerVersion,
+    /// The version is the same, but the mod has been published again
+    PublishVersionChanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModUpdate {
+    pub uuid: String,
+    pub name: String,
+    pub kind: UpdateKind,
+    pub installed: ModVersion,
+    pub latest: ModVersion,
+    pub installed_publish_version: ModVersion,
+    pub latest_publish_version: Option<ModVersion>,
+    pub download_url: String,
+}
+impl ModUpdate {
+    /// What changed, such as `v1.2.0.0 is available`
+    pub fn description(&self) -> String {
+        match self.kind {
+            UpdateKind::NewerVersion => format!("v{} is available", self.latest),
+            UpdateKind::PublishVersionChanged => format!("v{} was republished", self.latest),
+        }
+    }
+}
+
+/// Compare the installed mods against the manifest.
+/// Mods that aren't in the manifest are skipped.
+pub fn check_mod_updates(installed: &[ModData], manifest: &UpdateManifest) -> Vec<ModUpdate> {
+    installed
+        .iter()
+        .filter_map(|data| {
+            let entry = manifest.get(&data.uuid)?;
+            let kind = if entry.version > data.version {
+                UpdateKind::NewerVersion
+            } else if entry.version == data.version
+                && entry
+                    .publish_version
+                    .is_some_and(|publish| publish != data.publish_version)
+            {
+                UpdateKind::PublishVersionChanged
+            } else {
+                return None;
+            };
+
+            Some(ModUpdate {
+                uuid: data.uuid.clone(),
+                name: if entry.name.is_empty() {
+                    data.display_name()
+                } else {
+                    entry.name.clone()
+                },
+                kind,
+                installed: data.version,
+                latest: entry.version,
+                installed_publish_version: data.publish_version,
+                latest_publish_version: entry.publish_version,
+                download_url: entry.download_url.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Fetch the manifest and check the installed mods against it.
+pub fn check_for_mod_updates(
+    source: &dyn ManifestSource,
+    installed: &[ModData],
+) -> Result<Vec<ModUpdate>, UpdateError> {
+    let manifest = UpdateManifest::parse(&source.fetch()?)?;
+    Ok(check_mod_updates(installed, &manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_for_mod_updates() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest_path = dir.join("manifest.json");
+        std::fs::write(
+            &manifest_path,
+            r#"{ "Mods": {
+                "UUID-A": { "Version": "1.2", "DownloadUrl": "https://example.com/a.zip" },
+                "uuid-b": { "Version": 36028797018963968, "PublishVersion": "1.0.0.1" },
+                "uuid-c": { "Version": "1.0.0.0" }
+            } }"#,
+        )
+        .unwrap();
+
+        let mut installed = vec![
+            ModData::new("A", "uuid-a", "A", 0, "Add-on", "", "", "", ""),
+            ModData::new("B", "uuid-b", "B", 0, "Add-on", "", "", "", ""),
+            ModData::new("C", "uuid-c", "C", 0, "Add-on", "", "", "", ""),
+            ModData::new("D", "uuid-d", "D", 0, "Add-on", "", "", "", ""),
+        ];
+        installed[0].version = "1.1.5.0".parse().unwrap();
+        installed[1].version = "1.0".parse().unwrap();
+        installed[2].version = "2.0".parse().unwrap();
+        assert_eq!(
+            "1.2.3.4.5".parse::<ModVersion>(),
+            Err(crate::mod_data::ParseVersionError::TooManyParts)
+        );
+        assert_eq!(
+            "1.0.0.2147483648".parse::<ModVersion>(),
+            Err(crate::mod_data::ParseVersionError::BuildTooLarge)
+        );
+        assert_eq!(
+            u64::from("1.0.0.2147483647".parse::<ModVersion>().unwrap()),
+            36028799166447615
+        );
+
+        let source = manifest_source(manifest_path.to_str().unwrap());
+        let updates = check_for_mod_updates(source.as_ref(), &installed).unwrap();
+
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].uuid, "uuid-a");
+        assert_eq!(updates[0].kind, UpdateKind::NewerVersion);
+        assert_eq!(updates[0].latest.to_string(), "1.2.0.0");
+        assert_eq!(updates[0].download_url, "https://example.com/a.zip");
+        assert_eq!(updates[0].description(), "v1.2.0.0 is available");
+        assert_eq!(updates[1].uuid, "uuid-b");
+        assert_eq!(updates[1].kind, UpdateKind::PublishVersionChanged);
+        assert_eq!(
+            updates[1].latest_publish_version.unwrap().to_string(),
+            "1.0.0.1"
+        );
+    }
+}
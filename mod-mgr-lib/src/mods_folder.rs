@@ -0,0 +1,61 @@
+//! Reading the mods installed in the user's `Mods` folder.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    classify::{read_pak_mod, PakMod},
+    meta::MetaError,
+};
+
+/// Find the paks directly within the mods folder, sorted by file name.
+/// Hidden files are skipped, which includes paks that are still being installed.
+pub fn find_paks(mods_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paks = Vec::new();
+    for entry in std::fs::read_dir(mods_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if name.starts_with('.') || !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        if name.to_lowercase().ends_with(".pak") {
+            paks.push(path);
+        }
+    }
+
+    paks.sort();
+
+    Ok(paks)
+}
+
+/// The mods in the mods folder.
+#[derive(Debug)]
+pub struct ModsFolder {
+    pub mods: Vec<PakMod>,
+    /// Paks that could not be read
+    pub errors: Vec<(PathBuf, MetaError)>,
+}
+impl ModsFolder {
+    pub fn find(&self, uuid: &str) -> Option<&PakMod> {
+        self.mods
+            .iter()
+            .find(|m| m.meta.data.uuid.eq_ignore_ascii_case(uuid))
+    }
+}
+
+/// Read and classify every pak in the mods folder.
+pub fn read_mods_folder(mods_dir: &Path) -> std::io::Result<ModsFolder> {
+    let mut mods = Vec::new();
+    let mut errors = Vec::new();
+    for path in find_paks(mods_dir)? {
+        match read_pak_mod(&path) {
+            Ok(pak_mod) => mods.push(pak_mod),
+            Err(err) => errors.push((path, err)),
+        }
+    }
+
+    Ok(ModsFolder { mods, errors })
+}
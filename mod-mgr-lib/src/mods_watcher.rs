@@ -0,0 +1,299 @@
+//! Noticing when paks are added to, changed in or removed from the mods folder, or when the game
+//! rewrites a profile's `modsettings.lsx`, so that only those need to be read again.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    classify::{read_pak_mod, PakMod},
+    meta::MetaError,
+    mod_settings::MOD_SETTINGS_FILE_NAME,
+};
+
+/// How long to wait for the changes to stop, so that a pak which is still being copied in isn't
+/// read half-written
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+/// How often the polling fallback checks the folders, as it has to look at every file each time
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The files that changed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WatchedChanges {
+    /// Paks directly in the mods folder
+    pub paks: BTreeSet<PathBuf>,
+    /// The `modsettings.lsx` of profiles
+    pub mod_settings: BTreeSet<PathBuf>,
+}
+impl WatchedChanges {
+    pub fn is_empty(&self) -> bool {
+        self.paks.is_empty() && self.mod_settings.is_empty()
+    }
+
+    /// Add the path if it is one we care about, such as a pak in the mods folder.
+    pub fn add(&mut self, path: &Path, mods_dir: &Path) {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            return;
+        };
+
+        // Hidden files include paks that are still being installed, see `install`
+        if name.starts_with('.') {
+            return;
+        }
+
+        if name.to_lowercase().ends_with(".pak") && path.parent() == Some(mods_dir) {
+            self.paks.insert(path.to_path_buf());
+        } else if name.eq_ignore_ascii_case(MOD_SETTINGS_FILE_NAME) {
+            self.mod_settings.insert(path.to_path_buf());
+        }
+    }
+
+    fn extend(&mut self, other: WatchedChanges) {
+        self.paks.extend(other.paks);
+        self.mod_settings.extend(other.mod_settings);
+    }
+}
+
+/// Collects changes until there have been none for a while, so a burst of events for the same
+/// files is handled once.
+#[derive(Debug)]
+pub struct Debouncer {
+    pending: WatchedChanges,
+    last_change: Option<Instant>,
+    delay: Duration,
+}
+impl Debouncer {
+    pub fn new(delay: Duration) -> Debouncer {
+        Debouncer {
+            pending: WatchedChanges::default(),
+            last_change: None,
+            delay,
+        }
+    }
+
+    pub fn add(&mut self, changes: WatchedChanges, now: Instant) {
+        if changes.is_empty() {
+            return;
+        }
+
+        self.pending.extend(changes);
+        self.last_change = Some(now);
+    }
+
+    /// The changes, once there have been none for the delay.
+    pub fn take(&mut self, now: Instant) -> Option<WatchedChanges> {
+        let last_change = self.last_change?;
+        if now.duration_since(last_change) < self.delay {
+            return None;
+        }
+
+        self.last_change = None;
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+/// Watches the mods folder and the profiles folder.
+pub struct ModsWatcher {
+    // Stops watching when dropped
+    _watcher: Box<dyn Watcher>,
+    rx: Receiver<notify::Result<Event>>,
+    mods_dir: PathBuf,
+    debouncer: Debouncer,
+    polling: bool,
+}
+impl ModsWatcher {
+    /// Start watching, using the system's file events if it can. Some filesystems, like those
+    /// on SD cards, network drives or in some containers, don't provide them, in which case it
+    /// falls back to checking the folders every [`POLL_INTERVAL`]. Others accept the watch but
+    /// never send events, which can't be told apart from nothing changing, so `force_polling`
+    /// skips straight to checking the folders.
+    pub fn new(
+        mods_dir: &Path,
+        profiles_dir: &Path,
+        force_polling: bool,
+    ) -> notify::Result<ModsWatcher> {
+        let (tx, rx) = mpsc::channel();
+
+        if !force_polling {
+            let watcher = RecommendedWatcher::new(tx.clone(), Config::default())
+                .and_then(|watcher| watch(watcher, mods_dir, profiles_dir));
+            match watcher {
+                Ok(watcher) => return Ok(ModsWatcher::from_watcher(watcher, rx, mods_dir, false)),
+                Err(err) => eprintln!("Falling back to polling for changes to the mods: {err}"),
+            }
+        }
+
+        let config = Config::default().with_poll_interval(POLL_INTERVAL);
+        let watcher = watch(PollWatcher::new(tx, config)?, mods_dir, profiles_dir)?;
+        Ok(ModsWatcher::from_watcher(watcher, rx, mods_dir, true))
+    }
+
+    fn from_watcher(
+        watcher: Box<dyn Watcher>,
+        rx: Receiver<notify::Result<Event>>,
+        mods_dir: &Path,
+        polling: bool,
+    ) -> ModsWatcher {
+        ModsWatcher {
+            _watcher: watcher,
+            rx,
+            mods_dir: mods_dir.to_path_buf(),
+            debouncer: Debouncer::new(DEFAULT_DEBOUNCE),
+            polling,
+        }
+    }
+
+    /// Whether it is using the polling fallback
+    pub fn is_polling(&self) -> bool {
+        self.polling
+    }
+
+    /// The files that changed, once they've settled.
+    pub fn poll(&mut self) -> Option<WatchedChanges> {
+        let mut changes = WatchedChanges::default();
+        for event in self.rx.try_iter() {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for path in &event.paths {
+                        changes.add(path, &self.mods_dir);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("Error while watching the mods: {err}"),
+            }
+        }
+
+        let now = Instant::now();
+        self.debouncer.add(changes, now);
+        self.debouncer.take(now)
+    }
+}
+impl std::fmt::Debug for ModsWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModsWatcher")
+            .field("mods_dir", &self.mods_dir)
+            .field("debouncer", &self.debouncer)
+            .field("polling", &self.polling)
+            .finish_non_exhaustive()
+    }
+}
+
+fn watch(
+    mut watcher: impl Watcher + 'static,
+    mods_dir: &Path,
+    profiles_dir: &Path,
+) -> notify::Result<Box<dyn Watcher>> {
+    watcher.watch(mods_dir, RecursiveMode::NonRecursive)?;
+    // Each profile is a folder with its `modsettings.lsx` inside
+    if profiles_dir.is_dir() {
+        watcher.watch(profiles_dir, RecursiveMode::Recursive)?;
+    }
+
+    Ok(Box::new(watcher))
+}
+
+/// The result of reading a changed pak again
+#[derive(Debug)]
+pub enum PakRescan {
+    /// Added or changed
+    Updated(Box<PakMod>),
+    Removed(PathBuf),
+    Failed {
+        pak: PathBuf,
+        error: MetaError,
+    },
+}
+
+/// Read the paks that changed, rather than the whole mods folder.
+pub fn rescan_paks<'a>(paks: impl IntoIterator<Item = &'a PathBuf>) -> Vec<PakRescan> {
+    paks.into_iter()
+        .map(|pak| {
+            if !pak.is_file() {
+                return PakRescan::Removed(pak.clone());
+            }
+
+            match read_pak_mod(pak) {
+                Ok(pak_mod) => PakRescan::Updated(Box::new(pak_mod)),
+                Err(error) => PakRescan::Failed {
+                    pak: pak.clone(),
+                    error,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::empty_pak;
+
+    #[test]
+    fn test_rescan_paks() {
+        let temp = tempfile::tempdir().unwrap();
+        let mods_dir = temp.path();
+        let added = mods_dir.join("Added.pak");
+        let updated = mods_dir.join("Updated.pak");
+        let broken = mods_dir.join("Broken.pak");
+        let removed = mods_dir.join("Removed.pak");
+        std::fs::write(&added, empty_pak()).unwrap();
+        std::fs::write(&updated, empty_pak()).unwrap();
+        std::fs::write(&broken, "not a pak").unwrap();
+
+        let changes = rescan_paks([&added, &updated, &broken, &removed]);
+        assert_eq!(changes.len(), 4);
+        assert!(matches!(
+            &changes[0],
+            PakRescan::Updated(pak_mod) if pak_mod.meta.data.uuid == "Added"
+                && pak_mod.meta.data.file_path == added
+        ));
+        assert!(matches!(
+            &changes[1],
+            PakRescan::Updated(pak_mod) if pak_mod.meta.data.uuid == "Updated"
+        ));
+        assert!(matches!(&changes[2], PakRescan::Failed { pak, .. } if *pak == broken));
+        assert!(matches!(&changes[3], PakRescan::Removed(pak) if *pak == removed));
+    }
+
+    #[test]
+    fn test_debounced_changes() {
+        let mods_dir = Path::new("/docs/Mods");
+        let mut changes = WatchedChanges::default();
+        changes.add(&mods_dir.join("A.pak"), mods_dir);
+        changes.add(&mods_dir.join("B.PAK"), mods_dir);
+        changes.add(&mods_dir.join(".C.pak"), mods_dir);
+        changes.add(&mods_dir.join("notes.txt"), mods_dir);
+        changes.add(&mods_dir.join("Nested/D.pak"), mods_dir);
+        changes.add(
+            Path::new("/docs/PlayerProfiles/Public/modsettings.lsx"),
+            mods_dir,
+        );
+        assert_eq!(
+            changes.paks,
+            BTreeSet::from([mods_dir.join("A.pak"), mods_dir.join("B.PAK")])
+        );
+        assert_eq!(changes.mod_settings.len(), 1);
+
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(500));
+        assert_eq!(debouncer.take(start), None);
+        debouncer.add(changes.clone(), start);
+        assert_eq!(debouncer.take(start + Duration::from_millis(400)), None);
+
+        // More changes push it back
+        let mut more = WatchedChanges::default();
+        more.add(&mods_dir.join("E.pak"), mods_dir);
+        debouncer.add(more, start + Duration::from_millis(400));
+        assert_eq!(debouncer.take(start + Duration::from_millis(600)), None);
+
+        let taken = debouncer.take(start + Duration::from_millis(900)).unwrap();
+        assert_eq!(taken.paks.len(), 3);
+        assert_eq!(taken.mod_settings, changes.mod_settings);
+        assert_eq!(debouncer.take(start + Duration::from_secs(5)), None);
+    }
+}
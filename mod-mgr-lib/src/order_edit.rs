@@ -0,0 +1,249 @@
+//! Editing a profile's load order by the uuids, folders or names of mods, as the command line does.
+//! The order is the list of entries written to `modsettings.lsx`, with the main campaign first.
+
+use crate::{
+    mod_data::{DivinityModDependencyData, ModData},
+    profiles::with_main_campaign,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderEditError {
+    /// No installed mod matches the name
+    NotInstalled(String),
+    /// No mod in the load order matches the name
+    NotEnabled(String),
+    /// The main campaign was to be moved, or a mod moved to its place
+    MainCampaignMoved,
+}
+impl std::fmt::Display for OrderEditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderEditError::NotInstalled(name) => {
+                write!(f, "There is no installed mod matching {name:?}")
+            }
+            OrderEditError::NotEnabled(name) => {
+                write!(f, "There is no enabled mod matching {name:?}")
+            }
+            OrderEditError::MainCampaignMoved => write!(
+                f,
+                "Position 0 is the main campaign, which is always first. The first mod is at 1"
+            ),
+        }
+    }
+}
+impl std::error::Error for OrderEditError {}
+
+/// Whether the mod is the one the user named, by its uuid, folder or name.
+pub fn matches_mod(query: &str, uuid: &str, folder: &str, name: &str) -> bool {
+    [uuid, folder, name]
+        .iter()
+        .any(|value| !value.is_empty() && value.eq_ignore_ascii_case(query))
+}
+
+fn find_enabled(order: &[DivinityModDependencyData], name: &str) -> Result<usize, OrderEditError> {
+    order
+        .iter()
+        .position(|entry| matches_mod(name, &entry.uuid, &entry.folder, &entry.name))
+        .ok_or_else(|| OrderEditError::NotEnabled(name.to_string()))
+}
+
+/// The installed mods named by `names` that should be added to the end of the order.
+/// Mods that are already in the order, or that are named more than once, are only added once.
+pub fn mods_to_enable(
+    order: &[DivinityModDependencyData],
+    installed: &[ModData],
+    names: &[String],
+) -> Result<Vec<ModData>, OrderEditError> {
+    let mut to_enable: Vec<ModData> = Vec::new();
+    for name in names {
+        let data = installed
+            .iter()
+            .find(|data| matches_mod(name, &data.uuid, &data.folder, &data.name))
+            .ok_or_else(|| OrderEditError::NotInstalled(name.to_string()))?;
+
+        let already_enabled = order
+            .iter()
+            .map(|entry| &entry.uuid)
+            .chain(to_enable.iter().map(|data| &data.uuid))
+            .any(|uuid| uuid.eq_ignore_ascii_case(&data.uuid));
+        if already_enabled {
+            eprintln!("{} is already enabled", data.display_name());
+            continue;
+        }
+
+        to_enable.push(data.clone());
+    }
+
+    Ok(to_enable)
+}
+
+/// Remove the mods named by `names` from the order, returning their entries.
+pub fn disable_mods(
+    order: &mut Vec<DivinityModDependencyData>,
+    names: &[String],
+) -> Result<Vec<DivinityModDependencyData>, OrderEditError> {
+    names
+        .iter()
+        .map(|name| Ok(order.remove(find_enabled(order, name)?)))
+        .collect()
+}
+
+/// The order with the named mod moved to `position`.
+/// Positions count the main campaign, which is always at 0, so the first mod is at 1. Positions
+/// past the end move the mod to the end.
+pub fn move_mod(
+    order: &[DivinityModDependencyData],
+    name: &str,
+    position: usize,
+) -> Result<Vec<DivinityModDependencyData>, OrderEditError> {
+    if position == 0 {
+        return Err(OrderEditError::MainCampaignMoved);
+    }
+
+    // Added first, so that the positions are the same as the ones written
+    let mut order = with_main_campaign(order);
+    let idx = find_enabled(&order, name)?;
+    if idx == 0 {
+        return Err(OrderEditError::MainCampaignMoved);
+    }
+
+    let entry = order.remove(idx);
+    order.insert(position.min(order.len()), entry);
+
+    Ok(order)
+}
+
+/// The installed mods for an imported order, in its order, and the entries of the ones that aren't
+/// installed.
+/// `builtin` are the uuids of the game's own modules, which are skipped since they're always
+/// loaded.
+pub fn import_order(
+    imported: Vec<DivinityModDependencyData>,
+    installed: &[ModData],
+    builtin: &[String],
+) -> (Vec<ModData>, Vec<DivinityModDependencyData>) {
+    let mut mods = Vec::with_capacity(imported.len());
+    let mut missing = Vec::new();
+    for entry in imported {
+        let is_builtin = builtin
+            .iter()
+            .any(|uuid| uuid.eq_ignore_ascii_case(&entry.uuid));
+        if is_builtin {
+            continue;
+        }
+
+        match installed
+            .iter()
+            .find(|data| data.uuid.eq_ignore_ascii_case(&entry.uuid))
+        {
+            Some(data) => mods.push(data.clone()),
+            None => {
+                eprintln!("Skipping {}, which is not installed", entry.name);
+                missing.push(entry);
+            }
+        }
+    }
+
+    (mods, missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{profiles::main_campaign_entry, MAIN_CAMPAIGN_UUID};
+
+    fn installed(uuids: &str) -> Vec<ModData> {
+        uuids
+            .split_whitespace()
+            .map(|uuid| {
+                let name = uuid.to_uppercase();
+                ModData::new(&name, uuid, format!("{name}_Folder"), 0, "", "", "", "", "")
+            })
+            .collect()
+    }
+
+    fn order(uuids: &str) -> Vec<DivinityModDependencyData> {
+        installed(uuids)
+            .iter()
+            .map(|data| {
+                if data.uuid == "campaign" {
+                    main_campaign_entry()
+                } else {
+                    DivinityModDependencyData::from_mod_data(data)
+                }
+            })
+            .collect()
+    }
+
+    fn uuids(order: &[DivinityModDependencyData]) -> Vec<&str> {
+        order
+            .iter()
+            .map(|entry| {
+                if entry.uuid == MAIN_CAMPAIGN_UUID {
+                    "campaign"
+                } else {
+                    entry.uuid.as_str()
+                }
+            })
+            .collect()
+    }
+
+    fn names(names: &str) -> Vec<String> {
+        names.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_move_mod() {
+        // The campaign is added before moving, so the position is where the mod ends up
+        let moved = move_mod(&order("a b c"), "c", 1).unwrap();
+        assert_eq!(uuids(&moved), ["campaign", "c", "a", "b"]);
+
+        let moved = move_mod(&order("a campaign b c"), "A_folder", 3).unwrap();
+        assert_eq!(uuids(&moved), ["campaign", "b", "c", "a"]);
+        let moved = move_mod(&order("campaign a b c"), "a", 100).unwrap();
+        assert_eq!(uuids(&moved), ["campaign", "b", "c", "a"]);
+
+        assert_eq!(
+            move_mod(&order("campaign a"), "a", 0),
+            Err(OrderEditError::MainCampaignMoved)
+        );
+        assert_eq!(
+            move_mod(&order("campaign a"), MAIN_CAMPAIGN_UUID, 1),
+            Err(OrderEditError::MainCampaignMoved)
+        );
+        assert_eq!(
+            move_mod(&order("campaign a"), "x", 1),
+            Err(OrderEditError::NotEnabled("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        let installed = installed("a b c");
+        let to_enable = mods_to_enable(&order("a"), &installed, &names("A b B_FOLDER c")).unwrap();
+        let enabled: Vec<&str> = to_enable.iter().map(|data| data.uuid.as_str()).collect();
+        assert_eq!(enabled, ["b", "c"]);
+        assert_eq!(
+            mods_to_enable(&order("a"), &installed, &names("b x")),
+            Err(OrderEditError::NotInstalled("x".to_string()))
+        );
+
+        let mut disabling = order("campaign a b c");
+        let disabled = disable_mods(&mut disabling, &names("C a")).unwrap();
+        assert_eq!(uuids(&disabled), ["c", "a"]);
+        assert_eq!(uuids(&disabling), ["campaign", "b"]);
+        assert_eq!(
+            disable_mods(&mut disabling, &names("a")),
+            Err(OrderEditError::NotEnabled("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_import_order() {
+        let builtin = vec![MAIN_CAMPAIGN_UUID.to_string()];
+        let (mods, missing) = import_order(order("campaign B x a"), &installed("a b"), &builtin);
+        let mods: Vec<&str> = mods.iter().map(|data| data.uuid.as_str()).collect();
+        assert_eq!(mods, ["b", "a"]);
+        assert_eq!(uuids(&missing), ["x"]);
+    }
+}
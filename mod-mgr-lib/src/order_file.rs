@@ -0,0 +1,102 @@
+//! Load orders saved by the mod manager, which are kept in the `Orders/` folder and can be shared.
+//! These use the same json format as the C# version.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mod_data::DivinityModDependencyData,
+    mod_settings::{parse_mod_settings, ModSettingsError},
+};
+
+#[derive(Debug)]
+pub enum OrderFileError {
+    Io(std::io::Error),
+    ModSettings(ModSettingsError),
+    Json(serde_json::Error),
+}
+impl std::fmt::Display for OrderFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderFileError::Io(err) => write!(f, "{err}"),
+            OrderFileError::ModSettings(err) => write!(f, "{err}"),
+            OrderFileError::Json(err) => write!(f, "Failed to parse load order: {err}"),
+        }
+    }
+}
+impl std::error::Error for OrderFileError {}
+impl From<std::io::Error> for OrderFileError {
+    fn from(e: std::io::Error) -> Self {
+        OrderFileError::Io(e)
+    }
+}
+impl From<ModSettingsError> for OrderFileError {
+    fn from(e: ModSettingsError) -> Self {
+        OrderFileError::ModSettings(e)
+    }
+}
+impl From<serde_json::Error> for OrderFileError {
+    fn from(e: serde_json::Error) -> Self {
+        OrderFileError::Json(e)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct OrderEntry {
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct OrderFile {
+    pub name: String,
+    pub order: Vec<OrderEntry>,
+}
+impl OrderFile {
+    pub fn from_order(name: impl Into<String>, order: &[DivinityModDependencyData]) -> OrderFile {
+        OrderFile {
+            name: name.into(),
+            order: order
+                .iter()
+                .map(|entry| OrderEntry {
+                    uuid: entry.uuid.clone(),
+                    name: entry.name.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Read a load order from either an order json or a `modsettings.lsx`.
+/// Only the uuid and name of the mods are filled in for json orders.
+pub fn read_order_file(path: &Path) -> Result<Vec<DivinityModDependencyData>, OrderFileError> {
+    let text = std::fs::read_to_string(path)?;
+    let is_lsx = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lsx"));
+    if is_lsx {
+        return Ok(parse_mod_settings(&text)?);
+    }
+
+    let file: OrderFile = serde_json::from_str(text.trim_start_matches('\u{feff}'))?;
+
+    Ok(file
+        .order
+        .into_iter()
+        .map(|entry| DivinityModDependencyData {
+            uuid: entry.uuid,
+            name: entry.name,
+            folder: String::new(),
+            md5: String::new(),
+            version: Default::default(),
+        })
+        .collect())
+}
@@ -0,0 +1,95 @@
+//! Showing a long list a page of rows at a time. Handheld mode uses this rather than a scroll
+//! view, as touch scrolling isn't reliable there, and moves through the rows when they're dragged.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pager {
+    /// Index of the first row shown
+    pub offset: usize,
+    pub per_page: usize,
+}
+impl Pager {
+    pub fn new(per_page: usize) -> Pager {
+        Pager {
+            offset: 0,
+            per_page: per_page.max(1),
+        }
+    }
+
+    /// The furthest the list can be scrolled, so that the last page is full
+    fn max_offset(&self, total: usize) -> usize {
+        total.saturating_sub(self.per_page)
+    }
+
+    /// The rows that are shown, out of `total`.
+    pub fn visible(&self, total: usize) -> Range<usize> {
+        let start = self.offset.min(self.max_offset(total));
+        start..(start + self.per_page).min(total)
+    }
+
+    /// Move by `rows`, down if positive and up if negative.
+    pub fn scroll_by(&mut self, rows: isize, total: usize) {
+        let offset = self.offset.min(self.max_offset(total));
+        self.offset = offset
+            .saturating_add_signed(rows)
+            .min(self.max_offset(total));
+    }
+
+    pub fn next_page(&mut self, total: usize) {
+        self.scroll_by(self.per_page as isize, total);
+    }
+
+    pub fn previous_page(&mut self, total: usize) {
+        self.scroll_by(-(self.per_page as isize), total);
+    }
+
+    /// The position from where a drag started, after the pointer moved `distance` pixels over
+    /// rows that are `row_height` pixels tall. Dragging up moves down the list, like touch
+    /// scrolling.
+    pub fn dragged(from: Pager, distance: f64, row_height: f64, total: usize) -> Pager {
+        let mut pager = from;
+        pager.scroll_by(-(distance / row_height).trunc() as isize, total);
+        pager
+    }
+
+    /// Like `Page 2 of 5`
+    pub fn page_text(&self, total: usize) -> String {
+        let pages = total.div_ceil(self.per_page).max(1);
+        let visible = self.visible(total);
+        // The last page may overlap the one before it, so go by the end of what is shown
+        let page = visible.end.div_ceil(self.per_page).max(1);
+        format!("Page {page} of {pages}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager() {
+        let mut pager = Pager::new(10);
+        assert_eq!(pager.visible(4), 0..4);
+        assert_eq!(pager.page_text(0), "Page 1 of 1");
+
+        pager.next_page(25);
+        assert_eq!(pager.visible(25), 10..20);
+        assert_eq!(pager.page_text(25), "Page 2 of 3");
+        pager.next_page(25);
+        assert_eq!(pager.visible(25), 15..25);
+        assert_eq!(pager.page_text(25), "Page 3 of 3");
+        pager.next_page(25);
+        assert_eq!(pager.visible(25), 15..25);
+
+        // Mods were removed, so it is past the end
+        assert_eq!(pager.visible(12), 2..12);
+        pager.previous_page(12);
+        assert_eq!(pager.visible(12), 0..10);
+
+        let start = Pager::new(10);
+        assert_eq!(Pager::dragged(start, -95.0, 40.0, 25).offset, 2);
+        assert_eq!(Pager::dragged(start, 95.0, 40.0, 25).offset, 0);
+        assert_eq!(Pager::dragged(start, -4000.0, 40.0, 25).offset, 15);
+    }
+}
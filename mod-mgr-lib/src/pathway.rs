@@ -0,0 +1,173 @@
+//! Where the game and its per-user data are, found from the settings or by searching for them.
+//! The GUI and the command line both start by finding these.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    diagnostics::DiagnosticPaths,
+    settings::{settings_dir, Settings},
+    util::{
+        default_paths,
+        divinity_registry_helper::{self, get_game_install_path},
+        game_paths,
+    },
+    BG3_STEAM_ID,
+};
+
+#[derive(Debug, Clone)]
+pub struct PathwayData {
+    /// Path to the root game folder.  
+    /// i.e. `SteamLibrary/steamapps/common/Baldur's Gate 3`  
+    /// i.e. `.steam/steamapps/common/Baldur's Gate 3`
+    pub install_path: PathBuf,
+
+    /// Windows: Path to `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3`  
+    /// Linux: Path to `steamapps/compatdata/1086940/pfx/drive_c/users/steamuser/AppData/Local/Larian Studios/Baldur's Gate 3`
+    /// in the Proton prefix.  
+    /// MacOS: Path to `~/Documents/Larian Studios/Baldur's Gate 3`
+    pub larian_documents_folder: PathBuf,
+    /// `larian_documents_folder/Mods`
+    pub documents_mods_path: PathBuf,
+    /// `larian_documents_folder/GMCampaigns`
+    pub documents_gm_campaigns_path: PathBuf,
+    /// `larian_documents_folder/PlayerProfiles`
+    pub documents_profiles_path: PathBuf,
+    // TODO: last save file path
+    // TODO: script extendere latest release url
+    // TODO: script extender latest release version
+}
+impl PathwayData {
+    // TODO(minor): Does current game data path really need to be a separate field in this impl? Or even in the original?
+    /// Create a new [`PathwayData`] instance from the given information.  
+    /// If the supplied `larian_documents_folder` argument is not set it will be inferred.
+    pub fn new(
+        settings: &mut Settings,
+        current_game_data_path: &Path,
+        larian_documents_folder: Option<PathBuf>,
+    ) -> PathwayData {
+        // We find the install path first because on Linux the documents folder is inside the
+        // Proton prefix, which is in the same Steam library as the game.
+        let install_path = PathwayData::find_install_path(settings, current_game_data_path);
+
+        // TODO: I don't really know why they initialize documents game folder when they already have it set in a json. Just load it from there? I just skip that.
+
+        let larian_documents_folder = larian_documents_folder
+            .or_else(|| game_paths::find_larian_documents_folder(install_path.as_deref()))
+            .unwrap_or_default();
+
+        let pathway = PathwayData {
+            documents_mods_path: larian_documents_folder.join(game_paths::DOCUMENTS_MODS_FOLDER),
+            documents_gm_campaigns_path: larian_documents_folder
+                .join(game_paths::DOCUMENTS_GM_CAMPAIGNS_FOLDER),
+            documents_profiles_path: larian_documents_folder
+                .join(game_paths::DOCUMENTS_PROFILES_FOLDER),
+            larian_documents_folder,
+
+            install_path: install_path.unwrap_or_default(),
+        };
+
+        // The folder that `Larian Studios/Baldur's Gate 3` is in. On Windows this is
+        // `%LOCALAPPDATA%`.
+        // On Linux we only create the folders if this exists, because otherwise Proton has not
+        // set up the prefix yet and creating parts of it ourselves would confuse it.
+        let documents_folder = pathway
+            .larian_documents_folder
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+
+        if !documents_folder.as_os_str().is_empty() && documents_folder.is_dir() {
+            if let Err(err) = std::fs::create_dir_all(&pathway.larian_documents_folder) {
+                eprintln!("Failed to create larian game documents folder: {err}");
+            }
+
+            if !pathway.documents_mods_path.is_dir() {
+                eprintln!(
+                    "No mods folder found at {:?}. Creating folder.",
+                    pathway.documents_mods_path
+                );
+                std::fs::create_dir(&pathway.documents_mods_path)
+                    .expect("Failed to create mods folder");
+            }
+
+            if !pathway.documents_gm_campaigns_path.is_dir() {
+                eprintln!(
+                    "No GM campaigns folder found at {:?}. Creating folder.",
+                    pathway.documents_gm_campaigns_path
+                );
+                std::fs::create_dir(&pathway.documents_gm_campaigns_path)
+                    .expect("Failed to create GM campaigns folder");
+            }
+
+            if !pathway.documents_profiles_path.is_dir() {
+                eprintln!(
+                    "No player profiles folder found at {:?}. Creating folder.",
+                    pathway.documents_profiles_path
+                );
+                std::fs::create_dir(&pathway.documents_profiles_path)
+                    .expect("Failed to create player profiles folder");
+            }
+        } else {
+            // TODO: show the error dialog so it isn't hidden
+            eprintln!("Failed to find the folder the Larian documents folder should be in. Has the game been run at least once? Got path: {documents_folder:?}");
+        }
+
+        // The check at the end of the C# version of this function is not done here because we
+        // can't (nicely) synchronously open a file dialogue. We have a separate view that is shown
+        // on startup for that.
+
+        pathway
+    }
+
+    pub fn diagnostic_paths(&self) -> DiagnosticPaths {
+        DiagnosticPaths {
+            install_path: self.install_path.clone(),
+            larian_documents_folder: self.larian_documents_folder.clone(),
+            mods_path: self.documents_mods_path.clone(),
+            profiles_path: self.documents_profiles_path.clone(),
+            settings_dir: settings_dir(),
+        }
+    }
+
+    /// Find the root game folder, either from the game data path if it is valid or by searching
+    /// for it.  
+    /// If the game executable path isn't set then it is filled in from the install path.
+    fn find_install_path(
+        settings: &mut Settings,
+        current_game_data_path: &Path,
+    ) -> Option<PathBuf> {
+        // If the current game data path isn't valid then we'll try to find it.
+        let install_path = if current_game_data_path.as_os_str().is_empty()
+            || !current_game_data_path.is_dir()
+        {
+            get_game_install_path(
+                default_paths::GOG_INFO.registry_32,
+                default_paths::GOG_INFO.registry_64,
+                BG3_STEAM_ID,
+                default_paths::STEAM_INFO.root_folder_name,
+            )
+            .filter(|install_path| !install_path.as_os_str().is_empty() && install_path.is_dir())?
+        } else {
+            // The data folder is directly within the install folder.
+            let install_path = current_game_data_path.parent()?;
+            install_path
+                .canonicalize()
+                .unwrap_or_else(|_| install_path.to_owned())
+        };
+
+        if !settings.game_executable_path.is_file() {
+            let exe_path = if !divinity_registry_helper::is_gog() {
+                install_path.join(default_paths::STEAM_INFO.exe_path)
+            } else {
+                install_path.join(default_paths::GOG_INFO.exe_path)
+            };
+
+            if exe_path.is_file() {
+                eprintln!("Exe path set to {exe_path:?}");
+                settings.game_executable_path = exe_path;
+            }
+        }
+
+        Some(install_path)
+    }
+}
@@ -0,0 +1,346 @@
+//! The game's player profiles, which are in `PlayerProfiles/<Profile>/` within the game's documents
+//! folder. Each profile has its own load order (`modsettings.lsx`).
+
+use std::path::{Path, PathBuf};
+
+use lsf::{parse_lsf, LSF};
+
+use crate::{
+    mod_data::{DivinityModDependencyData, ModData, ModVersion},
+    mod_settings::{
+        read_mod_settings, write_order_settings, ModSettingsError, MOD_SETTINGS_FILE_NAME,
+    },
+    MAIN_CAMPAIGN_FOLDER, MAIN_CAMPAIGN_UUID, MAIN_CAMPAIGN_VERSION,
+};
+
+/// The information about a single profile, within the profile's folder
+pub const PROFILE_FILE_NAME: &str = "profile8.lsf";
+/// Which profile is active, within the `PlayerProfiles` folder
+pub const PLAYER_PROFILES_FILE_NAME: &str = "playerprofiles8.lsf";
+/// The profile that the game creates by default
+pub const DEFAULT_PROFILE_NAME: &str = "Public";
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(std::io::Error),
+    Lsf(String),
+    /// The file did not have the region we expected
+    NoRegion(&'static str),
+}
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::Io(err) => write!(f, "{err}"),
+            ProfileError::Lsf(err) => write!(f, "Failed to parse profile: {err}"),
+            ProfileError::NoRegion(region) => write!(f, "Profile has no {region} region"),
+        }
+    }
+}
+impl std::error::Error for ProfileError {}
+impl From<std::io::Error> for ProfileError {
+    fn from(e: std::io::Error) -> Self {
+        ProfileError::Io(e)
+    }
+}
+
+fn read_lsf(path: &Path) -> Result<LSF, ProfileError> {
+    let data = std::fs::read(path)?;
+    parse_lsf(&data).map_err(|err| ProfileError::Lsf(err.to_string()))
+}
+
+/// Get a string attribute of the region with the given name.
+fn region_attr(lsf: &LSF, region: &str, attr: &str) -> Option<String> {
+    lsf.regions()
+        .find(|&node| lsf.node_name(node) == Some(region))
+        .and_then(|node| lsf.find_attribute(node, attr))
+        .and_then(|attr| lsf.attribute_string(attr))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// The name of the profile, which is also the name of its folder
+    pub name: String,
+    /// The name shown in game, which may be empty
+    pub display_name: String,
+    pub uuid: String,
+    pub folder: PathBuf,
+}
+impl Profile {
+    /// The name to show for the profile
+    pub fn title(&self) -> &str {
+        if self.display_name.is_empty() {
+            &self.name
+        } else {
+            &self.display_name
+        }
+    }
+
+    pub fn mod_settings_path(&self) -> PathBuf {
+        self.folder.join(MOD_SETTINGS_FILE_NAME)
+    }
+
+    /// Read the profile's load order. If the profile does not have one yet, then it is empty.
+    pub fn read_load_order(&self) -> Result<Vec<DivinityModDependencyData>, ModSettingsError> {
+        let path = self.mod_settings_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        read_mod_settings(&path)
+    }
+
+    /// Write the profile's load order. The mods' `md5` should be filled in.
+    pub fn write_load_order<'a>(
+        &self,
+        mods: impl IntoIterator<Item = &'a ModData>,
+    ) -> Result<(), ModSettingsError> {
+        let order: Vec<DivinityModDependencyData> = mods
+            .into_iter()
+            .map(DivinityModDependencyData::from_mod_data)
+            .collect();
+        self.write_order(&order)
+    }
+
+    /// Write the profile's load order, adding the main campaign at the start if it is missing.
+    pub fn write_order(&self, order: &[DivinityModDependencyData]) -> Result<(), ModSettingsError> {
+        write_order_settings(&self.mod_settings_path(), &with_main_campaign(order))
+    }
+}
+
+/// The entry for the game's main campaign, which the game expects to be the first module in
+/// `modsettings.lsx`.
+pub fn main_campaign_entry() -> DivinityModDependencyData {
+    DivinityModDependencyData {
+        uuid: MAIN_CAMPAIGN_UUID.to_string(),
+        name: MAIN_CAMPAIGN_FOLDER.to_string(),
+        folder: MAIN_CAMPAIGN_FOLDER.to_string(),
+        md5: String::new(),
+        version: ModVersion::from(MAIN_CAMPAIGN_VERSION),
+    }
+}
+
+/// The order with the main campaign first.
+pub fn with_main_campaign(order: &[DivinityModDependencyData]) -> Vec<DivinityModDependencyData> {
+    let mut result = Vec::with_capacity(order.len() + 1);
+    let campaign = order
+        .iter()
+        .find(|entry| entry.uuid.eq_ignore_ascii_case(MAIN_CAMPAIGN_UUID))
+        .cloned()
+        .unwrap_or_else(main_campaign_entry);
+    result.push(campaign);
+    result.extend(
+        order
+            .iter()
+            .filter(|entry| !entry.uuid.eq_ignore_ascii_case(MAIN_CAMPAIGN_UUID))
+            .cloned(),
+    );
+
+    result
+}
+
+/// Read the profile in the given folder.
+pub fn read_profile(folder: &Path) -> Result<Profile, ProfileError> {
+    let lsf = read_lsf(&folder.join(PROFILE_FILE_NAME))?;
+    if !lsf
+        .regions()
+        .any(|node| lsf.node_name(node) == Some("PlayerProfile"))
+    {
+        return Err(ProfileError::NoRegion("PlayerProfile"));
+    }
+
+    let folder_name = folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(Profile {
+        name: region_attr(&lsf, "PlayerProfile", "PlayerProfileName").unwrap_or(folder_name),
+        display_name: region_attr(&lsf, "PlayerProfile", "PlayerProfileDisplayName")
+            .unwrap_or_default(),
+        uuid: region_attr(&lsf, "PlayerProfile", "PlayerProfileID").unwrap_or_default(),
+        folder: folder.to_owned(),
+    })
+}
+
+/// Find the profiles in the `PlayerProfiles` folder, sorted by name.
+/// Folders without a valid profile are skipped.
+pub fn find_profiles(profiles_dir: &Path) -> std::io::Result<Vec<Profile>> {
+    let mut profiles = Vec::new();
+    for entry in std::fs::read_dir(profiles_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let folder = entry.path();
+        if !folder.join(PROFILE_FILE_NAME).is_file() {
+            continue;
+        }
+
+        match read_profile(&folder) {
+            Ok(profile) => profiles.push(profile),
+            Err(err) => eprintln!("Skipping profile at {folder:?}: {err}"),
+        }
+    }
+
+    profiles.sort_by_key(|p| p.name.to_lowercase());
+
+    Ok(profiles)
+}
+
+/// Read the uuid of the profile that the game last used.
+pub fn read_active_profile_uuid(profiles_dir: &Path) -> Result<Option<String>, ProfileError> {
+    let lsf = read_lsf(&profiles_dir.join(PLAYER_PROFILES_FILE_NAME))?;
+    Ok(region_attr(&lsf, "UserProfiles", "ActiveProfile").filter(|uuid| !uuid.is_empty()))
+}
+
+/// Pick the active profile. This is the one with `active_uuid`, or otherwise the default profile
+/// or the first one.
+pub fn active_profile<'a>(
+    profiles: &'a [Profile],
+    active_uuid: Option<&str>,
+) -> Option<&'a Profile> {
+    active_uuid
+        .and_then(|uuid| {
+            profiles
+                .iter()
+                .find(|p| !p.uuid.is_empty() && p.uuid.eq_ignore_ascii_case(uuid))
+        })
+        .or_else(|| profiles.iter().find(|p| p.name == DEFAULT_PROFILE_NAME))
+        .or_else(|| profiles.first())
+}
+
+/// Load the profiles and find the active one.
+/// Returns the profiles and the index of the active profile.
+pub fn load_profiles(profiles_dir: &Path) -> std::io::Result<(Vec<Profile>, Option<usize>)> {
+    let profiles = find_profiles(profiles_dir)?;
+
+    let active_uuid = match read_active_profile_uuid(profiles_dir) {
+        Ok(uuid) => uuid,
+        Err(err) => {
+            eprintln!("Failed to read the active profile: {err}");
+            None
+        }
+    };
+
+    let active = active_profile(&profiles, active_uuid.as_deref())
+        .and_then(|active| profiles.iter().position(|p| p == active));
+
+    Ok((profiles, active))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an uncompressed BG3 LSF with a single region holding string attributes.
+    fn lsf_bytes(region: &str, attrs: &[(&str, &str)]) -> Vec<u8> {
+        // Every name is in its own hash entry, the region name is first.
+        let mut names = Vec::new();
+        let name_count = 1 + attrs.len() as u32;
+        names.extend(name_count.to_le_bytes());
+        for name in std::iter::once(region).chain(attrs.iter().map(|(name, _)| *name)) {
+            names.extend(1u16.to_le_bytes());
+            names.extend((name.len() as u16).to_le_bytes());
+            names.extend(name.as_bytes());
+        }
+
+        // Region node: name, parent, next sibling, first attribute
+        let mut nodes = Vec::new();
+        for v in [0i32, -1, -1, if attrs.is_empty() { -1 } else { 0 }] {
+            nodes.extend(v.to_le_bytes());
+        }
+
+        let mut attributes = Vec::new();
+        let mut values = Vec::new();
+        for (i, (_, value)) in attrs.iter().enumerate() {
+            let length = value.len() as u32 + 1;
+            let next = if i + 1 == attrs.len() {
+                -1
+            } else {
+                i as i32 + 1
+            };
+            attributes.extend((((i as u32) + 1) << 16).to_le_bytes());
+            attributes.extend((22 | (length << 6)).to_le_bytes());
+            attributes.extend(next.to_le_bytes());
+            attributes.extend((values.len() as u32).to_le_bytes());
+            values.extend(value.as_bytes());
+            values.push(0);
+        }
+
+        let mut data = b"LSOF".to_vec();
+        data.extend(4u32.to_le_bytes());
+        data.extend(0i32.to_le_bytes());
+        for section in [&names, &nodes, &attributes, &values] {
+            data.extend((section.len() as u32).to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+        }
+        // Compression flags, unknown fields, and has_sibling_data
+        data.extend([0, 0, 0, 0]);
+        data.extend(1u32.to_le_bytes());
+        for section in [names, nodes, attributes, values] {
+            data.extend(section);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_profiles() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let public = dir.join("Public");
+        let other = dir.join("Other");
+        std::fs::create_dir_all(&public).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::create_dir_all(dir.join("NotAProfile")).unwrap();
+
+        std::fs::write(
+            public.join(PROFILE_FILE_NAME),
+            lsf_bytes(
+                "PlayerProfile",
+                &[
+                    ("PlayerProfileName", "Public"),
+                    ("PlayerProfileID", "uuid-public"),
+                ],
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            other.join(PROFILE_FILE_NAME),
+            lsf_bytes(
+                "PlayerProfile",
+                &[
+                    ("PlayerProfileDisplayName", "Second"),
+                    ("PlayerProfileName", "Other"),
+                    ("PlayerProfileID", "uuid-other"),
+                ],
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(PLAYER_PROFILES_FILE_NAME),
+            lsf_bytes("UserProfiles", &[("ActiveProfile", "UUID-OTHER")]),
+        )
+        .unwrap();
+
+        let (profiles, active) = load_profiles(dir).unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.title()).collect();
+        assert_eq!(names, vec!["Second", "Public"]);
+        assert_eq!(active, Some(0));
+        assert_eq!(profiles[1].uuid, "uuid-public");
+        assert_eq!(active_profile(&profiles, None).unwrap().name, "Public");
+
+        let profile = &profiles[0];
+        assert!(profile.read_load_order().unwrap().is_empty());
+        let mods = vec![ModData::new(
+            "A", "uuid-a", "A", 1, "Add-on", "", "", "", "",
+        )];
+        profile.write_load_order(&mods).unwrap();
+        let order = profile.read_load_order().unwrap();
+
+        assert_eq!(order.len(), 2);
+        assert_eq!(order[0].uuid, MAIN_CAMPAIGN_UUID);
+        assert_eq!(order[1].uuid, "uuid-a");
+    }
+}
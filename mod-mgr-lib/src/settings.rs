@@ -1,66 +1,478 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
-// TODO: provide these descriptions in the ui
+use crate::{launch::LaunchMethod, DIR_DATA};
 
-const SETTINGS_PATH: &str = "Data/settings.json";
+const SETTINGS_FILE_NAME: &str = "settings.json";
+/// Where the settings were stored before we used a per-user config directory. This is relative to
+/// the current directory, which is also where the C# mod manager keeps its settings.
+const LEGACY_SETTINGS_PATH: &str = "Data/settings.json";
+/// Name of our folder within the user's config directory.
+const CONFIG_DIR_NAME: &str = "bg3-mod-mgr";
+
+/// Get the directory that the settings (and other persistent mod manager data) are stored in.  
+/// Linux: `$XDG_CONFIG_HOME/bg3-mod-mgr`, typically `~/.config/bg3-mod-mgr`  
+/// MacOS: `~/Library/Application Support/bg3-mod-mgr`  
+/// Windows: `Data/`, relative to the current directory, like the C# version.
+pub fn settings_dir() -> PathBuf {
+    if cfg!(windows) {
+        return PathBuf::from(DIR_DATA);
+    }
+
+    dirs::config_dir()
+        .map(|dir| dir.join(CONFIG_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from(DIR_DATA))
+}
+
+/// Get the path to the settings file.
+pub fn settings_path() -> PathBuf {
+    settings_dir().join(SETTINGS_FILE_NAME)
+}
+
+/// A non-fatal problem encountered while loading the settings.  
+/// These are meant to be shown to the user, they don't stop the settings from being loaded.
+#[derive(Debug)]
+pub enum SettingsWarning {
+    /// There was no settings file, so the defaults were used.
+    NotFound(PathBuf),
+    /// The settings file existed but could not be read. The defaults were used.
+    Io(PathBuf, std::io::Error),
+    /// The settings file was not valid json. The defaults were used.
+    Parse(serde_json::Error),
+    /// The settings file was valid json, but not an object. The defaults were used.
+    NotAnObject,
+    /// A field had a value of the wrong type, so the default value was used for it.
+    InvalidField {
+        field: String,
+        error: serde_json::Error,
+    },
+    /// The settings were copied from the legacy location.
+    Migrated { from: PathBuf, to: PathBuf },
+    /// The settings were loaded from the legacy location, but we failed to copy them to the new
+    /// location.
+    MigrationFailed {
+        from: PathBuf,
+        to: PathBuf,
+        error: std::io::Error,
+    },
+    /// A path in the settings does not point at what it should.
+    InvalidPath {
+        field: &'static str,
+        path: PathBuf,
+        problem: PathProblem,
+    },
+}
+impl std::fmt::Display for SettingsWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsWarning::NotFound(path) => {
+                write!(f, "No settings file at {path:?}, using default settings")
+            }
+            SettingsWarning::Io(path, err) => {
+                write!(f, "Failed to read settings file at {path:?}: {err}")
+            }
+            SettingsWarning::Parse(err) => write!(f, "Failed to parse settings file: {err}"),
+            SettingsWarning::NotAnObject => {
+                write!(f, "Settings file did not contain a json object")
+            }
+            SettingsWarning::InvalidField { field, error } => {
+                write!(
+                    f,
+                    "Invalid value for setting {field}, using default: {error}"
+                )
+            }
+            SettingsWarning::Migrated { from, to } => {
+                write!(f, "Migrated settings from {from:?} to {to:?}")
+            }
+            SettingsWarning::MigrationFailed { from, to, error } => write!(
+                f,
+                "Failed to migrate settings from {from:?} to {to:?}: {error}"
+            ),
+            SettingsWarning::InvalidPath {
+                field,
+                path,
+                problem,
+            } => write!(f, "Setting {field} ({path:?}) {problem}"),
+        }
+    }
+}
+
+/// The paks that every install of the game has in its data folder
+pub const GAME_DATA_PAKS: &[&str] = &["Gustav.pak", "Shared.pak"];
+
+/// Why a path in the settings isn't what it should be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathProblem {
+    Missing,
+    NotADirectory,
+    NotAFile,
+    /// The game's data folder doesn't have one of [`GAME_DATA_PAKS`]
+    MissingPak(&'static str),
+    NotExecutable,
+}
+impl std::fmt::Display for PathProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathProblem::Missing => write!(f, "does not exist"),
+            PathProblem::NotADirectory => write!(f, "is not a directory"),
+            PathProblem::NotAFile => write!(f, "is not a file"),
+            PathProblem::MissingPak(pak) => write!(f, "does not contain {pak}"),
+            PathProblem::NotExecutable => write!(f, "is not an executable"),
+        }
+    }
+}
+
+pub fn check_directory(path: &Path) -> Result<(), PathProblem> {
+    if !path.exists() {
+        Err(PathProblem::Missing)
+    } else if !path.is_dir() {
+        Err(PathProblem::NotADirectory)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_file(path: &Path) -> Result<(), PathProblem> {
+    if !path.exists() {
+        Err(PathProblem::Missing)
+    } else if !path.is_file() {
+        Err(PathProblem::NotAFile)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that the path is the game's `Data` folder, by looking for its paks.
+pub fn check_game_data_path(path: &Path) -> Result<(), PathProblem> {
+    check_directory(path)?;
+    match GAME_DATA_PAKS.iter().find(|pak| !path.join(pak).is_file()) {
+        Some(pak) => Err(PathProblem::MissingPak(pak)),
+        None => Ok(()),
+    }
+}
+
+/// Check that the path is a program. On Linux and MacOS the game's `.exe` is run through Proton
+/// or Wine, so it counts even without the executable permission.
+pub fn check_game_executable_path(path: &Path) -> Result<(), PathProblem> {
+    check_file(path)?;
+    let is_exe = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    if is_exe || has_executable_permission(path) {
+        Ok(())
+    } else {
+        Err(PathProblem::NotExecutable)
+    }
+}
+
+#[cfg(unix)]
+fn has_executable_permission(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|meta| meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn has_executable_permission(_path: &Path) -> bool {
+    false
+}
+
+/// The result of [`Settings::load`].
+#[derive(Debug)]
+pub struct LoadedSettings {
+    pub settings: Settings,
+    /// The path that the settings were loaded from, or would have been loaded from if it existed.
+    pub path: PathBuf,
+    pub warnings: Vec<SettingsWarning>,
+}
+
+/// Defines `setting_doc!`, which gives the description of a setting by its name in the settings
+/// file, and [`Settings::description`], which looks them up for the ui. The descriptions are also
+/// the docs of the [`Settings`] fields, so the two can't drift apart.
+macro_rules! setting_descriptions {
+    ($($field:ident => $text:literal,)*) => {
+        macro_rules! setting_doc {
+            $(($field) => { $text };)*
+        }
+
+        impl Settings {
+            /// The description of the setting, by its name in the settings file, to show in the
+            /// ui.
+            pub fn description(field: &str) -> Option<&'static str> {
+                $(
+                    if field == stringify!($field) {
+                        return Some($text);
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+setting_descriptions! {
+    GameDataPath => "Path to the game's data folder, which holds Gustav.pak and Shared.pak.",
+    GameExecutablePath => "Path to the game's executable, like bin/bg3.exe.",
+    DocumentsFolderPathOverride => "Override the default location of the game's documents \
+        folder, which holds the mods and profiles.",
+    TelemetryDisabled => "Always disable Larian's telemetry. It is always disabled when mods \
+        are active.",
+    SavedLoadOrdersPath => "Folder that exported load orders are saved to.",
+    WorkshopPath => "Path to the workshop folder. Currently unused.",
+    GameStoryLogEnabled => "Enable the Osiris story log (osiris.log) when launching.",
+    AutoAddMissingDependenciesOnExport => "Add the dependencies of the active mods when \
+        exporting a load order.",
+    DisableMissingModWarnings => "Don't warn about mods in a load order that aren't installed.",
+    DisableWorkshopTagCheck => "Don't look up the tags of mods on the workshop.",
+    ExportDefaultExtenderSettings => "Write every Script Extender setting, even the ones that \
+        are the extender's defaults.",
+    ShiftFocusOnSwap => "When moving the selected mods to the other list with Enter, move focus \
+        there too.",
+    SaveWindowLocation => "Remember where the window was.",
+    LaunchDX11 => "Launch the game with DirectX 11 rather than Vulkan.",
+    SkipLauncher => "Skip Larian's launcher when launching the game.",
+    CheckForUpdates => "Check for updates when the mod manager starts.",
+    ModUpdateManifest => "Url or path of the manifest listing the latest versions of mods, used \
+        to check for mod updates.",
+    GameLaunchParams => "Extra arguments to pass to the game when launching it.",
+    LaunchMethod => "How to launch the game. Defaults to running the executable on Windows, and \
+        going through Steam elsewhere.",
+    ProtonPath => "The Proton version to launch the game with, when launching through Proton.",
+    HandheldMode => "Larger rows, and pages instead of scrolling, for touch screens like the \
+        Steam Deck's.",
+    Theme => "The colors and font sizes of the mod manager.",
+    PollForModChanges => "Check the mods folder and profiles for changes every few seconds, \
+        rather than waiting for the system to report them. Watching can start without errors on \
+        SD cards and network drives and then never report a change, so turn this on if changes \
+        aren't picked up.",
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[serde(rename_all = "PascalCase", default)]
 pub struct Settings {
     // TODO: give the paths decent default values on the common platforms.
-    /// Path to the game's data folder.  
+    #[doc = setting_doc!(GameDataPath)]
+    ///
     /// Ex: "C:/Steam/steamapps/common/Baldurs Gate 3/Data"
     pub game_data_path: PathBuf,
-    /// Path to the game's executable.  
+    #[doc = setting_doc!(GameExecutablePath)]
+    ///
     /// Ex: "C:/Steam/steamapps/common/Baldurs Gate 3/bin/bg3.exe"
     pub game_executable_path: PathBuf,
-    /// Override the default location to `%LOCALAPPDATA/Larian Studios/Baldur's Gate 3/`
+    #[doc = setting_doc!(DocumentsFolderPathOverride)]
+    ///
+    /// The default is `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3/`.
     pub documents_folder_path_override: PathBuf,
-    /// Whether Larian's telemetry options for BG3 will always be disabled, regardless of active
-    /// mods. Telemetry is always disabled if mods are active.
+    #[doc = setting_doc!(TelemetryDisabled)]
     pub telemetry_disabled: bool,
-    /// Folder name where load orders should be saved.
+    #[doc = setting_doc!(SavedLoadOrdersPath)]
     pub saved_load_orders_path: PathBuf,
-    /// Path to the workshop folder. Currently unused.
+    #[doc = setting_doc!(WorkshopPath)]
     pub workshop_path: PathBuf,
-    /// When launching the game, enabled the Osiris story log (osiris.log)
+    #[doc = setting_doc!(GameStoryLogEnabled)]
     pub game_story_log_enabled: bool,
+    #[doc = setting_doc!(AutoAddMissingDependenciesOnExport)]
     pub auto_add_missing_dependencies_on_export: bool,
     // TODO: auto updates
     // pub enable_automatic_updates: bool,
-    /// If a load order is missing mods, no warnings will be displayed
+    #[doc = setting_doc!(DisableMissingModWarnings)]
     pub disable_missing_mod_warnings: bool,
+    #[doc = setting_doc!(DisableWorkshopTagCheck)]
+    ///
     /// The mod manager will try and find mod tags from the workshop by default
     pub disable_workshop_tag_check: bool,
-    /// Export all values, even if it matches a default extender value
+    #[doc = setting_doc!(ExportDefaultExtenderSettings)]
     pub export_default_extender_settings: bool,
-    /// When moving selected mods to the opposite list with Enter, move focus to that list as well
+    #[doc = setting_doc!(ShiftFocusOnSwap)]
     pub shift_focus_on_swap: bool,
+    #[doc = setting_doc!(SaveWindowLocation)]
     pub save_window_location: bool,
+    #[doc = setting_doc!(LaunchDX11)]
     #[serde(rename = "LaunchDX11")]
     pub launch_dx11: bool,
-    /// Pass `--skip-launcher` when launching the game.
+    #[doc = setting_doc!(SkipLauncher)]
+    ///
+    /// This passes `--skip-launcher`.
     pub skip_launcher: bool,
-    /// Automatically check for updates when the program starts
+    #[doc = setting_doc!(CheckForUpdates)]
     pub check_for_updates: bool,
+    #[doc = setting_doc!(ModUpdateManifest)]
+    ///
+    /// See [`crate::mod_updates`].
+    pub mod_update_manifest: String,
+    #[doc = setting_doc!(GameLaunchParams)]
     pub game_launch_params: String,
-    // // TODO: linux specific settings? Like proton version to use?
-    // pub launch: LaunchSettings,
+    #[doc = setting_doc!(LaunchMethod)]
+    pub launch_method: LaunchMethod,
+    #[doc = setting_doc!(ProtonPath)]
+    ///
+    /// Used with [`LaunchMethod::Proton`]. This is the folder of the compatibility tool, like
+    /// `compatibilitytools.d/GE-Proton8-16`.
+    pub proton_path: PathBuf,
+    #[doc = setting_doc!(HandheldMode)]
+    pub handheld_mode: HandheldMode,
+    #[doc = setting_doc!(Theme)]
+    pub theme: ThemeKind,
+    #[doc = setting_doc!(PollForModChanges)]
+    pub poll_for_mod_changes: bool,
 }
 impl Settings {
-    pub fn load() -> std::io::Result<Settings> {
-        todo!()
+    /// Load the settings from [`settings_path`].  
+    /// If there is no settings file there, but there is one at the legacy location (`Data/` in the
+    /// current directory) then it is loaded from there and copied over.  
+    /// This never fails, any problems are reported as warnings and the defaults are used in place
+    /// of whatever was invalid.
+    pub fn load() -> LoadedSettings {
+        Settings::load_or_migrate(settings_path(), PathBuf::from(LEGACY_SETTINGS_PATH))
+    }
+
+    /// Load the settings from `path`, or from `legacy_path` and copy them to `path` if only the
+    /// legacy file exists.  
+    /// See [`Settings::load`]
+    fn load_or_migrate(path: PathBuf, legacy_path: PathBuf) -> LoadedSettings {
+        if path.is_file() || path == legacy_path || !legacy_path.is_file() {
+            return Settings::load_from(&path);
+        }
+
+        let mut loaded = Settings::load_from(&legacy_path);
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let migrate = std::fs::create_dir_all(dir).and_then(|_| std::fs::copy(&legacy_path, &path));
+        match migrate {
+            Ok(_) => {
+                loaded.warnings.push(SettingsWarning::Migrated {
+                    from: legacy_path,
+                    to: path.clone(),
+                });
+                loaded.path = path;
+            }
+            Err(error) => loaded.warnings.push(SettingsWarning::MigrationFailed {
+                from: legacy_path,
+                to: path,
+                error,
+            }),
+        }
+
+        loaded
+    }
+
+    /// Load the settings from a specific file.  
+    /// See [`Settings::load`]
+    pub fn load_from(path: &Path) -> LoadedSettings {
+        let mut warnings = Vec::new();
+
+        let settings = match std::fs::read_to_string(path) {
+            Ok(content) => Settings::from_json_lenient(&content, &mut warnings),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                warnings.push(SettingsWarning::NotFound(path.to_owned()));
+                Settings::default()
+            }
+            Err(err) => {
+                warnings.push(SettingsWarning::Io(path.to_owned(), err));
+                Settings::default()
+            }
+        };
+
+        settings.validate_paths(&mut warnings);
+
+        LoadedSettings {
+            settings,
+            path: path.to_owned(),
+            warnings,
+        }
+    }
+
+    /// Parse the settings from json.  
+    /// Missing fields are filled in from [`Settings::default`], and unknown fields (such as the
+    /// ones the C# mod manager writes that we don't support) are ignored.  
+    /// Fields with invalid values are replaced with their default value and a warning.
+    pub fn from_json_lenient(content: &str, warnings: &mut Vec<SettingsWarning>) -> Settings {
+        let value: serde_json::Value = match serde_json::from_str(content) {
+            Ok(value) => value,
+            Err(err) => {
+                warnings.push(SettingsWarning::Parse(err));
+                return Settings::default();
+            }
+        };
+
+        let serde_json::Value::Object(fields) = value else {
+            warnings.push(SettingsWarning::NotAnObject);
+            return Settings::default();
+        };
+
+        // The common case is that it is entirely valid.
+        if let Ok(settings) = serde_json::from_value(serde_json::Value::Object(fields.clone())) {
+            return settings;
+        }
+
+        // Otherwise we add the fields one at a time, so that a single bad field doesn't reset
+        // everything.
+        let mut current = match serde_json::to_value(Settings::default()) {
+            Ok(serde_json::Value::Object(current)) => current,
+            _ => unreachable!("Settings always serializes to an object"),
+        };
+        for (field, value) in fields {
+            if !current.contains_key(&field) {
+                // Unknown field, serde would ignore it anyway.
+                continue;
+            }
+
+            let mut attempt = current.clone();
+            attempt.insert(field.clone(), value);
+            match serde_json::from_value::<Settings>(serde_json::Value::Object(attempt.clone())) {
+                Ok(_) => current = attempt,
+                Err(error) => warnings.push(SettingsWarning::InvalidField { field, error }),
+            }
+        }
+
+        serde_json::from_value(serde_json::Value::Object(current)).unwrap_or_default()
     }
 
+    /// Check that the paths which are set point at something sensible.  
+    /// Empty paths are considered unset and are not warned about.
+    pub fn validate_paths(&self, warnings: &mut Vec<SettingsWarning>) {
+        let mut check = |field, path: &Path, check_path: fn(&Path) -> Result<(), PathProblem>| {
+            if path.as_os_str().is_empty() {
+                return;
+            }
+
+            if let Err(problem) = check_path(path) {
+                warnings.push(SettingsWarning::InvalidPath {
+                    field,
+                    path: path.to_owned(),
+                    problem,
+                });
+            }
+        };
+
+        check("GameDataPath", &self.game_data_path, check_game_data_path);
+        check(
+            "GameExecutablePath",
+            &self.game_executable_path,
+            check_game_executable_path,
+        );
+        check(
+            "DocumentsFolderPathOverride",
+            &self.documents_folder_path_override,
+            check_directory,
+        );
+        check("WorkshopPath", &self.workshop_path, check_directory);
+    }
+
+    /// Save the settings to [`settings_path`], creating the directory if needed.
     pub fn save(&self) -> anyhow::Result<()> {
         let contents = serde_json::to_string_pretty(self)?;
+        std::fs::create_dir_all(settings_dir())?;
         let mut file = std::fs::OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
-            .open(SETTINGS_PATH)?;
+            .open(settings_path())?;
 
         file.write_all(contents.as_bytes())?;
 
@@ -107,8 +519,80 @@ impl Default for Settings {
             export_default_extender_settings: false,
             skip_launcher: true,
             check_for_updates: true,
+            mod_update_manifest: String::new(),
             game_launch_params: String::new(),
-            // launch: LaunchSettings::default(),
+            launch_method: LaunchMethod::default_for_platform(),
+            proton_path: PathBuf::new(),
+            handheld_mode: HandheldMode::Auto,
+            theme: ThemeKind::Dark,
+            poll_for_mod_changes: false,
+        }
+    }
+}
+
+/// Whether we're running on a Steam Deck, going by the `SteamDeck` environment variable that
+/// Steam sets.
+pub fn is_steam_deck() -> bool {
+    std::env::var("SteamDeck").is_ok_and(|value| value == "1")
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandheldMode {
+    /// Enabled on a Steam Deck
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+impl HandheldMode {
+    pub fn is_enabled(self) -> bool {
+        match self {
+            HandheldMode::Auto => is_steam_deck(),
+            HandheldMode::On => true,
+            HandheldMode::Off => false,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            HandheldMode::Auto => "Auto (Steam Deck)",
+            HandheldMode::On => "On",
+            HandheldMode::Off => "Off",
+        }
+    }
+
+    /// The next mode, for a button that cycles through them
+    pub fn next(self) -> HandheldMode {
+        match self {
+            HandheldMode::Auto => HandheldMode::On,
+            HandheldMode::On => HandheldMode::Off,
+            HandheldMode::Off => HandheldMode::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+impl ThemeKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            ThemeKind::Dark => "Dark",
+            ThemeKind::Light => "Light",
+            ThemeKind::HighContrast => "High Contrast",
+        }
+    }
+
+    /// The next theme, for a button that cycles through them
+    pub fn next(self) -> ThemeKind {
+        match self {
+            ThemeKind::Dark => ThemeKind::Light,
+            ThemeKind::Light => ThemeKind::HighContrast,
+            ThemeKind::HighContrast => ThemeKind::Dark,
         }
     }
 }
@@ -135,10 +619,11 @@ impl Default for Settings {
 //     pub media_path: bool,
 // }
 
-// TODO: keybindings
+/// The Script Extender's settings file, which is in the game's `bin` folder
+pub const SCRIPT_EXTENDER_SETTINGS_FILE_NAME: &str = "ScriptExtenderSettings.json";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
 pub struct ScriptExtenderSettings {
     /// Make the Osiris extension functionality available ingame or in the editor
     pub enable_extensions: bool,
@@ -161,6 +646,54 @@ pub struct ScriptExtenderSettings {
     pub lua_builtin_resource_directory: PathBuf,
     pub default_to_client_console: bool,
     pub show_perf_warnings: bool,
+
+    /// Settings we don't know about, such as ones added by newer versions of the extender. These
+    /// are kept so that saving doesn't remove them.
+    #[serde(flatten)]
+    pub unknown: serde_json::Map<String, serde_json::Value>,
+}
+impl ScriptExtenderSettings {
+    pub fn path(game_bin_dir: &Path) -> PathBuf {
+        game_bin_dir.join(SCRIPT_EXTENDER_SETTINGS_FILE_NAME)
+    }
+
+    /// Load the settings from the file. Returns `None` if it does not exist.  
+    /// Settings missing from the file are given their default values.
+    pub fn load_from(path: &Path) -> anyhow::Result<Option<ScriptExtenderSettings>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(serde_json::from_str(
+            text.trim_start_matches('\u{feff}'),
+        )?))
+    }
+
+    /// Convert the settings to json.  
+    /// Like the C# version, settings which are the same as the extender's defaults are left out
+    /// unless `export_defaults` is set, so that the extender's own defaults apply to them. Unknown
+    /// settings are always kept.
+    pub fn to_json(&self, export_defaults: bool) -> serde_json::Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        if !export_defaults {
+            let defaults = serde_json::to_value(ScriptExtenderSettings::default())?;
+            if let (Some(map), Some(defaults)) = (value.as_object_mut(), defaults.as_object()) {
+                map.retain(|key, value| {
+                    self.unknown.contains_key(key) || defaults.get(key) != Some(value)
+                });
+            }
+        }
+
+        serde_json::to_string_pretty(&value)
+    }
+
+    /// Save the settings to the file, see [`ScriptExtenderSettings::to_json`].
+    pub fn save_to(&self, path: &Path, export_defaults: bool) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_json(export_defaults)?)?;
+        Ok(())
+    }
 }
 impl Default for ScriptExtenderSettings {
     fn default() -> Self {
@@ -184,6 +717,160 @@ impl Default for ScriptExtenderSettings {
             lua_builtin_resource_directory: PathBuf::default(),
             default_to_client_console: false,
             show_perf_warnings: false,
+            unknown: serde_json::Map::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let data = root.join("Data");
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::write(data.join("Gustav.pak"), b"").unwrap();
+        std::fs::write(root.join("notes.txt"), b"").unwrap();
+        std::fs::write(root.join("bg3.exe"), b"").unwrap();
+
+        assert_eq!(
+            check_game_data_path(&data),
+            Err(PathProblem::MissingPak("Shared.pak"))
+        );
+        std::fs::write(data.join("Shared.pak"), b"").unwrap();
+        assert_eq!(check_game_data_path(&data), Ok(()));
+        assert_eq!(
+            check_game_data_path(&root.join("Missing")),
+            Err(PathProblem::Missing)
+        );
+        assert_eq!(
+            check_game_data_path(&root.join("notes.txt")),
+            Err(PathProblem::NotADirectory)
+        );
+
+        assert_eq!(check_game_executable_path(&root.join("bg3.exe")), Ok(()));
+        assert_eq!(
+            check_game_executable_path(&data),
+            Err(PathProblem::NotAFile)
+        );
+        assert_eq!(
+            check_game_executable_path(&root.join("notes.txt")),
+            Err(PathProblem::NotExecutable)
+        );
+    }
+
+    #[test]
+    fn test_descriptions() {
+        // Every setting in the settings file is described
+        let value = serde_json::to_value(Settings::default()).unwrap();
+        for field in value.as_object().unwrap().keys() {
+            let description = Settings::description(field);
+            assert!(
+                description.is_some_and(|text| !text.is_empty()),
+                "{field} has no description"
+            );
         }
+
+        assert_eq!(
+            Settings::description("Theme"),
+            Some("The colors and font sizes of the mod manager.")
+        );
+        assert_eq!(Settings::description("Missing"), None);
+    }
+
+    #[test]
+    fn test_from_json_lenient() {
+        let mut warnings = Vec::new();
+
+        // Missing fields are defaulted and the C# manager's extra fields are ignored
+        let settings = Settings::from_json_lenient(
+            r#"{ "TelemetryDisabled": true, "DarkThemeEnabled": true }"#,
+            &mut warnings,
+        );
+        assert!(warnings.is_empty());
+        assert!(settings.telemetry_disabled);
+        assert!(settings.check_for_updates);
+        assert_eq!(settings.saved_load_orders_path, PathBuf::from("Orders/"));
+
+        // A bad field is defaulted on its own
+        let settings = Settings::from_json_lenient(
+            r#"{ "TelemetryDisabled": true, "SkipLauncher": "yes", "Theme": "Light" }"#,
+            &mut warnings,
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            SettingsWarning::InvalidField { field, .. } if field == "SkipLauncher"
+        ));
+        assert!(settings.skip_launcher);
+        assert!(settings.telemetry_disabled);
+        assert_eq!(settings.theme, ThemeKind::Light);
+
+        warnings.clear();
+        let settings = Settings::from_json_lenient("[1, 2]", &mut warnings);
+        assert!(matches!(warnings[..], [SettingsWarning::NotAnObject]));
+        assert!(settings.check_for_updates);
+
+        warnings.clear();
+        Settings::from_json_lenient("{ not json", &mut warnings);
+        assert!(matches!(warnings[..], [SettingsWarning::Parse(_)]));
+    }
+
+    #[test]
+    fn test_migrate_settings() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config").join(SETTINGS_FILE_NAME);
+        let legacy_path = temp.path().join(LEGACY_SETTINGS_PATH);
+
+        // Nothing to load or migrate
+        let loaded = Settings::load_or_migrate(path.clone(), legacy_path.clone());
+        assert_eq!(loaded.path, path);
+        assert!(matches!(
+            loaded.warnings[..],
+            [SettingsWarning::NotFound(_)]
+        ));
+
+        std::fs::create_dir_all(legacy_path.parent().unwrap()).unwrap();
+        std::fs::write(&legacy_path, r#"{ "LaunchDX11": true }"#).unwrap();
+        let loaded = Settings::load_or_migrate(path.clone(), legacy_path.clone());
+        assert!(loaded.settings.launch_dx11);
+        assert_eq!(loaded.path, path);
+        assert!(matches!(
+            &loaded.warnings[..],
+            [SettingsWarning::Migrated { from, to }] if *from == legacy_path && *to == path
+        ));
+        assert!(path.is_file());
+
+        // Once migrated, the new file is used and the legacy one is left alone
+        std::fs::write(&path, r#"{ "LaunchDX11": false }"#).unwrap();
+        let loaded = Settings::load_or_migrate(path.clone(), legacy_path.clone());
+        assert!(!loaded.settings.launch_dx11);
+        assert!(loaded.warnings.is_empty());
+        assert!(Settings::load_from(&legacy_path).settings.launch_dx11);
+    }
+
+    #[test]
+    fn test_extender_settings() {
+        let text = r#"{ "EnableExtensions": false, "CreateConsole": true, "SomeNewSetting": 5 }"#;
+        let settings: ScriptExtenderSettings = serde_json::from_str(text).unwrap();
+        assert!(!settings.enable_extensions);
+        assert!(settings.create_console);
+        assert_eq!(settings.debugger_port, 9999);
+        assert_eq!(settings.unknown.len(), 1);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&settings.to_json(false).unwrap()).unwrap();
+        let json = json.as_object().unwrap();
+        assert_eq!(json.len(), 3);
+        assert_eq!(json["EnableExtensions"], false);
+        assert_eq!(json["SomeNewSetting"], 5);
+
+        let all = settings.to_json(true).unwrap();
+        let reloaded: ScriptExtenderSettings = serde_json::from_str(&all).unwrap();
+        assert_eq!(reloaded, settings);
+        assert!(all.contains("DebuggerPort"));
     }
 }
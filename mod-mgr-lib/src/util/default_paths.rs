@@ -53,6 +53,6 @@ pub const DOCUMENTS_GAME_FOLDER: &str = "Larian Studios\\Baldur's Gate 3";
 /// is automatically expanded in Rust.
 pub const APP_DATA_GAME_FOLDER: &str = "Larian Studios\\Baldur's Gate 3";
 // pub const APP_DATA_GAME_FOLDER: &str = "%LOCALAPPDATA%\\Larian Studios\\Baldur's Gate 3";
-// TODO: Linux + MacOS default data game folder
+// Linux + MacOS use `mod_mgr_lib::util::game_paths` since they have to be searched for.
 
 pub const GAME_DATA_FOLDER: &str = "Data";
@@ -7,6 +7,8 @@ use std::path::PathBuf;
 
 use steamlocate::SteamDir;
 
+use crate::util::steam_vdf;
+
 pub const PATH_STEAM_WORKSHOP_FOLDER: &str = "steamapps/workshop";
 
 static STEAM_INSTALL_DIR: std::sync::OnceLock<Option<SteamDir>> = std::sync::OnceLock::new();
@@ -57,11 +59,14 @@ pub fn is_gog() -> bool {
     IS_GOG.get().copied().unwrap_or(false)
 }
 
-/// Unlike the C# version this just takes the game id rather than the path for it.
+/// Unlike the C# version this just takes the game id rather than the path for it.  
+/// `steam_folder_name` is the usual name of the game's folder in `steamapps/common`, which is
+/// used if we find a library without an app manifest for the game.
 pub fn get_game_install_path(
     gog_reg_32: &str,
     gog_reg_64: &str,
     steam_game_id: u32,
+    steam_folder_name: &str,
 ) -> Option<PathBuf> {
     if let Some(mut steam_dir) = get_steam_install_dir() {
         // Note: we aren't really checking whether the directory exists. It should do that when it
@@ -71,7 +76,7 @@ pub fn get_game_install_path(
             return LAST_GAME_PATH.get().unwrap().clone();
         }
 
-        println!("Looking for game with app id: {steam_game_id}");
+        eprintln!("Looking for game with app id: {steam_game_id}");
         if let Some(folder) = steam_dir.app(&steam_game_id) {
             let folder = folder.path.clone();
             eprintln!("Found game at {folder:?}");
@@ -82,8 +87,19 @@ pub fn get_game_install_path(
             let _ = LAST_GAME_PATH.set(Some(folder));
             return LAST_GAME_PATH.get().unwrap().clone();
         }
-        // We don't need to manually search the other library folders because steamlocate
-        // handles that for us.
+    }
+
+    // steamlocate only looks at the main Steam install and trusts its list of libraries, which
+    // misses things like Flatpak Steam or an SD card that was mounted somewhere else. So we
+    // search every library we can find ourselves.
+    let libraries = steam_vdf::all_library_folders();
+    if let Some(folder) = steam_vdf::find_app_install(&libraries, steam_game_id, steam_folder_name)
+    {
+        eprintln!("Found game at {folder:?} by searching Steam libraries");
+
+        let _ = IS_GOG.set(false);
+        let _ = LAST_GAME_PATH.set(Some(folder));
+        return LAST_GAME_PATH.get().unwrap().clone();
     }
 
     // Since we failed to find the steam folder, we now try to find the gog folder.
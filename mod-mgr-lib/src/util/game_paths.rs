@@ -0,0 +1,248 @@
+//! Discovery of where the game keeps its per-user data (`Larian Studios/Baldur's Gate 3`), which
+//! holds the `Mods` and `PlayerProfiles` folders.
+//!
+//! On Windows this is just `%LOCALAPPDATA%`, but on Linux the game runs under Proton, and so it is
+//! inside the Wine prefix that Steam made for the game. That prefix lives in the `compatdata`
+//! folder of the Steam library the game is installed in, which is not necessarily the main Steam
+//! folder.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    util::{
+        divinity_registry_helper::get_steam_install_dir,
+        steam_vdf::{all_library_folders, LibraryFolder},
+    },
+    BG3_STEAM_ID,
+};
+
+/// The game's folder within the user's local appdata folder (Windows, Proton) or their documents
+/// folder (MacOS).
+pub const DOCUMENTS_GAME_FOLDER: &str = "Larian Studios/Baldur's Gate 3";
+/// The local appdata folder within a Proton prefix (`steamapps/compatdata/<app id>/`).
+pub const PROTON_APPDATA_FOLDER: &str = "pfx/drive_c/users/steamuser/AppData/Local";
+
+pub const DOCUMENTS_MODS_FOLDER: &str = "Mods";
+pub const DOCUMENTS_GM_CAMPAIGNS_FOLDER: &str = "GMCampaigns";
+pub const DOCUMENTS_PROFILES_FOLDER: &str = "PlayerProfiles";
+
+/// Get the places that Steam is commonly installed to on this platform, whether or not they exist.
+/// These are what we check in addition to whatever steamlocate found.
+pub fn steam_root_candidates() -> Vec<PathBuf> {
+    match dirs::home_dir() {
+        Some(home) => steam_root_candidates_in(&home),
+        None => Vec::new(),
+    }
+}
+
+/// [`steam_root_candidates`] within the given home folder
+fn steam_root_candidates_in(home: &Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "macos") {
+        vec![home.join("Library/Application Support/Steam")]
+    } else if cfg!(windows) {
+        Vec::new()
+    } else {
+        vec![
+            home.join(".steam/steam"),
+            home.join(".steam/root"),
+            home.join(".local/share/Steam"),
+            // Flatpak Steam
+            home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+            home.join(".var/app/com.valvesoftware.Steam/data/Steam"),
+        ]
+    }
+}
+
+/// Get the Steam folders that exist, with the one steamlocate found first.
+/// Symlinked folders (`~/.steam/steam` typically points at `~/.local/share/Steam`) are only
+/// included once.
+pub fn steam_roots() -> Vec<PathBuf> {
+    let located = get_steam_install_dir().map(|steam_dir| steam_dir.path);
+    let mut roots: Vec<PathBuf> = Vec::new();
+
+    for root in located.into_iter().chain(steam_root_candidates()) {
+        if !root.is_dir() {
+            continue;
+        }
+
+        let canonical = root.canonicalize().unwrap_or(root);
+        if !roots.contains(&canonical) {
+            roots.push(canonical);
+        }
+    }
+
+    roots
+}
+
+/// Get the `steamapps/common/` style library folder that contains the given game install path.
+/// i.e. `SteamLibrary/steamapps/common/Baldurs Gate 3` -> `SteamLibrary/steamapps`
+pub fn steamapps_folder_of_install(install_path: &Path) -> Option<PathBuf> {
+    let common = install_path.parent()?;
+    if !common
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("common"))
+    {
+        return None;
+    }
+
+    common.parent().map(Path::to_path_buf)
+}
+
+/// Get the path to the game's documents folder within a `steamapps` folder's Proton prefix.
+/// This does not check whether it exists.
+pub fn proton_documents_folder(steamapps: &Path) -> PathBuf {
+    steamapps
+        .join("compatdata")
+        .join(BG3_STEAM_ID.to_string())
+        .join(PROTON_APPDATA_FOLDER)
+        .join(DOCUMENTS_GAME_FOLDER)
+}
+
+/// Get every location the game's documents folder could be at on this platform, in order of
+/// preference. These are not checked for existence.
+/// `install_path` is the root game folder, if known. On Linux the Proton prefix next to it is
+/// preferred since that is the one the game will actually use.
+pub fn larian_documents_folder_candidates(install_path: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            candidates.push(PathBuf::from(local).join(DOCUMENTS_GAME_FOLDER));
+        }
+
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join("AppData/Local").join(DOCUMENTS_GAME_FOLDER));
+        }
+    } else if cfg!(target_os = "macos") {
+        // The native MacOS version keeps it in the user's documents folder.
+        if let Some(documents) = dirs::document_dir() {
+            candidates.push(documents.join(DOCUMENTS_GAME_FOLDER));
+        }
+    } else {
+        candidates = proton_documents_folder_candidates(install_path, &all_library_folders());
+    }
+
+    let mut unique = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if !unique.contains(&candidate) {
+            unique.push(candidate);
+        }
+    }
+
+    unique
+}
+
+/// Get the game's documents folder within the Proton prefix of each library, starting with the
+/// library the game is installed in.
+fn proton_documents_folder_candidates(
+    install_path: Option<&Path>,
+    libraries: &[LibraryFolder],
+) -> Vec<PathBuf> {
+    let install_steamapps = install_path.and_then(steamapps_folder_of_install);
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for steamapps in install_steamapps
+        .into_iter()
+        .chain(libraries.iter().map(LibraryFolder::steamapps))
+    {
+        let candidate = proton_documents_folder(&steamapps);
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+
+    candidates
+}
+
+/// Find the game's documents folder (`Larian Studios/Baldur's Gate 3`).
+/// This prefers folders that exist, but if none of them do then it returns the most likely
+/// location, so that it can be created. Returns `None` if we couldn't even guess.
+pub fn find_larian_documents_folder(install_path: Option<&Path>) -> Option<PathBuf> {
+    let candidates = larian_documents_folder_candidates(install_path);
+    eprintln!("Looking for the Larian documents folder in {candidates:?}");
+    choose_documents_folder(&candidates)
+}
+
+/// The first of the candidates that exists, or else the first of them
+fn choose_documents_folder(candidates: &[PathBuf]) -> Option<PathBuf> {
+    candidates
+        .iter()
+        .find(|candidate| candidate.is_dir())
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::steam_vdf::read_library_folders;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_steam_root_candidates() {
+        let home = Path::new("/home/deck");
+        let candidates = steam_root_candidates_in(home);
+        assert_eq!(candidates[0], home.join(".steam/steam"));
+        assert!(candidates.contains(&home.join(".local/share/Steam")));
+        assert!(
+            candidates.contains(&home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"))
+        );
+    }
+
+    #[test]
+    fn test_documents_folder() {
+        let temp = tempfile::tempdir().unwrap();
+        let steam_root = temp.path().join("Steam");
+        let sd_card = temp.path().join("SDCard");
+        let install = sd_card.join("steamapps/common/Baldurs Gate 3");
+        std::fs::create_dir_all(steam_root.join("steamapps")).unwrap();
+        std::fs::create_dir_all(&install).unwrap();
+        std::fs::write(
+            steam_root.join("steamapps/libraryfolders.vdf"),
+            format!(
+                r#""libraryfolders"
+                {{
+                    "0" {{ "path" "{}" "apps" {{ "228980" "1" }} }}
+                    "1" {{ "path" "{}" "apps" {{ "1086940" "1" }} }}
+                }}"#,
+                steam_root.display(),
+                sd_card.display()
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            steamapps_folder_of_install(&install),
+            Some(sd_card.join("steamapps"))
+        );
+        assert_eq!(steamapps_folder_of_install(&sd_card), None);
+
+        let sd_card_documents = proton_documents_folder(&sd_card.join("steamapps"));
+        assert!(sd_card_documents.ends_with(
+            "compatdata/1086940/pfx/drive_c/users/steamuser/AppData/Local/Larian Studios/Baldur's Gate 3"
+        ));
+        let root_documents = proton_documents_folder(&steam_root.join("steamapps"));
+
+        // The library the game is installed in comes first, and each is only listed once
+        let libraries = read_library_folders(&steam_root);
+        let candidates = proton_documents_folder_candidates(Some(&install), &libraries);
+        assert_eq!(
+            candidates,
+            [sd_card_documents.clone(), root_documents.clone()]
+        );
+        let candidates = proton_documents_folder_candidates(None, &libraries);
+        assert_eq!(
+            candidates,
+            [root_documents.clone(), sd_card_documents.clone()]
+        );
+
+        // The most likely one is used until one of them exists
+        assert_eq!(choose_documents_folder(&[]), None);
+        let candidates = [sd_card_documents.clone(), root_documents.clone()];
+        assert_eq!(
+            choose_documents_folder(&candidates),
+            Some(sd_card_documents)
+        );
+        std::fs::create_dir_all(&root_documents).unwrap();
+        assert_eq!(choose_documents_folder(&candidates), Some(root_documents));
+    }
+}
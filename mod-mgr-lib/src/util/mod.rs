@@ -1 +1,24 @@
+pub mod default_paths;
 pub mod divinity_registry_helper;
+pub mod game_paths;
+pub mod steam_vdf;
+pub mod time;
+
+/// A version 18 pak with no files, for tests
+#[cfg(test)]
+pub(crate) fn empty_pak() -> Vec<u8> {
+    let mut pak = b"LSPK".to_vec();
+    pak.extend(18u32.to_le_bytes());
+    // The file list comes straight after the header
+    pak.extend(40u64.to_le_bytes());
+    pak.extend(9u32.to_le_bytes());
+    // Flags, priority, MD5 and number of parts
+    pak.extend([0, 0]);
+    pak.extend([0; 16]);
+    pak.extend(1u16.to_le_bytes());
+    // No files, in an LZ4 block holding nothing
+    pak.extend(0u32.to_le_bytes());
+    pak.extend(1u32.to_le_bytes());
+    pak.push(0);
+    pak
+}
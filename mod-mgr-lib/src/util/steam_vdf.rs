@@ -0,0 +1,624 @@
+//! Parsing of Steam's text KeyValues (VDF) files, which is what `libraryfolders.vdf` and the
+//! `appmanifest_<app id>.acf` files are written in.
+//! We use this to find the game across every Steam library, as a fallback for when steamlocate
+//! fails (or is given a stale library list, like with a Steam Deck SD card that was remounted
+//! somewhere else).
+
+use std::path::{Path, PathBuf};
+
+use crate::util::game_paths::steam_roots;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfError {
+    UnexpectedEof,
+    /// A `}` with no matching `{`
+    UnexpectedClose {
+        line: usize,
+    },
+    /// A key that was followed by neither a value nor a `{`
+    MissingValue {
+        key: String,
+        line: usize,
+    },
+    UnterminatedString {
+        line: usize,
+    },
+    /// The file parsed, but was missing a field we needed.
+    MissingField(&'static str),
+    InvalidField(&'static str),
+}
+impl std::fmt::Display for VdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VdfError::UnexpectedEof => write!(f, "Unexpected end of file"),
+            VdfError::UnexpectedClose { line } => write!(f, "Unexpected '}}' on line {line}"),
+            VdfError::MissingValue { key, line } => {
+                write!(f, "Key {key:?} on line {line} has no value")
+            }
+            VdfError::UnterminatedString { line } => {
+                write!(f, "Unterminated string starting on line {line}")
+            }
+            VdfError::MissingField(field) => write!(f, "Missing field {field:?}"),
+            VdfError::InvalidField(field) => write!(f, "Invalid value for field {field:?}"),
+        }
+    }
+}
+impl std::error::Error for VdfError {}
+
+/// A value in a VDF file. Either a string or a nested group of key-value pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Vdf {
+    Str(String),
+    /// Entries are kept in file order. Keys may be duplicated.
+    Map(Vec<(String, Vdf)>),
+}
+impl Vdf {
+    /// Get the first value with the given key. Keys are compared case-insensitively, like Steam
+    /// does.
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        self.entries()
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Get the string value with the given key
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Vdf::Str(v) => Some(v),
+            Vdf::Map(_) => None,
+        }
+    }
+
+    /// Get the entries of the map. Empty if this is a string.
+    pub fn entries(&self) -> &[(String, Vdf)] {
+        match self {
+            Vdf::Str(_) => &[],
+            Vdf::Map(entries) => entries,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+struct Tokenizer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            chars: input.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, VdfError> {
+        loop {
+            let Some(&c) = self.chars.peek() else {
+                return Ok(None);
+            };
+
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.chars.next();
+                }
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '/' => {
+                    self.chars.next();
+                    if self.chars.peek() == Some(&'/') {
+                        // Comment until the end of the line
+                        while self.chars.peek().is_some_and(|c| *c != '\n') {
+                            self.chars.next();
+                        }
+                    } else {
+                        // A lone slash is just the start of an unquoted string.
+                        let mut value = String::from('/');
+                        self.unquoted(&mut value);
+                        return Ok(Some(Token::Str(value)));
+                    }
+                }
+                '{' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Open));
+                }
+                '}' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Close));
+                }
+                '"' => {
+                    self.chars.next();
+                    return self.quoted().map(|v| Some(Token::Str(v)));
+                }
+                // Conditionals like `[$WIN32]`, which we don't care about.
+                '[' => while self.chars.next().is_some_and(|c| c != ']') {},
+                _ => {
+                    let mut value = String::new();
+                    self.unquoted(&mut value);
+                    return Ok(Some(Token::Str(value)));
+                }
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, VdfError> {
+        let start_line = self.line;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(VdfError::UnterminatedString { line: start_line }),
+                Some('"') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => return Err(VdfError::UnterminatedString { line: start_line }),
+                },
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn unquoted(&mut self, value: &mut String) {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                break;
+            }
+            value.push(c);
+            self.chars.next();
+        }
+    }
+}
+
+/// Parse a VDF file, returning the root key and its value.
+pub fn parse_vdf(input: &str) -> Result<(String, Vdf), VdfError> {
+    let mut tokenizer = Tokenizer::new(input);
+
+    let key = match tokenizer.next_token()? {
+        Some(Token::Str(key)) => key,
+        Some(Token::Close) => {
+            return Err(VdfError::UnexpectedClose {
+                line: tokenizer.line,
+            })
+        }
+        Some(Token::Open) | None => return Err(VdfError::UnexpectedEof),
+    };
+
+    let value = parse_value(&mut tokenizer, &key)?;
+
+    Ok((key, value))
+}
+
+fn parse_value(tokenizer: &mut Tokenizer, key: &str) -> Result<Vdf, VdfError> {
+    match tokenizer.next_token()? {
+        Some(Token::Str(value)) => Ok(Vdf::Str(value)),
+        Some(Token::Open) => parse_map(tokenizer),
+        Some(Token::Close) | None => Err(VdfError::MissingValue {
+            key: key.to_string(),
+            line: tokenizer.line,
+        }),
+    }
+}
+
+fn parse_map(tokenizer: &mut Tokenizer) -> Result<Vdf, VdfError> {
+    let mut entries = Vec::new();
+    loop {
+        match tokenizer.next_token()? {
+            Some(Token::Str(key)) => {
+                let value = parse_value(tokenizer, &key)?;
+                entries.push((key, value));
+            }
+            Some(Token::Close) => return Ok(Vdf::Map(entries)),
+            Some(Token::Open) => {
+                return Err(VdfError::MissingValue {
+                    key: String::new(),
+                    line: tokenizer.line,
+                })
+            }
+            None => return Err(VdfError::UnexpectedEof),
+        }
+    }
+}
+
+/// A Steam library folder, from `libraryfolders.vdf`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryFolder {
+    /// The root of the library. The games are in `path/steamapps/common`.
+    pub path: PathBuf,
+    /// The app ids that Steam believes are installed in this library.
+    /// This is empty for the old format, which did not list them.
+    pub apps: Vec<u32>,
+}
+impl LibraryFolder {
+    pub fn steamapps(&self) -> PathBuf {
+        self.path.join("steamapps")
+    }
+}
+
+/// Parse `libraryfolders.vdf`.
+/// This supports both the current format, where each library is a group with a `path` and
+/// `apps`, and the old format where each library is just a numbered path.
+pub fn parse_library_folders(input: &str) -> Result<Vec<LibraryFolder>, VdfError> {
+    let (_, root) = parse_vdf(input)?;
+
+    let mut folders = Vec::new();
+    for (key, value) in root.entries() {
+        // Libraries are numbered, other keys like `contentstatsid` are not libraries.
+        if key.parse::<u32>().is_err() {
+            continue;
+        }
+
+        match value {
+            Vdf::Str(path) => folders.push(LibraryFolder {
+                path: PathBuf::from(path),
+                apps: Vec::new(),
+            }),
+            Vdf::Map(_) => {
+                let path = value
+                    .get_str("path")
+                    .ok_or(VdfError::MissingField("path"))?;
+                let apps = value
+                    .get("apps")
+                    .map(|apps| {
+                        apps.entries()
+                            .iter()
+                            .filter_map(|(app_id, _)| app_id.parse().ok())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                folders.push(LibraryFolder {
+                    path: PathBuf::from(path),
+                    apps,
+                });
+            }
+        }
+    }
+
+    Ok(folders)
+}
+
+/// The parts of an `appmanifest_<app id>.acf` that we care about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppManifest {
+    pub app_id: u32,
+    pub name: String,
+    /// Folder name within `steamapps/common`
+    pub install_dir: String,
+    pub state_flags: u32,
+}
+impl AppManifest {
+    /// Whether Steam considers the app to be fully installed.
+    pub fn is_installed(&self) -> bool {
+        const STATE_FULLY_INSTALLED: u32 = 4;
+        self.state_flags & STATE_FULLY_INSTALLED != 0
+    }
+}
+
+pub fn parse_app_manifest(input: &str) -> Result<AppManifest, VdfError> {
+    let (_, root) = parse_vdf(input)?;
+
+    let app_id = root
+        .get_str("appid")
+        .ok_or(VdfError::MissingField("appid"))?
+        .parse()
+        .map_err(|_| VdfError::InvalidField("appid"))?;
+    let install_dir = root
+        .get_str("installdir")
+        .ok_or(VdfError::MissingField("installdir"))?
+        .to_string();
+    let name = root.get_str("name").unwrap_or_default().to_string();
+    let state_flags = root
+        .get_str("StateFlags")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    Ok(AppManifest {
+        app_id,
+        name,
+        install_dir,
+        state_flags,
+    })
+}
+
+/// Read the library folders listed by the Steam install at `steam_root`.
+/// The Steam root itself is always included, since older versions of Steam didn't list it.
+pub fn read_library_folders(steam_root: &Path) -> Vec<LibraryFolder> {
+    let mut folders = vec![LibraryFolder {
+        path: steam_root.to_owned(),
+        apps: Vec::new(),
+    }];
+
+    for vdf_path in [
+        steam_root.join("steamapps/libraryfolders.vdf"),
+        steam_root.join("config/libraryfolders.vdf"),
+    ] {
+        let Ok(content) = std::fs::read_to_string(&vdf_path) else {
+            continue;
+        };
+
+        match parse_library_folders(&content) {
+            Ok(found) => {
+                for folder in found {
+                    add_library_folder(&mut folders, folder);
+                }
+            }
+            Err(err) => eprintln!("Failed to parse {vdf_path:?}: {err}"),
+        }
+    }
+
+    folders
+}
+
+fn add_library_folder(folders: &mut Vec<LibraryFolder>, folder: LibraryFolder) {
+    if let Some(existing) = folders.iter_mut().find(|f| f.path == folder.path) {
+        for app in folder.apps {
+            if !existing.apps.contains(&app) {
+                existing.apps.push(app);
+            }
+        }
+    } else {
+        folders.push(folder);
+    }
+}
+
+/// Get the places that removable drives are typically mounted at, which might hold a Steam
+/// library that `libraryfolders.vdf` has a stale path for.
+/// i.e. the Steam Deck mounts its SD card at `/run/media/mmcblk0p1` or `/run/media/deck/<label>`
+pub fn removable_library_candidates() -> Vec<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+    for mount_root in ["/run/media", "/media"] {
+        let Ok(entries) = std::fs::read_dir(mount_root) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Either the drive itself, or a per-user folder of drives
+            candidates.push(path.clone());
+            if let Ok(user_entries) = std::fs::read_dir(&path) {
+                candidates.extend(user_entries.flatten().map(|e| e.path()));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|path| path.join("steamapps").is_dir())
+        .collect()
+}
+
+/// Get every Steam library we can find, from all of the Steam installs and removable drives.
+pub fn all_library_folders() -> Vec<LibraryFolder> {
+    let mut folders = Vec::new();
+    for root in steam_roots() {
+        for folder in read_library_folders(&root) {
+            add_library_folder(&mut folders, folder);
+        }
+    }
+
+    for path in removable_library_candidates() {
+        add_library_folder(
+            &mut folders,
+            LibraryFolder {
+                path,
+                apps: Vec::new(),
+            },
+        );
+    }
+
+    folders
+}
+
+/// Find where the app is installed within the given library, using its app manifest.
+/// If there is no manifest, this falls back to checking for `steamapps/common/<fallback_dir>`.
+pub fn find_app_in_library(
+    library: &LibraryFolder,
+    app_id: u32,
+    fallback_dir: &str,
+) -> Option<PathBuf> {
+    let steamapps = library.steamapps();
+    let manifest_path = steamapps.join(format!("appmanifest_{app_id}.acf"));
+
+    if let Ok(content) = std::fs::read_to_string(&manifest_path) {
+        match parse_app_manifest(&content) {
+            Ok(manifest) => {
+                let install_path = steamapps.join("common").join(&manifest.install_dir);
+                if install_path.is_dir() {
+                    return Some(install_path);
+                }
+            }
+            Err(err) => eprintln!("Failed to parse {manifest_path:?}: {err}"),
+        }
+    }
+
+    let install_path = steamapps.join("common").join(fallback_dir);
+    install_path.is_dir().then_some(install_path)
+}
+
+/// Find where the app is installed across the given libraries.
+/// Libraries which list the app are checked first, but since that list can be out of date the
+/// others are checked as well.
+pub fn find_app_install(
+    libraries: &[LibraryFolder],
+    app_id: u32,
+    fallback_dir: &str,
+) -> Option<PathBuf> {
+    let (listed, unlisted): (Vec<_>, Vec<_>) = libraries
+        .iter()
+        .partition(|library| library.apps.contains(&app_id));
+
+    listed
+        .into_iter()
+        .chain(unlisted)
+        .find_map(|library| find_app_in_library(library, app_id, fallback_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY_FOLDERS: &str = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/deck/.local/share/Steam"
+		"label"		""
+		"contentid"		"4295829036113640612"
+		"totalsize"		"0"
+		"apps"
+		{
+			"228980"		"207033873"
+			"1493710"		"1258004165"
+		}
+	}
+	"1"
+	{
+		"path"		"/run/media/mmcblk0p1"
+		"label"		""
+		"apps"
+		{
+			"1086940"		"127361280641"
+		}
+	}
+}
+"#;
+
+    const OLD_LIBRARY_FOLDERS: &str = r#"
+"LibraryFolders"
+{
+	"TimeNextStatsReport"		"1694000000"
+	"ContentStatsID"		"-123"
+	"1"		"D:\\SteamLibrary"
+	"2"		"E:\\Games\\Steam Library"
+}
+"#;
+
+    const APP_MANIFEST: &str = r#"
+"AppState"
+{
+	"appid"		"1086940"
+	"Universe"		"1"
+	"name"		"Baldur's Gate 3"
+	"StateFlags"		"4"
+	"installdir"		"Baldurs Gate 3"
+	// Comments are allowed
+	"UserConfig"
+	{
+		"language"		"english"
+	}
+}
+"#;
+
+    #[test]
+    fn test_parse_library_folders() {
+        let folders = parse_library_folders(LIBRARY_FOLDERS).unwrap();
+        assert_eq!(
+            folders,
+            vec![
+                LibraryFolder {
+                    path: PathBuf::from("/home/deck/.local/share/Steam"),
+                    apps: vec![228980, 1493710],
+                },
+                LibraryFolder {
+                    path: PathBuf::from("/run/media/mmcblk0p1"),
+                    apps: vec![1086940],
+                },
+            ]
+        );
+
+        let folders = parse_library_folders(OLD_LIBRARY_FOLDERS).unwrap();
+        assert_eq!(
+            folders,
+            vec![
+                LibraryFolder {
+                    path: PathBuf::from("D:\\SteamLibrary"),
+                    apps: Vec::new(),
+                },
+                LibraryFolder {
+                    path: PathBuf::from("E:\\Games\\Steam Library"),
+                    apps: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_app_manifest() {
+        let manifest = parse_app_manifest(APP_MANIFEST).unwrap();
+        assert_eq!(
+            manifest,
+            AppManifest {
+                app_id: 1086940,
+                name: "Baldur's Gate 3".to_string(),
+                install_dir: "Baldurs Gate 3".to_string(),
+                state_flags: 4,
+            }
+        );
+        assert!(manifest.is_installed());
+
+        assert_eq!(
+            parse_app_manifest("\"AppState\" { \"appid\" \"1\" "),
+            Err(VdfError::UnexpectedEof)
+        );
+        assert_eq!(
+            parse_app_manifest("\"AppState\" { \"appid\" \"1\" }"),
+            Err(VdfError::MissingField("installdir"))
+        );
+    }
+
+    #[test]
+    fn test_find_app_install() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let main_library = root.join("Steam");
+        // A library on an external drive, with the game's folder named differently than usual
+        let sd_library = root.join("sdcard");
+        let install = sd_library.join("steamapps/common/BG3 Custom");
+        std::fs::create_dir_all(main_library.join("steamapps/common")).unwrap();
+        std::fs::create_dir_all(&install).unwrap();
+        std::fs::write(
+            sd_library.join("steamapps/appmanifest_1086940.acf"),
+            APP_MANIFEST.replace("\"Baldurs Gate 3\"", "\"BG3 Custom\""),
+        )
+        .unwrap();
+
+        let libraries = vec![
+            LibraryFolder {
+                path: main_library,
+                apps: Vec::new(),
+            },
+            // Not listed as having the game, it should still be found.
+            LibraryFolder {
+                path: sd_library,
+                apps: Vec::new(),
+            },
+        ];
+
+        let found = find_app_install(&libraries, 1086940, "Baldurs Gate 3");
+
+        assert_eq!(found, Some(install));
+    }
+}
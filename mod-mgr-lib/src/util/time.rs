@@ -0,0 +1,54 @@
+//! Formatting unix timestamps without pulling in a date library.
+
+use std::time::SystemTime;
+
+use crate::meta::system_time_secs;
+
+/// The current time as seconds since the unix epoch, or 0 if the clock is before it.
+pub fn now_secs() -> u64 {
+    system_time_secs(SystemTime::now()).unwrap_or(0)
+}
+
+/// The UTC date and time of the timestamp, as `(year, month, day, hour, minute, second)`.
+pub fn civil_from_secs(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    // Civil from days algorithm, from http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86400) as i64;
+    let time = (secs % 86400) as u32;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    (y, m, d, time / 3600, time / 60 % 60, time % 60)
+}
+
+/// The UTC date of the timestamp, as `YYYY-MM-DD`.
+pub fn date_string(secs: u64) -> String {
+    let (y, m, d, ..) = civil_from_secs(secs);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// The UTC date and time of the timestamp, as `YYYY-MM-DD_HH-MM-SS`, for use in a file name.
+pub fn timestamp_name(secs: u64) -> String {
+    let (y, m, d, hour, minute, second) = civil_from_secs(secs);
+    format!("{y:04}-{m:02}-{d:02}_{hour:02}-{minute:02}-{second:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(timestamp_name(1691065810), "2023-08-03_12-30-10");
+        assert_eq!(date_string(1691065810), "2023-08-03");
+        assert_eq!(timestamp_name(0), "1970-01-01_00-00-00");
+        // Leap day
+        assert_eq!(civil_from_secs(951782400), (2000, 2, 29, 0, 0, 0));
+    }
+}
@@ -0,0 +1,495 @@
+//! Checking a load order for problems that would stop the game from loading it as intended.
+
+use crate::{
+    extender::{DivinityExtenderModStatus, ScriptExtenderConfig},
+    load_order::LoadOrder,
+    md5_cache::{check_pak_change, PakChange},
+    mod_data::{DivinityModDependencyData, ModData},
+};
+
+/// An installed mod, with what we need to know to validate a load order containing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationMod {
+    pub data: ModData,
+    pub dependencies: Vec<DivinityModDependencyData>,
+    pub extender_status: DivinityExtenderModStatus,
+    /// The extender version from the mod's extender config, if it gives one
+    pub required_extender_version: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The mod is in the load order but isn't installed
+    MissingMod { uuid: String, name: String },
+    /// The mod is in the load order more than once
+    DuplicateMod { uuid: String, name: String },
+    /// A dependency of the mod is not in the load order
+    MissingDependency {
+        uuid: String,
+        name: String,
+        dependency: DivinityModDependencyData,
+        /// Whether the dependency is installed, and so only needs to be enabled
+        installed: bool,
+    },
+    /// A dependency of the mod is loaded after it
+    DependencyAfter {
+        uuid: String,
+        name: String,
+        dependency: DivinityModDependencyData,
+    },
+    /// The mod needs the Script Extender, and it isn't usable
+    Extender {
+        uuid: String,
+        name: String,
+        status: DivinityExtenderModStatus,
+        required_version: Option<u32>,
+    },
+    /// The mod's pak has changed since the load order was saved
+    PakChanged { uuid: String, name: String },
+    /// The mod's pak has the header of a pre-release version of the game, which the released game
+    /// can't load
+    ClassicMod { uuid: String, name: String },
+}
+impl ValidationIssue {
+    /// The uuid of the mod that the issue is about
+    pub fn uuid(&self) -> &str {
+        match self {
+            ValidationIssue::MissingMod { uuid, .. }
+            | ValidationIssue::DuplicateMod { uuid, .. }
+            | ValidationIssue::MissingDependency { uuid, .. }
+            | ValidationIssue::DependencyAfter { uuid, .. }
+            | ValidationIssue::Extender { uuid, .. }
+            | ValidationIssue::PakChanged { uuid, .. }
+            | ValidationIssue::ClassicMod { uuid, .. } => uuid,
+        }
+    }
+
+    /// A short name for the kind of issue, used in machine readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ValidationIssue::MissingMod { .. } => "missing_mod",
+            ValidationIssue::DuplicateMod { .. } => "duplicate_mod",
+            ValidationIssue::MissingDependency { .. } => "missing_dependency",
+            ValidationIssue::DependencyAfter { .. } => "dependency_after",
+            ValidationIssue::Extender { .. } => "extender",
+            ValidationIssue::PakChanged { .. } => "pak_changed",
+            ValidationIssue::ClassicMod { .. } => "classic_mod",
+        }
+    }
+
+    /// Whether the issue is caused by a mod that isn't installed, which the user can choose to not
+    /// be warned about with `disable_missing_mod_warnings`.
+    pub fn is_missing_mod(&self) -> bool {
+        matches!(
+            self,
+            ValidationIssue::MissingMod { .. }
+                | ValidationIssue::MissingDependency {
+                    installed: false,
+                    ..
+                }
+        )
+    }
+
+    /// The change to the load order that fixes the issue, if there is one
+    pub fn fix(&self) -> Option<ValidationFix> {
+        match self {
+            ValidationIssue::MissingDependency {
+                uuid,
+                dependency,
+                installed: true,
+                ..
+            } => Some(ValidationFix::EnableBefore {
+                uuid: dependency.uuid.clone(),
+                before: uuid.clone(),
+            }),
+            ValidationIssue::DependencyAfter {
+                uuid, dependency, ..
+            } => Some(ValidationFix::MoveBefore {
+                uuid: dependency.uuid.clone(),
+                before: uuid.clone(),
+            }),
+            ValidationIssue::DuplicateMod { uuid, .. } => {
+                Some(ValidationFix::DisableDuplicates { uuid: uuid.clone() })
+            }
+            ValidationIssue::ClassicMod { uuid, .. } => {
+                Some(ValidationFix::Disable { uuid: uuid.clone() })
+            }
+            _ => None,
+        }
+    }
+}
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::MissingMod { name, .. } => {
+                write!(f, "{name} is in the load order but is not installed")
+            }
+            ValidationIssue::DuplicateMod { name, .. } => {
+                write!(f, "{name} is in the load order more than once")
+            }
+            ValidationIssue::MissingDependency {
+                name,
+                dependency,
+                installed,
+                ..
+            } => {
+                if *installed {
+                    write!(
+                        f,
+                        "{name} depends on {}, which is not enabled",
+                        dependency.name
+                    )
+                } else {
+                    write!(
+                        f,
+                        "{name} depends on {}, which is not installed",
+                        dependency.name
+                    )
+                }
+            }
+            ValidationIssue::DependencyAfter {
+                name, dependency, ..
+            } => write!(
+                f,
+                "{name} depends on {}, which is loaded after it",
+                dependency.name
+            ),
+            ValidationIssue::Extender {
+                name,
+                status,
+                required_version,
+                ..
+            } => {
+                let config = ScriptExtenderConfig {
+                    required_extension_version: *required_version,
+                    ..Default::default()
+                };
+                write!(f, "{name}: {}", status.tooltip(&config).trim_end())
+            }
+            ValidationIssue::PakChanged { name, .. } => {
+                write!(f, "{name} has changed since the load order was saved")
+            }
+            ValidationIssue::ClassicMod { name, .. } => {
+                write!(
+                    f,
+                    "{name} was packed for a pre-release version of the game and can't be loaded"
+                )
+            }
+        }
+    }
+}
+
+/// A change to the load order that fixes a [`ValidationIssue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationFix {
+    /// Enable the mod, loading it just before `before`
+    EnableBefore {
+        uuid: String,
+        before: String,
+    },
+    /// Move the mod to just before `before`
+    MoveBefore {
+        uuid: String,
+        before: String,
+    },
+    /// Remove every copy of the mod in the load order after the first
+    DisableDuplicates {
+        uuid: String,
+    },
+    Disable {
+        uuid: String,
+    },
+}
+impl ValidationFix {
+    /// The text of the button that applies the fix
+    pub fn title(&self) -> &'static str {
+        match self {
+            ValidationFix::EnableBefore { .. } => "Enable",
+            ValidationFix::MoveBefore { .. } => "Move Above",
+            ValidationFix::DisableDuplicates { .. } => "Remove Duplicate",
+            ValidationFix::Disable { .. } => "Disable",
+        }
+    }
+
+    /// Apply the fix to the order. Uuids are matched ignoring case, as they are when validating.
+    pub fn apply(&self, order: &mut LoadOrder) {
+        match self {
+            ValidationFix::EnableBefore { uuid, before }
+            | ValidationFix::MoveBefore { uuid, before } => {
+                let Some(target) = position(&order.active, before) else {
+                    return;
+                };
+                // Only the first copy is moved, any others are left for DisableDuplicates
+                let moving = if let Some(idx) = position(&order.active, uuid) {
+                    if idx < target {
+                        return;
+                    }
+                    order.active.remove(idx)
+                } else if let Some(idx) = position(&order.inactive, uuid) {
+                    order.inactive.remove(idx)
+                } else {
+                    return;
+                };
+                order.active.insert(target, moving);
+            }
+            ValidationFix::DisableDuplicates { uuid } => {
+                // The first copy stays enabled, so the mod doesn't also go in the inactive list
+                let mut seen = false;
+                order.active.retain(|u| {
+                    let is_copy = u.eq_ignore_ascii_case(uuid);
+                    let keep = !(is_copy && seen);
+                    seen |= is_copy;
+                    keep
+                });
+            }
+            ValidationFix::Disable { uuid } => {
+                let Some(idx) = position(&order.active, uuid) else {
+                    return;
+                };
+                let moving = order.active[idx].clone();
+                order.active.retain(|u| !u.eq_ignore_ascii_case(uuid));
+                order.inactive.push(moving);
+            }
+        }
+    }
+}
+
+fn contains(list: &[String], uuid: &str) -> bool {
+    position(list, uuid).is_some()
+}
+
+fn position(list: &[String], uuid: &str) -> Option<usize> {
+    list.iter().position(|u| u.eq_ignore_ascii_case(uuid))
+}
+
+/// Check the load order.
+/// `installed` are the mods in the mods folder, which should have their `md5` filled in.
+/// `ignored` are the uuids of the game's own modules, which are never installed in the mods folder
+/// and are always loaded.
+pub fn validate_load_order(
+    order: &[DivinityModDependencyData],
+    installed: &[ValidationMod],
+    ignored: &[String],
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let find = |uuid: &str| {
+        installed
+            .iter()
+            .find(|m| m.data.uuid.eq_ignore_ascii_case(uuid))
+    };
+    let position = |uuid: &str| {
+        order
+            .iter()
+            .position(|entry| entry.uuid.eq_ignore_ascii_case(uuid))
+    };
+
+    for (idx, entry) in order.iter().enumerate() {
+        if contains(ignored, &entry.uuid) {
+            continue;
+        }
+
+        if position(&entry.uuid) != Some(idx) {
+            issues.push(ValidationIssue::DuplicateMod {
+                uuid: entry.uuid.clone(),
+                name: entry.name.clone(),
+            });
+            continue;
+        }
+
+        let Some(installed_mod) = find(&entry.uuid) else {
+            issues.push(ValidationIssue::MissingMod {
+                uuid: entry.uuid.clone(),
+                name: entry.name.clone(),
+            });
+            continue;
+        };
+        let name = installed_mod.data.display_name();
+
+        if installed_mod.data.is_classic_mod {
+            issues.push(ValidationIssue::ClassicMod {
+                uuid: entry.uuid.clone(),
+                name: name.clone(),
+            });
+        }
+
+        for dependency in &installed_mod.dependencies {
+            if contains(ignored, &dependency.uuid) {
+                continue;
+            }
+
+            match position(&dependency.uuid) {
+                None => issues.push(ValidationIssue::MissingDependency {
+                    uuid: entry.uuid.clone(),
+                    name: name.clone(),
+                    dependency: dependency.clone(),
+                    installed: find(&dependency.uuid).is_some(),
+                }),
+                Some(dep_idx) if dep_idx > idx => issues.push(ValidationIssue::DependencyAfter {
+                    uuid: entry.uuid.clone(),
+                    name: name.clone(),
+                    dependency: dependency.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        if installed_mod.extender_status.is_problem() {
+            issues.push(ValidationIssue::Extender {
+                uuid: entry.uuid.clone(),
+                name: name.clone(),
+                status: installed_mod.extender_status,
+                required_version: installed_mod.required_extender_version,
+            });
+        }
+
+        if matches!(
+            check_pak_change(&entry.md5, &installed_mod.data.md5),
+            PakChange::Changed { .. }
+        ) {
+            issues.push(ValidationIssue::PakChanged {
+                uuid: entry.uuid.clone(),
+                name,
+            });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(uuid: &str, dependencies: &[&str]) -> ValidationMod {
+        ValidationMod {
+            data: ModData::new(uuid, uuid, uuid, 1, "Add-on", "", "", "", ""),
+            dependencies: dependencies
+                .iter()
+                .map(|dep| DivinityModDependencyData {
+                    uuid: dep.to_string(),
+                    name: dep.to_string(),
+                    folder: dep.to_string(),
+                    md5: String::new(),
+                    version: Default::default(),
+                })
+                .collect(),
+            extender_status: DivinityExtenderModStatus::None,
+            required_extender_version: None,
+        }
+    }
+
+    fn order(uuids: &[&str]) -> Vec<DivinityModDependencyData> {
+        uuids
+            .iter()
+            .map(|uuid| DivinityModDependencyData::from_mod_data(&installed(uuid, &[]).data))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_load_order() {
+        let mut mods = vec![
+            installed("a", &["base"]),
+            installed("b", &["a", "c"]),
+            installed("c", &[]),
+            installed("d", &["c"]),
+        ];
+        mods[3].extender_status = DivinityExtenderModStatus::RequiredMissing;
+        mods[3].required_extender_version = Some(12);
+        let ignored = vec!["base".to_string()];
+
+        assert!(validate_load_order(&order(&["base", "a", "c", "b"]), &mods, &ignored).is_empty());
+
+        let issues = validate_load_order(&order(&["b", "a", "a", "x", "d"]), &mods, &ignored);
+        let kinds: Vec<&str> = issues.iter().map(ValidationIssue::kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "dependency_after",
+                "missing_dependency",
+                "duplicate_mod",
+                "missing_mod",
+                "missing_dependency",
+                "extender"
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "b depends on c, which is not enabled"
+        );
+        assert_eq!(
+            issues[5].to_string(),
+            "d: [MISSING] Requires Script Extender v12 or higher"
+        );
+    }
+
+    #[test]
+    fn test_validation_fixes() {
+        let ids =
+            |ids: &str| -> Vec<String> { ids.split_whitespace().map(str::to_string).collect() };
+        let mut mods = vec![
+            installed("a", &[]),
+            installed("b", &["a", "c"]),
+            installed("c", &[]),
+            installed("d", &[]),
+        ];
+        mods[3].data.is_classic_mod = true;
+        let mut load_order = LoadOrder {
+            active: ids("b a d B"),
+            inactive: ids("c"),
+        };
+
+        // Fix the first issue until there are none left
+        for _ in 0..5 {
+            let active: Vec<&str> = load_order.active.iter().map(String::as_str).collect();
+            let issues = validate_load_order(&order(&active), &mods, &[]);
+            let Some(fix) = issues.iter().find_map(ValidationIssue::fix) else {
+                break;
+            };
+            fix.apply(&mut load_order);
+        }
+
+        assert_eq!(load_order.active, ids("a c b"));
+        assert_eq!(load_order.inactive, ids("d"));
+
+        // Dependencies written in a different case are still fixed
+        let mods = vec![
+            installed("a", &[]),
+            installed("b", &["A", "C"]),
+            installed("c", &[]),
+            installed("d", &["B"]),
+        ];
+        let mut load_order = LoadOrder {
+            active: ids("b a d"),
+            inactive: ids("c"),
+        };
+        for _ in 0..5 {
+            let active: Vec<&str> = load_order.active.iter().map(String::as_str).collect();
+            let issues = validate_load_order(&order(&active), &mods, &[]);
+            let Some(fix) = issues.iter().find_map(ValidationIssue::fix) else {
+                break;
+            };
+            fix.apply(&mut load_order);
+        }
+        assert_eq!(load_order.active, ids("a c b d"));
+        assert!(load_order.inactive.is_empty());
+
+        // Only the first copy of a duplicated mod is moved
+        let mut load_order = LoadOrder {
+            active: ids("b a A"),
+            inactive: Vec::new(),
+        };
+        ValidationFix::MoveBefore {
+            uuid: "A".to_string(),
+            before: "B".to_string(),
+        }
+        .apply(&mut load_order);
+        assert_eq!(load_order.active, ids("a b A"));
+
+        ValidationFix::Disable {
+            uuid: "a".to_string(),
+        }
+        .apply(&mut load_order);
+        assert_eq!(load_order.active, ids("b"));
+        assert_eq!(load_order.inactive, ids("a"));
+    }
+}
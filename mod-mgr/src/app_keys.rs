@@ -1,3 +1,97 @@
 //! https://github.com/LaughingLeader/BG3ModManager/blob/master/GUI/ViewModels/AppKeys.cs
+//! Running the actions that keys are bound to. The bindings themselves are in
+//! [`mod_mgr_lib::keybindings`].
 
-// TODO
+use floem::{
+    event::Event,
+    glazier::{KbKey, KeyEvent},
+};
+use mod_mgr_lib::keybindings::{AppAction, KeyCombo};
+
+use crate::{settings_view::open_settings_window, view_util::is_text_input_focused, MainData};
+
+/// The keys that were pressed, or `None` if it is a key that can't be bound, such as a modifier on
+/// its own.
+pub fn key_combo(event: &KeyEvent) -> Option<KeyCombo> {
+    let name = match &event.key {
+        KbKey::Character(c) => c.as_str(),
+        KbKey::Enter => "Enter",
+        KbKey::Escape => "Escape",
+        KbKey::Backspace => "Backspace",
+        KbKey::Delete => "Delete",
+        KbKey::Insert => "Insert",
+        KbKey::Tab => "Tab",
+        KbKey::ArrowUp => "Up",
+        KbKey::ArrowDown => "Down",
+        KbKey::ArrowLeft => "Left",
+        KbKey::ArrowRight => "Right",
+        KbKey::PageUp => "PageUp",
+        KbKey::PageDown => "PageDown",
+        KbKey::Home => "Home",
+        KbKey::End => "End",
+        KbKey::F1 => "F1",
+        KbKey::F2 => "F2",
+        KbKey::F3 => "F3",
+        KbKey::F4 => "F4",
+        KbKey::F5 => "F5",
+        KbKey::F6 => "F6",
+        KbKey::F7 => "F7",
+        KbKey::F8 => "F8",
+        KbKey::F9 => "F9",
+        KbKey::F10 => "F10",
+        KbKey::F11 => "F11",
+        KbKey::F12 => "F12",
+        _ => return None,
+    };
+
+    // Cmd on MacOS is treated as Ctrl, so that the same bindings work everywhere
+    let ctrl = event.mods.ctrl() || event.mods.meta();
+    KeyCombo::new(name, ctrl, event.mods.shift(), event.mods.alt())
+}
+
+/// Run the action bound to the key that was pressed, if there is one.
+/// Keys typed into a text input, like the search boxes, are left to it.
+pub fn handle_key(main_data: &MainData, event: &Event) -> bool {
+    let Event::KeyDown(key) = event else {
+        return false;
+    };
+    if is_text_input_focused() {
+        return false;
+    }
+    let Some(combo) = key_combo(key) else {
+        return false;
+    };
+    let Some(action) = main_data
+        .keybindings
+        .with_untracked(|bindings| bindings.action_for(&combo))
+    else {
+        return false;
+    };
+
+    run_action(main_data, action);
+    true
+}
+
+pub fn run_action(main_data: &MainData, action: AppAction) {
+    match action {
+        AppAction::SaveOrder => main_data.save_load_order(),
+        AppAction::ExportOrder => main_data.export_load_order(),
+        AppAction::InstallMod => main_data.choose_mod_to_install(),
+        AppAction::LaunchGame => main_data.launch_game(),
+        AppAction::SwapLists => main_data.swap_selected(),
+        AppAction::MoveUp => main_data.shift_selected(true),
+        AppAction::MoveDown => main_data.shift_selected(false),
+        AppAction::Undo => main_data.undo_order(),
+        AppAction::Redo => main_data.redo_order(),
+        AppAction::FocusSearch => {
+            let list = main_data
+                .selection
+                .with_untracked(|selection| selection.list);
+            main_data.focus_search.set(Some(list));
+        }
+        AppAction::OpenSettings => open_settings_window(main_data.clone()),
+        AppAction::OpenModsFolder => main_data.open_mods_folder(),
+        AppAction::CheckForUpdates => main_data.check_for_mod_updates(),
+        AppAction::ExportDiagnostics => main_data.export_diagnostics(),
+    }
+}
@@ -0,0 +1,714 @@
+//! The command line interface, for managing mods without the GUI, such as over SSH.
+//! It uses the same settings file as the GUI. Every command prints its result as json to stdout,
+//! while messages about what it is doing are printed to stderr.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use clap::Subcommand;
+use mod_mgr_lib::{
+    backups::{self, Backups, PakHash, Snapshot},
+    diagnostics::collect_diagnostics,
+    extender::{DivinityExtenderModStatus, ExtenderInstall},
+    install::{self, InstallError, InstallOutcome, InstalledMod},
+    launch::{self, LaunchContext},
+    md5_cache::Md5Cache,
+    mod_data::{DivinityModDependencyData, ModData},
+    mod_updates::{self, ModUpdate, UpdateKind},
+    mods_folder::{read_mods_folder, ModsFolder},
+    order_edit,
+    order_file::{read_order_file, OrderFile},
+    pathway::PathwayData,
+    profiles::{self, Profile},
+    settings::{ScriptExtenderSettings, Settings},
+    validate::{validate_load_order, ValidationMod},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    resources::ignored_mods::{ignored_mods_iter, IGNORE_DEPENDENCIES},
+    Args,
+};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// List the installed mods, and where they are in the load order
+    List,
+    /// Enable mods by adding them to the end of the load order
+    Enable {
+        /// The uuid, folder or name of each mod
+        #[arg(required = true)]
+        mods: Vec<String>,
+    },
+    /// Disable mods by removing them from the load order
+    Disable {
+        /// The uuid, folder or name of each mod
+        #[arg(required = true)]
+        mods: Vec<String>,
+    },
+    /// Move an enabled mod to a position in the load order, as shown by `list`.
+    /// Position 0 is the main campaign, which is always first, so the first mod is at 1.
+    Move {
+        /// The uuid, folder or name of the mod
+        #[arg(value_name = "MOD")]
+        mod_name: String,
+        position: usize,
+    },
+    /// Import or export the load order
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Check the load order for missing mods, missing dependencies and other problems.
+    /// Exits with code 2 if there are any.
+    Validate,
+    /// Install a `.pak` or a `.zip` of paks into the mods folder
+    Install { archive: PathBuf },
+    /// List or restore backups of the load order, which are taken whenever it is changed
+    #[command(subcommand)]
+    Backup(BackupCommand),
+    /// Collect a report of the setup for troubleshooting, printing it if no path is given
+    Diagnostics {
+        /// Where to write the report. It is written as a zip if the path ends with `.zip`, and as
+        /// json otherwise.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Check the installed mods for updates
+    Updates {
+        /// Url or path of the update manifest, instead of the one in the settings
+        #[arg(long)]
+        manifest: Option<String>,
+    },
+    /// Launch the game
+    Launch,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum OrderCommand {
+    /// Replace the load order with one from an order json or a `modsettings.lsx`
+    Import { path: PathBuf },
+    /// Write the load order as an order json, printing it if no path is given
+    Export { path: Option<PathBuf> },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum BackupCommand {
+    /// List the backups, newest first
+    List,
+    /// Restore a backup to the profile it was taken of
+    Restore {
+        /// The id of the backup, from `backup list`
+        id: String,
+    },
+}
+
+/// Run the command, printing the result. Returns the exit code.
+pub fn run(args: &Args, command: &Command, settings: Settings) -> i32 {
+    let cli = Cli::new(args, settings);
+    match cli.run(command) {
+        Ok(value) => {
+            println!("{}", serde_json::to_string_pretty(&value).unwrap());
+            exit_code(&value)
+        }
+        Err(err) => {
+            println!("{}", json!({ "error": format!("{err:#}") }));
+            1
+        }
+    }
+}
+
+/// The exit code for a command's result. Results with `"valid": false` exit with 2, so that scripts
+/// can check the load order without reading the output.
+fn exit_code(value: &Value) -> i32 {
+    if value.get("valid") == Some(&Value::Bool(false)) {
+        2
+    } else {
+        0
+    }
+}
+
+/// The uuids of the game's own modules, which are never in the mods folder
+fn ignored_uuids() -> Vec<String> {
+    IGNORE_DEPENDENCIES
+        .iter()
+        .map(|uuid| uuid.to_string())
+        .chain(ignored_mods_iter().map(|data| data.uuid))
+        .collect()
+}
+
+fn entry_json(entry: &DivinityModDependencyData) -> Value {
+    json!({
+        "uuid": entry.uuid,
+        "name": entry.name,
+        "folder": entry.folder,
+        "version": entry.version.to_string(),
+    })
+}
+
+fn order_json(order: &[DivinityModDependencyData]) -> Value {
+    Value::Array(order.iter().map(entry_json).collect())
+}
+
+fn installed_json(installed: &InstalledMod) -> Value {
+    let data = &installed.meta.data;
+    let (outcome, detail) = match &installed.outcome {
+        InstallOutcome::Installed => ("installed", Value::Null),
+        InstallOutcome::Updated { previous } => ("updated", json!(previous.to_string())),
+        InstallOutcome::AlreadyInstalled => ("already_installed", Value::Null),
+        InstallOutcome::NewerInstalled { installed } => {
+            ("newer_installed", json!(installed.to_string()))
+        }
+        InstallOutcome::FileConflict { existing } => ("file_conflict", json!(existing)),
+    };
+
+    json!({
+        "uuid": data.uuid,
+        "name": data.display_name(),
+        "version": data.version.to_string(),
+        "file": data.file_path,
+        "outcome": outcome,
+        "detail": detail,
+    })
+}
+
+fn update_json(update: &ModUpdate) -> Value {
+    json!({
+        "uuid": update.uuid,
+        "name": update.name,
+        "kind": match update.kind {
+            UpdateKind::NewerVersion => "newer_version",
+            UpdateKind::PublishVersionChanged => "publish_version_changed",
+        },
+        "installed": update.installed.to_string(),
+        "latest": update.latest.to_string(),
+        "installed_publish_version": update.installed_publish_version.to_string(),
+        "latest_publish_version": update.latest_publish_version.map(|v| v.to_string()),
+        "download_url": update.download_url,
+    })
+}
+
+fn snapshot_json(snapshot: &Snapshot) -> Value {
+    json!({
+        "id": snapshot.id,
+        "created": snapshot.info.created,
+        "profile": snapshot.info.profile_name,
+        "active_profile": snapshot.info.active_profile_uuid,
+        "paks": snapshot.info.paks.len(),
+    })
+}
+
+struct Cli {
+    dry: bool,
+    settings: Settings,
+    pathway: PathwayData,
+    /// The profile to use instead of the one the game last used
+    profile_name: Option<String>,
+}
+impl Cli {
+    fn new(args: &Args, mut settings: Settings) -> Cli {
+        let doc_path_override = Some(settings.documents_folder_path_override.clone())
+            .filter(|path| !path.as_os_str().is_empty());
+        let game_data_path = settings.game_data_path.clone();
+
+        // Finding the paths can fill in the game executable path, like it does for the GUI
+        let pathway = PathwayData::new(&mut settings, &game_data_path, doc_path_override);
+
+        Cli {
+            dry: args.dry,
+            settings,
+            pathway,
+            profile_name: args.profile.clone(),
+        }
+    }
+
+    fn run(&self, command: &Command) -> anyhow::Result<Value> {
+        match command {
+            Command::List => self.list(),
+            Command::Enable { mods } => self.enable(mods),
+            Command::Disable { mods } => self.disable(mods),
+            Command::Move { mod_name, position } => self.move_mod(mod_name, *position),
+            Command::Order(OrderCommand::Import { path }) => self.import_order(path),
+            Command::Order(OrderCommand::Export { path }) => self.export_order(path.as_deref()),
+            Command::Validate => self.validate(),
+            Command::Install { archive } => self.install(archive),
+            Command::Backup(BackupCommand::List) => self.list_backups(),
+            Command::Backup(BackupCommand::Restore { id }) => self.restore_backup(id),
+            Command::Diagnostics { output } => self.diagnostics(output.as_deref()),
+            Command::Updates { manifest } => self.updates(manifest.as_deref()),
+            Command::Launch => self.launch(),
+        }
+    }
+
+    fn game_bin_dir(&self) -> PathBuf {
+        self.settings
+            .game_executable_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    fn mods_folder(&self) -> anyhow::Result<ModsFolder> {
+        let mods_dir = &self.pathway.documents_mods_path;
+        let folder = read_mods_folder(mods_dir)
+            .with_context(|| format!("Failed to read the mods folder at {mods_dir:?}"))?;
+        for (path, err) in &folder.errors {
+            eprintln!("Failed to read mod at {path:?}: {err}");
+        }
+
+        Ok(folder)
+    }
+
+    /// The profile chosen with `--profile`, or the one the game last used.
+    fn profile(&self) -> anyhow::Result<Profile> {
+        let profiles_dir = &self.pathway.documents_profiles_path;
+        let (profiles, active) = profiles::load_profiles(profiles_dir)
+            .with_context(|| format!("Failed to read player profiles at {profiles_dir:?}"))?;
+
+        match &self.profile_name {
+            Some(name) => profiles
+                .into_iter()
+                .find(|profile| {
+                    profile.name.eq_ignore_ascii_case(name)
+                        || profile.display_name.eq_ignore_ascii_case(name)
+                })
+                .ok_or_else(|| anyhow!("There is no profile named {name:?}")),
+            None => active
+                .map(|idx| profiles[idx].clone())
+                .ok_or_else(|| anyhow!("There is no active profile, choose one with --profile")),
+        }
+    }
+
+    fn read_order(&self, profile: &Profile) -> anyhow::Result<Vec<DivinityModDependencyData>> {
+        profile
+            .read_load_order()
+            .with_context(|| format!("Failed to read the load order of {}", profile.title()))
+    }
+
+    fn write_order(
+        &self,
+        profile: &Profile,
+        order: &[DivinityModDependencyData],
+    ) -> anyhow::Result<()> {
+        let path = profile.mod_settings_path();
+        if self.dry {
+            eprintln!("Dry run, not saving load order to {path:?}");
+            return Ok(());
+        }
+
+        let paks = self.hash_paks()?;
+        Backups::new()
+            .snapshot(profile, paks)
+            .context("Failed to back up the current load order")?;
+        profile
+            .write_order(order)
+            .with_context(|| format!("Failed to save the load order to {path:?}"))?;
+        eprintln!("Saved load order to {path:?}");
+
+        Ok(())
+    }
+
+    /// The names and MD5s of the paks in the mods folder, for backups
+    fn hash_paks(&self) -> anyhow::Result<Vec<PakHash>> {
+        let mut md5_cache = Md5Cache::load();
+        let paks = backups::hash_paks(&self.pathway.documents_mods_path, &mut md5_cache)
+            .context("Failed to hash the paks in the mods folder")?;
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
+
+        Ok(paks)
+    }
+
+    /// Fill in the `md5` of the mods, which the game uses to tell if a pak has changed.
+    fn fill_md5s<'a>(&self, mods: impl IntoIterator<Item = &'a mut ModData>) {
+        let mut md5_cache = Md5Cache::load();
+        for data in mods {
+            if let Err(err) = md5_cache.fill_mod_data(data) {
+                eprintln!("Failed to hash {}: {err}", data.display_name());
+            }
+        }
+
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
+    }
+
+    /// The data of the mods in the mods folder
+    fn installed_mods(&self) -> anyhow::Result<Vec<ModData>> {
+        Ok(self
+            .mods_folder()?
+            .mods
+            .into_iter()
+            .map(|pak_mod| pak_mod.meta.data)
+            .collect())
+    }
+
+    /// The load order entries for the mods, with their `md5` filled in.
+    fn order_entries(&self, mut mods: Vec<ModData>) -> Vec<DivinityModDependencyData> {
+        self.fill_md5s(&mut mods);
+        mods.iter()
+            .map(DivinityModDependencyData::from_mod_data)
+            .collect()
+    }
+
+    fn list(&self) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let order = self.read_order(&profile)?;
+        let folder = self.mods_folder()?;
+
+        let mods: Vec<Value> = folder
+            .mods
+            .iter()
+            .map(|pak_mod| {
+                let data = &pak_mod.meta.data;
+                let position = order
+                    .iter()
+                    .position(|entry| entry.uuid.eq_ignore_ascii_case(&data.uuid));
+                json!({
+                    "uuid": data.uuid,
+                    "name": data.display_name(),
+                    "folder": data.folder,
+                    "author": data.author,
+                    "version": data.version.to_string(),
+                    "type": data.mod_type,
+                    "file": data.file_path,
+                    "enabled": position.is_some(),
+                    "position": position,
+                    "requires_extender": pak_mod
+                        .extender
+                        .as_ref()
+                        .is_some_and(|config| config.requires_extender()),
+                })
+            })
+            .collect();
+        let errors: Vec<Value> = folder
+            .errors
+            .iter()
+            .map(|(path, err)| json!({ "file": path, "error": err.to_string() }))
+            .collect();
+
+        Ok(json!({
+            "profile": profile.name,
+            "mods": mods,
+            "errors": errors,
+        }))
+    }
+
+    fn enable(&self, names: &[String]) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let mut order = self.read_order(&profile)?;
+        let to_enable = order_edit::mods_to_enable(&order, &self.installed_mods()?, names)?;
+
+        // Don't back up and rewrite the load order when nothing changes
+        if to_enable.is_empty() {
+            return Ok(json!({
+                "profile": profile.name,
+                "enabled": [],
+                "order": order_json(&order),
+            }));
+        }
+
+        let to_enable = self.order_entries(to_enable);
+        let enabled = order_json(&to_enable);
+        order.extend(to_enable);
+        self.write_order(&profile, &order)?;
+
+        Ok(json!({
+            "profile": profile.name,
+            "enabled": enabled,
+            "order": order_json(&order),
+        }))
+    }
+
+    fn disable(&self, names: &[String]) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let mut order = self.read_order(&profile)?;
+
+        let disabled = order_edit::disable_mods(&mut order, names)?;
+
+        self.write_order(&profile, &order)?;
+
+        Ok(json!({
+            "profile": profile.name,
+            "disabled": order_json(&disabled),
+            "order": order_json(&order),
+        }))
+    }
+
+    fn move_mod(&self, name: &str, position: usize) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let order = order_edit::move_mod(&self.read_order(&profile)?, name, position)?;
+        self.write_order(&profile, &order)?;
+
+        Ok(json!({
+            "profile": profile.name,
+            "order": order_json(&order),
+        }))
+    }
+
+    fn import_order(&self, path: &Path) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let imported = read_order_file(path)
+            .with_context(|| format!("Failed to read load order from {path:?}"))?;
+        let builtin: Vec<String> = ignored_mods_iter().map(|data| data.uuid).collect();
+
+        // Order jsons only have the uuid and name, so we fill the rest in from the mods
+        let (mods, missing) = order_edit::import_order(imported, &self.installed_mods()?, &builtin);
+        let order = self.order_entries(mods);
+
+        self.write_order(&profile, &order)?;
+
+        Ok(json!({
+            "profile": profile.name,
+            "order": order_json(&order),
+            "missing": order_json(&missing),
+        }))
+    }
+
+    fn export_order(&self, path: Option<&Path>) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let order = self.read_order(&profile)?;
+        let file = OrderFile::from_order(profile.title(), &order);
+
+        let Some(path) = path else {
+            return Ok(serde_json::to_value(&file)?);
+        };
+
+        if self.dry {
+            eprintln!("Dry run, not saving load order to {path:?}");
+        } else {
+            std::fs::write(path, file.to_json()?)
+                .with_context(|| format!("Failed to write load order to {path:?}"))?;
+            eprintln!("Saved load order to {path:?}");
+        }
+
+        Ok(json!({
+            "profile": profile.name,
+            "path": path,
+            "order": order_json(&order),
+        }))
+    }
+
+    fn validate(&self) -> anyhow::Result<Value> {
+        let profile = self.profile()?;
+        let order = self.read_order(&profile)?;
+        let folder = self.mods_folder()?;
+
+        let bin_dir = self.game_bin_dir();
+        let extender_install = ExtenderInstall::detect(&bin_dir);
+        let extensions_enabled =
+            match ScriptExtenderSettings::load_from(&ScriptExtenderSettings::path(&bin_dir)) {
+                Ok(extender_settings) => extender_settings
+                    .map(|extender_settings| extender_settings.enable_extensions)
+                    .unwrap_or(true),
+                Err(err) => {
+                    eprintln!("Failed to read Script Extender settings: {err}");
+                    true
+                }
+            };
+
+        let mut installed: Vec<ValidationMod> = folder
+            .mods
+            .into_iter()
+            .map(|pak_mod| ValidationMod {
+                extender_status: pak_mod
+                    .extender
+                    .as_ref()
+                    .map(|config| {
+                        DivinityExtenderModStatus::compute(
+                            config,
+                            &extender_install,
+                            extensions_enabled,
+                        )
+                    })
+                    .unwrap_or_default(),
+                required_extender_version: pak_mod
+                    .extender
+                    .as_ref()
+                    .and_then(|config| config.required_extension_version),
+                data: pak_mod.meta.data,
+                dependencies: pak_mod.meta.dependencies,
+            })
+            .collect();
+        self.fill_md5s(installed.iter_mut().map(|m| &mut m.data));
+
+        let issues: Vec<Value> = validate_load_order(&order, &installed, &ignored_uuids())
+            .iter()
+            .map(|issue| {
+                json!({
+                    "kind": issue.kind(),
+                    "uuid": issue.uuid(),
+                    "message": issue.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "profile": profile.name,
+            "valid": issues.is_empty(),
+            "issues": issues,
+        }))
+    }
+
+    fn install(&self, archive: &Path) -> anyhow::Result<Value> {
+        if self.dry {
+            eprintln!("Dry run, not installing {archive:?}");
+            return Ok(json!({ "archive": archive, "mods": [] }));
+        }
+
+        let installed = self.installed_mods()?;
+        let (results, error) =
+            match install::install_mod(archive, &self.pathway.documents_mods_path, &installed) {
+                Ok(results) => (results, None),
+                // Report the mods that were installed before it failed along with the error
+                Err(InstallError::Partial { installed, error }) => {
+                    (installed, Some(error.to_string()))
+                }
+                Err(err) => {
+                    return Err(anyhow::Error::from(err))
+                        .with_context(|| format!("Failed to install {archive:?}"))
+                }
+            };
+
+        Ok(json!({
+            "archive": archive,
+            "mods": results.iter().map(installed_json).collect::<Vec<_>>(),
+            "error": error,
+        }))
+    }
+
+    fn list_backups(&self) -> anyhow::Result<Value> {
+        let snapshots = Backups::new().list()?;
+
+        Ok(json!({
+            "backups": snapshots.iter().map(snapshot_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn restore_backup(&self, id: &str) -> anyhow::Result<Value> {
+        let backups = Backups::new();
+        let snapshot = backups.find(id)?;
+
+        let profiles_dir = &self.pathway.documents_profiles_path;
+        let (profiles, _) = profiles::load_profiles(profiles_dir)
+            .with_context(|| format!("Failed to read player profiles at {profiles_dir:?}"))?;
+        let profile = profiles
+            .into_iter()
+            .find(|profile| snapshot.is_for(profile))
+            .ok_or_else(|| {
+                anyhow!(
+                    "The profile {} that was backed up no longer exists",
+                    snapshot.info.profile_name
+                )
+            })?;
+
+        if self.dry {
+            eprintln!("Dry run, not restoring backup {id} to {}", profile.title());
+            return Ok(json!({
+                "restored": snapshot_json(&snapshot),
+                "order": order_json(&snapshot.read_load_order()?),
+            }));
+        }
+
+        let report = backups.restore(&snapshot, &profile, self.hash_paks()?)?;
+
+        Ok(json!({
+            "restored": snapshot_json(&snapshot),
+            "previous": snapshot_json(&report.previous),
+            "order": order_json(&snapshot.read_load_order()?),
+            "missing_paks": report.paks.missing,
+            "changed_paks": report.paks.changed,
+        }))
+    }
+
+    fn diagnostics(&self, output: Option<&Path>) -> anyhow::Result<Value> {
+        // The report is still useful without a profile
+        let profile = self
+            .profile()
+            .map_err(|err| eprintln!("Not including a load order: {err:#}"))
+            .ok();
+        let mut md5_cache = Md5Cache::load();
+        let report = collect_diagnostics(
+            &self.settings,
+            self.pathway.diagnostic_paths(),
+            profile.as_ref(),
+            &ignored_uuids(),
+            &mut md5_cache,
+        );
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
+
+        let Some(output) = output else {
+            return Ok(report.to_value()?);
+        };
+
+        if self.dry {
+            eprintln!("Dry run, not writing diagnostics to {output:?}");
+        } else {
+            report
+                .write_to(output)
+                .with_context(|| format!("Failed to write diagnostics to {output:?}"))?;
+            eprintln!("Wrote diagnostics to {output:?}");
+        }
+
+        Ok(json!({
+            "path": output,
+            "errors": report.errors,
+        }))
+    }
+
+    fn updates(&self, manifest: Option<&str>) -> anyhow::Result<Value> {
+        let location = manifest.unwrap_or(&self.settings.mod_update_manifest);
+        if location.trim().is_empty() {
+            return Err(anyhow!(
+                "No update manifest is set, pass one with --manifest or set ModUpdateManifest in the settings"
+            ));
+        }
+
+        let installed = self.installed_mods()?;
+        let source = mod_updates::manifest_source(location);
+        let updates = mod_updates::check_for_mod_updates(source.as_ref(), &installed)?;
+
+        Ok(json!({
+            "manifest": location,
+            "updates": updates.iter().map(update_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn launch(&self) -> anyhow::Result<Value> {
+        let command = launch::build_launch_command(&self.settings, &LaunchContext::detect())?;
+        for warning in &command.warnings {
+            eprintln!("Warning: {warning}");
+        }
+
+        let warnings: Vec<String> = command.warnings.iter().map(ToString::to_string).collect();
+        let pid = if self.dry {
+            eprintln!("Dry run, not launching game with {command}");
+            None
+        } else {
+            eprintln!(
+                "Launching game with {}: {command}",
+                self.settings.launch_method.title()
+            );
+            Some(command.spawn()?.id())
+        };
+
+        Ok(json!({
+            "command": command.to_string(),
+            "warnings": warnings,
+            "pid": pid,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&json!({ "valid": false, "issues": [] })), 2);
+        assert_eq!(exit_code(&json!({ "valid": true, "issues": [] })), 0);
+        assert_eq!(exit_code(&json!({ "order": [] })), 0);
+    }
+}
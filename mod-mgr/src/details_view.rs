@@ -0,0 +1,304 @@
+//! The panel beside the mod lists, showing the details of the last selected mod.
+
+use floem::{
+    cosmic_text::Weight,
+    reactive::{create_effect, create_rw_signal, RwSignal},
+    style::Style,
+    view::View,
+    views::{label, list, scroll, stack, Decorators},
+};
+use mod_mgr_lib::{
+    conflicts::ModConflictDetails,
+    extender::DivinityExtenderModStatus,
+    mod_data::Visibility,
+    mod_details::{dependency_statuses, DependencyStatus},
+    mod_updates::ModUpdate,
+    util::time::date_string,
+};
+
+use crate::{theme::theme, ui_mod_data::UIModData, view_util::button, MainData};
+
+/// Listing thousands of files makes the panel slow, and isn't useful to read through anyway
+const MAX_LISTED_FILES: usize = 200;
+
+#[derive(Debug, Clone)]
+struct ModDetails {
+    mod_data: UIModData,
+    dependencies: Vec<DependencyStatus>,
+    files: Vec<String>,
+    /// `None` if the mod's pak couldn't be read
+    conflicts: Option<ModConflictDetails>,
+    /// From the last check for mod updates
+    update: Option<ModUpdate>,
+}
+impl ModDetails {
+    fn new(main_data: &MainData, mod_data: UIModData) -> ModDetails {
+        let uuid = mod_data.data.uuid.clone();
+        let active = main_data.load_order().active;
+        let installed: Vec<String> = main_data
+            .mods
+            .with_untracked(|mods| mods.iter().map(|m| m.data.uuid.clone()).collect());
+        let dependencies: Vec<_> = mod_data.dependencies.iter().cloned().collect();
+
+        ModDetails {
+            dependencies: dependency_statuses(
+                &uuid,
+                &dependencies,
+                &active,
+                &installed,
+                &main_data.ignored_uuids(),
+            ),
+            files: main_data
+                .mod_files(&[uuid.clone()])
+                .pop()
+                .map(|files| files.files)
+                .unwrap_or_default(),
+            conflicts: main_data.mod_conflicts(&uuid),
+            update: main_data.mod_updates.with_untracked(|updates| {
+                updates
+                    .iter()
+                    .find(|update| update.uuid.eq_ignore_ascii_case(&uuid))
+                    .cloned()
+            }),
+            mod_data,
+        }
+    }
+}
+
+/// The names of the mods, for showing which mods a file conflicts with
+fn mod_names(main_data: &MainData, uuids: &[String]) -> String {
+    uuids
+        .iter()
+        .map(|uuid| {
+            main_data
+                .with_mod(uuid, |m| m.map(UIModData::display_name))
+                .unwrap_or_else(|| uuid.clone())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn details_panel(main_data: MainData) -> impl View {
+    let details: RwSignal<Option<ModDetails>> = create_rw_signal(None);
+    let effect_data = main_data.clone();
+    create_effect(move |_| {
+        let uuid = effect_data
+            .selection
+            .with(|selection| selection.selected.last().cloned());
+        // Also reruns when the mods or their updates change, such as when they're reordered
+        effect_data.mod_updates.with(|_| ());
+        let mod_data = uuid.and_then(|uuid| effect_data.with_mod(&uuid, |m| m.cloned()));
+        details.set(mod_data.map(|mod_data| ModDetails::new(&effect_data, mod_data)));
+    });
+
+    let overrides_data = main_data.clone();
+    let overridden_data = main_data.clone();
+    scroll(move || {
+        stack(move || {
+            (
+                label(move || {
+                    details.with(|details| match details {
+                        Some(details) => details.mod_data.display_name(),
+                        None => "Select a mod to see its details".to_string(),
+                    })
+                })
+                .style(|| {
+                    Style::BASE
+                        .font_size(theme().heading_font_size)
+                        .font_weight(Weight::BOLD)
+                        .margin_bottom_px(6.0)
+                }),
+                detail_row(details, "Author", |d| {
+                    (d.mod_data.data.author_visibility() == Visibility::Visible)
+                        .then(|| d.mod_data.data.author.clone())
+                }),
+                detail_row(details, "Version", |d| {
+                    Some(d.mod_data.data.version.to_string())
+                }),
+                detail_row(details, "Update", |d| {
+                    let update = d.update.as_ref()?;
+                    Some(if update.download_url.is_empty() {
+                        update.description()
+                    } else {
+                        format!("{}, from {}", update.description(), update.download_url)
+                    })
+                }),
+                detail_row(details, "Type", |d| Some(d.mod_data.data.mod_type.clone())),
+                detail_row(details, "Folder", |d| Some(d.mod_data.data.folder.clone())),
+                detail_row(details, "UUID", |d| Some(d.mod_data.data.uuid.clone())),
+                detail_row(details, "Last Updated", |d| {
+                    d.mod_data.data.last_modified.map(date_string)
+                }),
+                detail_row(details, "Tags", |d| {
+                    let tags = &d.mod_data.data.tags;
+                    (!tags.iter().all(|tag| tag.trim().is_empty())).then(|| tags.join(", "))
+                }),
+                detail_row(details, "Script Extender", |d| {
+                    let m = &d.mod_data;
+                    Some(match m.extender_status {
+                        DivinityExtenderModStatus::None => "Not used".to_string(),
+                        _ => m.tooltip(m.current_extender_version).trim_end().to_string(),
+                    })
+                }),
+                detail_row(details, "Description", |d| {
+                    (d.mod_data.data.description_visibility() == Visibility::Visible)
+                        .then(|| d.mod_data.data.description.clone())
+                }),
+                button("Open Pak Location", move || {
+                    if let Some(uuid) = details.with_untracked(|details| {
+                        Some(details.as_ref()?.mod_data.data.uuid.clone())
+                    }) {
+                        main_data.open_pak_location(&uuid);
+                    }
+                    true
+                })
+                .style(move || {
+                    Style::BASE
+                        .margin_top_px(6.0)
+                        .margin_bottom_px(6.0)
+                        .apply_if(details.with(Option::is_none), |s| s.hide())
+                }),
+                section(
+                    details,
+                    |d| format!("Dependencies ({})", d.dependencies.len()),
+                    |d| {
+                        d.dependencies
+                            .iter()
+                            .map(|status| {
+                                let text = format!(
+                                    "{} - {}",
+                                    status.dependency.name,
+                                    status.state.description()
+                                );
+                                (text, !status.state.is_satisfied())
+                            })
+                            .collect()
+                    },
+                ),
+                section(
+                    details,
+                    |d| match &d.conflicts {
+                        Some(conflicts) => format!("Overrides ({})", conflicts.overrides.len()),
+                        None => "Overrides (could not read the pak)".to_string(),
+                    },
+                    move |d| {
+                        d.conflicts
+                            .iter()
+                            .flat_map(|conflicts| &conflicts.overrides)
+                            .map(|file| {
+                                let names = mod_names(&overrides_data, &file.mods);
+                                (format!("{} (over {names})", file.path), false)
+                            })
+                            .collect()
+                    },
+                ),
+                section(
+                    details,
+                    |d| {
+                        let count = d.conflicts.as_ref().map_or(0, |c| c.overridden_by.len());
+                        format!("Overridden By ({count})")
+                    },
+                    move |d| {
+                        d.conflicts
+                            .iter()
+                            .flat_map(|conflicts| &conflicts.overridden_by)
+                            .map(|file| {
+                                let names = mod_names(&overridden_data, &file.mods);
+                                (format!("{} (by {names})", file.path), true)
+                            })
+                            .collect()
+                    },
+                ),
+                section(
+                    details,
+                    |d| format!("Files ({})", d.files.len()),
+                    |d| {
+                        let mut files: Vec<_> = d
+                            .files
+                            .iter()
+                            .take(MAX_LISTED_FILES)
+                            .map(|file| (file.clone(), false))
+                            .collect();
+                        if d.files.len() > MAX_LISTED_FILES {
+                            let more = d.files.len() - MAX_LISTED_FILES;
+                            files.push((format!("... and {more} more"), false));
+                        }
+                        files
+                    },
+                ),
+            )
+        })
+        .style(|| {
+            Style::BASE
+                .flex_col()
+                .padding_px(10.0)
+                .width_pct(100.0)
+                .font_size(theme().font_size)
+        })
+    })
+    .style(|| Style::BASE.color(theme().text).height_pct(100.0))
+}
+
+/// A line of the details, hidden if there is no mod selected or `text_fn` returns `None`.
+fn detail_row(
+    details: RwSignal<Option<ModDetails>>,
+    title: &'static str,
+    text_fn: impl Fn(&ModDetails) -> Option<String> + Copy + 'static,
+) -> impl View {
+    let text = move || details.with(|details| details.as_ref().and_then(text_fn));
+    stack(move || {
+        (
+            label(move || format!("{title}: ")).style(|| Style::BASE.font_weight(Weight::BOLD)),
+            label(move || text().unwrap_or_default()),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_row()
+            .margin_bottom_px(2.0)
+            .apply_if(text().is_none(), |s| s.hide())
+    })
+}
+
+/// A heading with a list of lines below it. Lines marked `true` are shown as problems.
+fn section(
+    details: RwSignal<Option<ModDetails>>,
+    title_fn: impl Fn(&ModDetails) -> String + 'static,
+    lines_fn: impl Fn(&ModDetails) -> Vec<(String, bool)> + 'static,
+) -> impl View {
+    stack(move || {
+        (
+            label(move || {
+                details.with(|details| details.as_ref().map(&title_fn).unwrap_or_default())
+            })
+            .style(|| {
+                Style::BASE
+                    .font_weight(Weight::BOLD)
+                    .margin_top_px(8.0)
+                    .margin_bottom_px(2.0)
+            }),
+            list(
+                move || {
+                    details
+                        .with(|details| details.as_ref().map(&lines_fn).unwrap_or_default())
+                        .into_iter()
+                        .enumerate()
+                },
+                |(idx, (text, _))| (*idx, text.clone()),
+                |(_, (text, is_problem))| {
+                    label(move || text.clone()).style(move || {
+                        Style::BASE
+                            .margin_left_px(8.0)
+                            .apply_if(is_problem, |s| s.color(theme().warning_text))
+                    })
+                },
+            )
+            .style(|| Style::BASE.flex_col()),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_col()
+            .apply_if(details.with(Option::is_none), |s| s.hide())
+    })
+}
@@ -1,46 +1,81 @@
 pub mod app_keys;
+mod cli;
+mod details_view;
 pub mod main_view;
 mod mod_table_view;
 pub mod resources;
 pub mod settings_view;
 pub mod tab_view;
 pub mod table;
+pub mod theme;
 pub mod ui_mod_data;
 pub mod util;
 pub mod view_util;
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::Duration,
 };
 
 use clap::Parser;
 use floem::{
-    peniko::Color,
-    reactive::{create_rw_signal, RwSignal},
+    glazier::{FileDialogOptions, FileSpec},
+    reactive::{create_effect, create_rw_signal, RwSignal},
 };
 use main_view::{app_view, StartupStage};
 use mod_mgr_lib::{
-    mod_data::ModData,
+    backups::{self, Backups},
+    conflicts::{read_mod_files, ConflictReport, ModConflictDetails, ModFiles},
+    diagnostics,
+    extender::{ExtenderInstall, ScriptExtenderConfig},
+    install::{self, InstallError, InstallOutcome},
+    keybindings::KeyBindings,
+    launch::{self, LaunchContext},
+    load_order::{LoadOrder, ModList, OrderHistory, Selection},
+    md5_cache::Md5Cache,
+    meta::ModMeta,
+    mod_data::{DivinityModDependencyData, ModData},
+    mod_details::conflict_order,
+    mod_loader::{
+        LoadModMsg, LoadPaths, LoadProgress, LoadedMods, ModLoader, DEFAULT_LOAD_TIMEOUT,
+    },
+    mod_updates::{self, ModUpdate},
+    mods_watcher::{rescan_paks, ModsWatcher, PakRescan, WatchedChanges},
+    order_file::OrderFile,
+    pathway::PathwayData,
+    profiles::{self, Profile},
     settings::{ScriptExtenderSettings, Settings},
-    util::divinity_registry_helper::{self, get_game_install_path},
-    BG3_STEAM_ID,
-};
-use resources::{
-    default_paths,
-    ignored_mods::{ignored_mods_iter, IGNORE_BUILTIN_PATH, IGNORE_DEPENDENCIES},
+    util::{
+        default_paths, divinity_registry_helper,
+        time::{now_secs, timestamp_name},
+    },
+    validate::{validate_load_order, ValidationFix, ValidationIssue, ValidationMod},
 };
+use resources::ignored_mods::{ignored_mods_iter, IGNORE_BUILTIN_PATH, IGNORE_DEPENDENCIES};
+use theme::set_theme;
 use ui_mod_data::UIModData;
 use util::space_replace;
 
-use crate::util::space_replace_front;
+/// How often to check on the mods being loaded in the background
+const LOAD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often to check the mods watcher. It waits for changes to settle, so this can be short.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Debug, Clone, Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     /// Run the application 'dry' without actually doing anything.
-    #[clap(long)]
+    #[clap(long, global = true)]
     dry: bool,
+    /// The player profile to use in commands, rather than the one the game last used
+    #[clap(long, global = true)]
+    profile: Option<String>,
+    /// Run a command without opening the GUI, printing the result as json
+    #[command(subcommand)]
+    command: Option<cli::Command>,
     // TODO(minor): custom settings path, overwrite active game data path, etc.
 }
 
@@ -50,9 +85,17 @@ pub struct Args {
 fn main() {
     let args = Args::parse();
 
-    // TODO: load settings from a settings file
-    let settings_path = PathBuf::from("Data/settings.json");
-    let settings = load_settings(&settings_path).expect("Failed to parse settings file");
+    let loaded = Settings::load();
+    // TODO: we could show a dialog box to the user listing these, and asking them if they want to
+    // open the settings file in a text editor to make it easy to share with me
+    for warning in &loaded.warnings {
+        eprintln!("{warning}");
+    }
+    let settings = loaded.settings;
+
+    if let Some(command) = &args.command {
+        std::process::exit(cli::run(&args, command, settings));
+    }
 
     // TODO: window title
     // TODO: on linux systems, alert that the window should be floating by default for tiling window managers. Or at least do that for settings/about
@@ -60,6 +103,9 @@ fn main() {
     // Start GUI
     let root_view = move || {
         let main_data = MainData::new(args.clone(), settings.clone());
+        main_data.load_profiles();
+        main_data.load_extender_settings();
+        main_data.refresh_extender_status();
         let startup_stage = main_data.startup_stage.clone();
 
         main_data.settings.with(move |settings| {
@@ -70,6 +116,10 @@ fn main() {
                 startup_stage.set(StartupStage::Ready);
             }
         });
+        // Otherwise they're loaded once the user has told us where the game is
+        if startup_stage.get_untracked() == StartupStage::Ready {
+            main_data.load_mods();
+        }
 
         // let view = app_view(main_data);
         // let id = view.id();
@@ -80,19 +130,6 @@ fn main() {
     floem::launch(root_view)
 }
 
-fn load_settings(path: &Path) -> serde_json::Result<Settings> {
-    if let Ok(content) = std::fs::read_to_string(&path) {
-        // TODO: we could show a dialog box to the user asking them if they want to
-        // - replace their settingsuration with the default settings
-        // - or open it in a text editor to make it easy to share with me
-        serde_json::from_str(&content)
-    } else {
-        // The file doesn't exist so we'll just use the default settingsuration.
-        eprintln!("There was no settings file at: {path:?}, using default settings.");
-        Ok(Settings::default())
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct MainData {
     pub dry: bool,
@@ -101,9 +138,36 @@ pub struct MainData {
 
     pub startup_stage: RwSignal<StartupStage>,
     pub pathway: RwSignal<PathwayData>,
-    // TODO: is this ever initialized?
+    /// The settings in the game's `bin` folder, `None` if they couldn't be read
     pub extender_settings: RwSignal<Option<ScriptExtenderSettings>>,
+    /// The Script Extender found in the game's `bin` folder
+    pub extender_install: RwSignal<ExtenderInstall>,
     pub mods: RwSignal<im::Vector<UIModData>>,
+    /// Loading the mods in the background, see [`MainData::load_mods`]
+    pub mod_loader: RwSignal<Option<ModLoader>>,
+    /// What is being loaded, `None` if nothing is
+    pub load_progress: RwSignal<Option<LoadProgress>>,
+    /// The problems from the last time the mods were loaded
+    pub load_errors: RwSignal<im::Vector<String>>,
+    /// Watching for changes to the mods folder and profiles, see [`MainData::watch_mods`]
+    pub mods_watcher: RwSignal<Option<ModsWatcher>>,
+    /// The files in each mod's pak, by uuid, read the first time they're needed
+    pub pak_files: RwSignal<HashMap<String, ModFiles>>,
+    /// Previous load orders, for undoing changes to it
+    pub order_history: RwSignal<OrderHistory>,
+    /// The selected mods in the active/inactive lists
+    pub selection: RwSignal<Selection>,
+    /// Set to focus the search box above one of the lists
+    pub focus_search: RwSignal<Option<ModList>>,
+    pub keybindings: RwSignal<KeyBindings>,
+    /// Whether a diagnostics report is being written, see [`MainData::export_diagnostics`]
+    pub exporting_diagnostics: RwSignal<bool>,
+    /// Mods with updates available, from the last time we checked
+    pub mod_updates: RwSignal<im::Vector<ModUpdate>>,
+    /// The player profiles in the `PlayerProfiles` folder, sorted by name
+    pub profiles: RwSignal<im::Vector<Profile>>,
+    /// Index into `profiles` of the profile whose load order is being edited
+    pub active_profile: RwSignal<Option<usize>>,
 
     pub ignore_builtin_path: RwSignal<im::Vector<Cow<'static, str>>>,
     pub ignored_mods: RwSignal<im::Vector<UIModData>>,
@@ -111,23 +175,19 @@ pub struct MainData {
 }
 impl MainData {
     // Roughly equivalent to C#'s LoadSettings, though it has the settings/settings loaded outside it.
-    pub fn new(args: Args, settings: Settings) -> MainData {
+    pub fn new(args: Args, mut settings: Settings) -> MainData {
         // TODO: App features?
-        let settings = create_rw_signal(settings.clone());
-
-        let (game_data_path, doc_path_override) = settings.with_untracked(|settings| {
-            (
-                settings.game_data_path.clone(),
-                settings.documents_folder_path_override.clone(),
-            )
-        });
+        let game_data_path = settings.game_data_path.clone();
+        let doc_path_override = settings.documents_folder_path_override.clone();
         let doc_path_override = if doc_path_override.as_os_str().is_empty() {
             None
         } else {
             Some(doc_path_override)
         };
 
-        let pathway_data = PathwayData::new(settings.clone(), &game_data_path, doc_path_override);
+        // Finding the paths can fill in the game executable path
+        let pathway_data = PathwayData::new(&mut settings, &game_data_path, doc_path_override);
+        let settings = create_rw_signal(settings);
 
         let pathway_data = create_rw_signal(pathway_data);
 
@@ -136,11 +196,25 @@ impl MainData {
 
         let startup_stage = create_rw_signal(StartupStage::Loading);
 
-        // TODO: there's various Keys that get actions added to them.
+        let keybindings = create_rw_signal(KeyBindings::load());
 
         let extender_settings = create_rw_signal(None);
+        let extender_install = create_rw_signal(ExtenderInstall::default());
 
         let mods = create_rw_signal(im::Vector::new());
+        let mod_loader = create_rw_signal(None);
+        let load_progress = create_rw_signal(None);
+        let load_errors = create_rw_signal(im::Vector::new());
+        let mods_watcher = create_rw_signal(None);
+        let pak_files = create_rw_signal(HashMap::new());
+        let order_history = create_rw_signal(OrderHistory::default());
+        let selection = create_rw_signal(Selection::default());
+        let focus_search = create_rw_signal(None);
+        let exporting_diagnostics = create_rw_signal(false);
+        let mod_updates = create_rw_signal(im::Vector::new());
+
+        let profiles = create_rw_signal(im::Vector::new());
+        let active_profile = create_rw_signal(None);
 
         // We don't load ignored mods from a path because it doesn't appear to get modified.
         let ignore_builtin_path = create_rw_signal(im::Vector::from_iter(
@@ -162,8 +236,10 @@ impl MainData {
         let ignored_mods = create_rw_signal(ignored_mods);
         let ignored_dependency_mods = create_rw_signal(ignored_dependency_mods);
 
+        // Switch the theme when it is changed in the settings
+        create_effect(move |_| set_theme(settings.with(|settings| settings.theme)));
+
         // TODO: log enabled listener
-        // TODO: theme change listener
         // TODO: extender settings enable extension listener
         // TODO: action on game launch changed listener
         // TODO: display filenames header change. Though this should be done in that area of code.
@@ -179,7 +255,21 @@ impl MainData {
             startup_stage,
             pathway: pathway_data,
             extender_settings,
+            extender_install,
             mods,
+            mod_loader,
+            load_progress,
+            load_errors,
+            mods_watcher,
+            pak_files,
+            order_history,
+            selection,
+            focus_search,
+            keybindings,
+            exporting_diagnostics,
+            mod_updates,
+            profiles,
+            active_profile,
             ignore_builtin_path,
             ignored_mods,
             ignored_dependency_mods,
@@ -212,6 +302,13 @@ impl MainData {
         })
     }
 
+    /// Whether to use the layout for touch screens, see [`mod_mgr_lib::settings::HandheldMode`].  
+    /// Note: this will track the signals required
+    pub fn is_handheld(&self) -> bool {
+        self.settings
+            .with(|settings| settings.handheld_mode.is_enabled())
+    }
+
     /// Check if the game exe can be opened.  
     /// Requires that the path is valid.  
     /// Note: this will track the signals required
@@ -293,79 +390,31 @@ impl MainData {
     }
 
     pub fn launch_game(&self) {
-        // TODO: linux implemenation
-        // TODO: macos implementation
-        // TODO: could we just generalize this by talking to steam? Probably can't pass arguments that way?
-
         self.settings.with_untracked(|settings| {
-            if !cfg!(windows) {
-                // TODO: Can we tell steam to launch the game with specific args?
-                // Or can we use proton-call and somehow get the proton version they have set?
-
-                // TODO: show this as an alert, or as a tooltip and don't let linux/mac users click it in the first place
-                // TODO: can mac users just launch the game with it since they have a native version?
-                eprintln!("Launching game is not supported on this platform");
-                return;
-            }
-
-            if !settings.game_executable_path.is_file() {
-                if settings.game_executable_path.as_os_str().is_empty() {
-                    eprintln!("No game executable path set");
-                    // TODO: show an alert.
-                } else {
-                    eprintln!(
-                        "Failed to find game executable at: {:?}",
-                        settings.game_executable_path
-                    );
+            let ctx = LaunchContext::detect();
+            let command = match launch::build_launch_command(settings, &ctx) {
+                Ok(command) => command,
+                Err(err) => {
                     // TODO: show an alert.
+                    eprintln!("{err}");
+                    return;
                 }
+            };
 
-                return;
-            }
-
-            let mut launch_params = settings.game_launch_params.clone();
-
-            if settings.game_story_log_enabled && !launch_params.contains("storylog") {
-                space_replace(&mut launch_params, "-storylog 1");
-            }
-
-            if settings.skip_launcher && !launch_params.contains("skip-launcher") {
-                space_replace_front(&mut launch_params, "-skip-launcher");
-            }
-
-            let mut exe_path = settings.game_executable_path.clone();
-            let exe_dir = exe_path.parent().unwrap();
-
-            if settings.launch_dx11 {
-                let next_exe = exe_dir.join("bg3_dx11.exe");
-                if next_exe.is_file() {
-                    exe_path = next_exe;
-                }
+            for warning in &command.warnings {
+                // TODO: show these as a tooltip on the launch button
+                eprintln!("Warning: {warning}");
             }
 
-            eprintln!("Opening game exe at: {exe_path:?} with args {launch_params}");
-
-            // We have to parse the arguments because Rust's command does not let you directly run
-            // it.
-            // We could manually pass it to system specific bash/cmd/whatever, but that is just
-            // harder and has issues of escaping the parameters properly.
-            //
-            // We could store the parameters as a vector, but that would break settings file
-            // compatibility with C# BG3ModManager. There's other methods but they have their own
-            // issues. This is simple and more than fast enough.
-            let args = shlex::split(&launch_params).unwrap_or_else(Vec::new);
-
-            let mut command = std::process::Command::new(exe_path);
-            command.args(args);
+            eprintln!(
+                "Launching game with {}: {command}",
+                settings.launch_method.title()
+            );
 
-            match command.spawn() {
-                Ok(_child) => {}
-                Err(err) => {
-                    eprintln!("Failed to launch game: {err}");
-                }
+            if let Err(err) = command.spawn() {
+                // TODO: show an alert.
+                eprintln!("{err}");
             }
-
-            todo!()
         })
     }
 
@@ -402,7 +451,9 @@ impl MainData {
             }
         }
 
-        // TODO: it runs export_extender_settings
+        if self.extender_settings.with_untracked(Option::is_some) {
+            self.export_extender_settings();
+        }
 
         self.settings.with_untracked(|settings| {
             if self.dry {
@@ -417,14 +468,39 @@ impl MainData {
     }
 
     pub fn open_settings_folder(&self) {
-        if let Err(err) = self
-            .settings
-            .with_untracked(|settings| open::that_detached(mod_mgr_lib::DIR_DATA))
-        {
+        if let Err(err) = open::that_detached(mod_mgr_lib::settings::settings_dir()) {
             eprintln!("Failed to open settings folder: {err}");
         }
     }
 
+    /// The Script Extender's settings file in the game's `bin` folder.  
+    /// `None` if the game executable path is not set.
+    pub fn extender_settings_path(&self) -> Option<PathBuf> {
+        self.settings.with_untracked(|settings| {
+            settings
+                .game_executable_path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(ScriptExtenderSettings::path)
+        })
+    }
+
+    /// Load the Script Extender's settings from the game's `bin` folder, using the defaults if
+    /// there is no settings file yet.
+    pub fn load_extender_settings(&self) {
+        let Some(path) = self.extender_settings_path() else {
+            return;
+        };
+
+        match ScriptExtenderSettings::load_from(&path) {
+            Ok(extender_settings) => self
+                .extender_settings
+                .set(Some(extender_settings.unwrap_or_default())),
+            // We leave it uninitialized so that we don't overwrite the file with the defaults
+            Err(err) => eprintln!("Failed to read Script Extender settings at {path:?}: {err}"),
+        }
+    }
+
     pub fn export_extender_settings(&self) {
         if self.extender_settings.with_untracked(Option::is_none) {
             // TODO: C# version writes it regardless of whether it is initialized?
@@ -432,32 +508,28 @@ impl MainData {
             return;
         }
 
-        let game_executable_path = self
-            .settings
-            .with_untracked(|settings| settings.game_executable_path.clone());
-
-        let dir_name = game_executable_path.parent().unwrap_or(&Path::new(""));
-        let output_file = dir_name.join("ScriptExtenderSettings.json");
+        let Some(output_file) = self.extender_settings_path() else {
+            eprintln!(
+                "Did not save script extender settings as the game executable path is not set"
+            );
+            return;
+        };
 
-        // TODO: we aren't saving it quite like how the mod manager does it. They skip over fields that are their default values, if a certain setting is off.
+        if self.dry {
+            eprintln!("Would have saved Script Extender settings to {output_file:?}");
+            return;
+        }
 
-        // let contents = serde_json::to_string_pretty(self.extender_settings.as_ref().unwrap());
-        let contents = self
+        let export_defaults = self
+            .settings
+            .with_untracked(|settings| settings.export_default_extender_settings);
+        let result = self
             .extender_settings
-            .with_untracked(|ext| serde_json::to_string_pretty(ext.as_ref().unwrap()));
-
-        match contents {
-            Ok(contents) => {
-                if let Err(err) = std::fs::write(&output_file, contents) {
-                    eprintln!("Failed to write Script Extender settings to {output_file:?}: {err}");
-                }
+            .with_untracked(|ext| ext.as_ref().unwrap().save_to(&output_file, export_defaults));
 
-                // TODO: it shows a success alert
-            }
-            // TODO: it shows an alert
-            Err(err) => {
-                eprintln!("Error tyrbubg Script Extender settings into json: {err:?}")
-            }
+        // TODO: it shows a success or failure alert
+        if let Err(err) = result {
+            eprintln!("Failed to write Script Extender settings to {output_file:?}: {err}");
         }
     }
 
@@ -473,8 +545,17 @@ impl MainData {
             .set(Some(ScriptExtenderSettings::default()));
     }
 
-    pub fn reset_keybindings(&self) {
-        // TODO:
+    /// Use the key bindings, and save them to the settings directory.
+    pub fn set_keybindings(&self, bindings: KeyBindings) {
+        self.keybindings.set(bindings);
+        if self.dry {
+            eprintln!("Dry run, not saving key bindings");
+            return;
+        }
+
+        if let Err(err) = self.keybindings.with_untracked(KeyBindings::save) {
+            eprintln!("Failed to save key bindings: {err}");
+        }
     }
 
     pub fn clear_workshop_cache(&self) {
@@ -504,7 +585,8 @@ impl MainData {
         })
     }
 
-    pub fn set_loaded_mods(&mut self, new_mods: impl Iterator<Item = UIModData>) {
+    pub fn set_loaded_mods(&self, new_mods: impl Iterator<Item = UIModData>) {
+        self.pak_files.update(HashMap::clear);
         self.mods.update(|mods| {
             mods.clear();
 
@@ -558,227 +640,996 @@ impl MainData {
         }
     }
 
-    pub fn load_workshop_mods(&self) {
-        todo!()
+    /// Ask the user for a mod to install, see [`MainData::install_mod`].
+    pub fn choose_mod_to_install(&self) {
+        let main_data = self.clone();
+        let options =
+            FileDialogOptions::new().allowed_types(vec![FileSpec::new("Mod", &["pak", "zip"])]);
+
+        floem::action::open_file(options, move |file_info| {
+            if let Some(file_info) = file_info {
+                main_data.install_mod(&file_info.path);
+            }
+        });
     }
 
-    pub fn check_for_mod_updates(&self) {
-        todo!()
+    /// Install a `.pak` or `.zip` of mods into the mods folder, adding them as inactive mods.
+    pub fn install_mod(&self, source: &Path) {
+        let mods_dir = self
+            .pathway
+            .with_untracked(|pathway| pathway.documents_mods_path.clone());
+        let installed: Vec<ModData> = self
+            .mods
+            .with_untracked(|mods| mods.iter().map(|m| m.data.clone()).collect());
+
+        let results = match install::install_mod(source, &mods_dir, &installed) {
+            Ok(results) => results,
+            // Still add the mods that were installed before it failed
+            Err(InstallError::Partial { installed, error }) => {
+                // TODO: show an alert.
+                eprintln!("Failed to install mod: {error}");
+                installed
+            }
+            Err(err) => {
+                // TODO: show an alert.
+                eprintln!("Failed to install mod: {err}");
+                return;
+            }
+        };
+
+        let mut md5_cache = Md5Cache::load();
+        self.mods.update(|mods| {
+            for mut result in results {
+                let name = result.meta.data.display_name();
+                match &result.outcome {
+                    InstallOutcome::Installed => eprintln!("Installed {name}"),
+                    InstallOutcome::Updated { previous } => {
+                        eprintln!("Updated {name} from v{previous}")
+                    }
+                    InstallOutcome::AlreadyInstalled => {
+                        eprintln!("{name} is already installed")
+                    }
+                    InstallOutcome::NewerInstalled { installed } => {
+                        eprintln!("A newer version of {name} (v{installed}) is already installed")
+                    }
+                    InstallOutcome::FileConflict { existing } => {
+                        eprintln!("Not installing {name}, a different mod is at {existing:?}")
+                    }
+                }
+
+                if !result.outcome.is_installed() {
+                    continue;
+                }
+
+                if let Err(err) = md5_cache.fill_mod_data(&mut result.meta.data) {
+                    eprintln!("Failed to hash {name}: {err}");
+                }
+
+                self.pak_files.update(|pak_files| {
+                    pak_files.remove(&result.meta.data.uuid);
+                });
+
+                replace_mod(mods, self.user_mod_data(result.meta, result.extender));
+            }
+        });
+
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
     }
 
-    pub fn load_mods(&self) {
-        todo!()
-        // enum LoadModCmd {
-        //     SetProgress(Cow<'static, str>),
-        // }
+    /// The [`UIModData`] for a mod in the mods folder, with its extender requirements checked.
+    fn user_mod_data(&self, meta: ModMeta, extender: Option<ScriptExtenderConfig>) -> UIModData {
+        let mut mod_data = UIModData::new(false, meta.data);
+        mod_data.dependencies = meta.dependencies.into_iter().collect();
+        mod_data.set_extender_config(extender);
+        mod_data.update_extender_status(
+            &self.extender_install.get_untracked(),
+            self.extensions_enabled(),
+        );
+        mod_data
+    }
 
-        // let mut final_mods = im::Vector::new();
-        // let mut mod_pak_data = im::Vector::new();
-        // let mut projects = im::Vector::new();
-        // let mut base_mods = im::Vector::new();
+    /// The files in the mods' paks, in the same order. Mods whose paks can't be read are skipped.
+    pub fn mod_files(&self, uuids: &[String]) -> Vec<ModFiles> {
+        let unread: Vec<ModData> = self.pak_files.with_untracked(|pak_files| {
+            self.mods.with_untracked(|mods| {
+                mods.iter()
+                    .filter(|m| {
+                        uuids.contains(&m.data.uuid) && !pak_files.contains_key(&m.data.uuid)
+                    })
+                    .map(|m| m.data.clone())
+                    .collect()
+            })
+        });
 
-        // // We send data over a channel to the main thread to be processed.
-        // let (tx, rx) = std::sync::mpsc::channel();
+        if !unread.is_empty() {
+            // TODO: this blocks the UI while reading the paks
+            let (files, errors) = read_mod_files(&unread);
+            for (mod_data, err) in errors {
+                eprintln!(
+                    "Failed to read the files in {}: {err:?}",
+                    mod_data.display_name()
+                );
+            }
+            self.pak_files.update(|pak_files| {
+                pak_files.extend(files.into_iter().map(|files| (files.uuid.clone(), files)))
+            });
+        }
 
-        // let game_data_path = self.settings.with_untracked(|s| s.game_data_path.clone());
+        self.pak_files.with_untracked(|pak_files| {
+            uuids
+                .iter()
+                .filter_map(|uuid| pak_files.get(uuid).cloned())
+                .collect()
+        })
+    }
 
-        // let game_dir_found = !game_data_path.as_os_str().is_empty() && game_data_path.is_dir();
+    /// The files that the mod shares with the enabled mods, and which of them the game uses.
+    /// An inactive mod is checked as if it were enabled last.
+    pub fn mod_conflicts(&self, uuid: &str) -> Option<ModConflictDetails> {
+        let order = conflict_order(uuid, &self.load_order().active);
+        ConflictReport::analyze(&self.mod_files(&order)).mod_details(uuid)
+    }
 
-        // if game_dir_found {
-        //     tx.send(LoadModCmd::SetProgress(Cow::Borrowed(
-        //         "Loading base game mods from data folder...",
-        //     )));
-        //     eprintln!("GameDataPath is {game_data_path:?}");
+    /// Open the folder containing the mod's pak in the user's file browser
+    pub fn open_pak_location(&self, uuid: &str) {
+        let folder = self.with_mod(uuid, |mod_d| {
+            Some(mod_d?.data.file_path.parent()?.to_path_buf())
+        });
+        let Some(folder) = folder else {
+            eprintln!("Mod {uuid} does not have a pak");
+            return;
+        };
 
-        //     // TODO: they have a timeout
-        //     std::thread::spawn(|| {
-        //         let mut base_mods = im::Vector::new();
+        if let Err(err) = open::that_detached(&folder) {
+            eprintln!("Failed to open the pak location: {err}");
+        }
+    }
 
-        //         let mod_resources = ModResources::default();
-        //         let mod_helper = ModPathVisitor::new(mod_resources);
-        //     });
-        // }
+    /// Whether the extender's `EnableExtensions` setting is on
+    pub fn extensions_enabled(&self) -> bool {
+        self.extender_settings.with_untracked(|extender_settings| {
+            extender_settings
+                .as_ref()
+                .map(|extender_settings| extender_settings.enable_extensions)
+                .unwrap_or(true)
+        })
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct PathwayData {
-    /// Path to the root game folder.  
-    /// i.e. `SteamLibrary/steamapps/common/Baldur's Gate 3`  
-    /// i.e. `.steam/steamapps/common/Baldur's Gate 3`
-    pub install_path: PathBuf,
+    /// Look for the Script Extender in the game's `bin` folder, and update whether each mod's
+    /// extender requirements are met.
+    pub fn refresh_extender_status(&self) {
+        let bin_dir = self.settings.with_untracked(|settings| {
+            settings
+                .game_executable_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+        });
 
-    /// Windows: Path to `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3`
-    /// Linux: TODO (pfx)
-    /// MacOS: TODO
-    pub larian_documents_folder: PathBuf,
-    /// Windows: Path to `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3/Mods`
-    pub documents_mods_path: PathBuf,
-    /// Windows: Path to `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3/GMCampaigns`
-    pub documents_gm_campaigns_path: PathBuf,
-    /// Windows: Path to `%LOCALAPPDATA%/Larian Studios/Baldur's Gate 3/PlayerProfiles`
-    pub documents_profiles_path: PathBuf,
-    // TODO: last save file path
-    // TODO: script extendere latest release url
-    // TODO: script extender latest release version
-}
-impl PathwayData {
-    // TODO(minor): Does current game data path really need to be a separate field in this impl? Or even in the original?
-    /// Create a new [`PathwayData`] instance from the given information.  
-    /// If the supplied `larian_documents_folder` argument is not set it will be inferred.
-    pub fn new(
-        settings: RwSignal<Settings>,
-        current_game_data_path: &Path,
-        larian_documents_folder: Option<PathBuf>,
-    ) -> PathwayData {
-        let (game_executable_path, game_data_path) = settings.with_untracked(|settings| {
-            (
-                settings.game_executable_path.clone(),
-                settings.game_data_path.clone(),
-            )
+        let install = ExtenderInstall::detect(&bin_dir);
+        match &install.updater_version {
+            Some(version) => {
+                eprintln!("Found the Script Extender updater v{version} in {bin_dir:?}")
+            }
+            None => eprintln!("Script Extender is not installed in {bin_dir:?}"),
+        }
+
+        let extensions_enabled = self.extensions_enabled();
+        self.mods.update(|mods| {
+            for mod_data in mods.iter_mut() {
+                mod_data.update_extender_status(&install, extensions_enabled);
+            }
         });
+        self.extender_install.set(install);
+    }
 
-        if cfg!(target_os = "linux") {
-            return PathwayData {
-                install_path: Default::default(),
-                larian_documents_folder: Default::default(),
-                documents_mods_path: Default::default(),
-                documents_gm_campaigns_path: Default::default(),
-                documents_profiles_path: Default::default(),
-            };
+    /// Find the player profiles, and select the one the game last used.
+    pub fn load_profiles(&self) {
+        let profiles_dir = self
+            .pathway
+            .with_untracked(|pathway| pathway.documents_profiles_path.clone());
+
+        let (found, active) = match profiles::load_profiles(&profiles_dir) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Failed to read player profiles at {profiles_dir:?}: {err}");
+                (Vec::new(), None)
+            }
+        };
+
+        self.profiles.set(found.into_iter().collect());
+        self.active_profile.set(None);
+        if let Some(active) = active {
+            self.select_profile(active);
         }
+    }
 
-        // TODO: some of this should be windows only
-        let mut documents_folder = std::env::var_os("LOCALAPPDATA")
-            .map(PathBuf::from)
-            .unwrap_or_else(|| {
-                PathBuf::from("C:/Users")
-                    .join(std::env::var("USERNAME").expect("Failed to get username"))
-                    .join("AppData/Local")
-            });
+    /// Switch to the profile, applying its load order to the mods.
+    pub fn select_profile(&self, idx: usize) {
+        let Some(profile) = self
+            .profiles
+            .with_untracked(|profiles| profiles.get(idx).cloned())
+        else {
+            return;
+        };
+
+        eprintln!("Selecting profile {}", profile.title());
+        self.active_profile.set(Some(idx));
+        self.order_history.update(OrderHistory::clear);
+        self.selection.update(Selection::clear);
+
+        match profile.read_load_order() {
+            Ok(order) => self.apply_load_order(&order),
+            Err(err) => eprintln!(
+                "Failed to read load order of profile {}: {err}",
+                profile.name
+            ),
+        }
+    }
+
+    /// Activate the mods in the order, in that order, and deactivate the rest.
+    /// Mods in the order that aren't installed are skipped.
+    pub fn apply_load_order(&self, order: &[DivinityModDependencyData]) {
+        self.mods.update(|mods| {
+            let mut remaining = mods.clone();
+            let mut ordered = im::Vector::new();
+            for entry in order {
+                let idx = remaining
+                    .iter()
+                    .position(|m| m.data.uuid.eq_ignore_ascii_case(&entry.uuid));
+                if let Some(idx) = idx {
+                    let mut mod_data = remaining.remove(idx);
+                    mod_data.is_active = true;
+                    ordered.push_back(mod_data);
+                } else {
+                    eprintln!("Mod {} in the load order is not installed", entry.name);
+                }
+            }
+
+            for mut mod_data in remaining {
+                mod_data.is_active = false;
+                ordered.push_back(mod_data);
+            }
 
-        // TODO: I don't really know why they initialize documents game folder when they already have it set in a json. Just load it from there? I just skip that.
+            *mods = ordered;
+        });
+    }
 
-        let mut larian_documents_folder = larian_documents_folder
-            .unwrap_or_else(|| documents_folder.join(default_paths::DOCUMENTS_GAME_FOLDER));
+    /// The order of the mods shown in the active and inactive lists.
+    pub fn load_order(&self) -> LoadOrder {
+        self.mods.with_untracked(|mods| {
+            let mut order = LoadOrder::default();
+            for m in mods.iter().filter(|m| m.is_listed()) {
+                let list = if m.is_active {
+                    &mut order.active
+                } else {
+                    &mut order.inactive
+                };
+                list.push(m.data.uuid.clone());
+            }
+            order
+        })
+    }
 
-        if !larian_documents_folder.is_dir() {
-            if let Some(user_folder) = dirs::home_dir() {
-                if user_folder.is_dir() {
-                    documents_folder = user_folder.join("AppData/Local");
-                    larian_documents_folder =
-                        documents_folder.join(default_paths::DOCUMENTS_GAME_FOLDER);
+    /// Rearrange the mods to match the order, activating the mods in its active list.
+    /// Mods that aren't in the order are kept after it, inactive.
+    pub fn set_order(&self, order: &LoadOrder) {
+        self.mods.update(|mods| {
+            let mut remaining = mods.clone();
+            let mut ordered = im::Vector::new();
+            for (uuid, is_active) in order
+                .active
+                .iter()
+                .map(|uuid| (uuid, true))
+                .chain(order.inactive.iter().map(|uuid| (uuid, false)))
+            {
+                if let Some(idx) = remaining.iter().position(|m| &m.data.uuid == uuid) {
+                    let mut mod_data = remaining.remove(idx);
+                    mod_data.is_active = is_active;
+                    ordered.push_back(mod_data);
                 }
             }
+
+            for mut mod_data in remaining {
+                mod_data.is_active = false;
+                ordered.push_back(mod_data);
+            }
+
+            *mods = ordered;
+        });
+    }
+
+    /// Change the load order, so that it can be undone.
+    pub fn change_order(&self, change: impl FnOnce(&mut LoadOrder)) {
+        let mut order = self.load_order();
+        let mut changed = false;
+        self.order_history
+            .update(|history| changed = history.apply(&mut order, change));
+        if changed {
+            self.set_order(&order);
+        }
+    }
+
+    pub fn undo_order(&self) {
+        let mut order = self.load_order();
+        let mut changed = false;
+        self.order_history
+            .update(|history| changed = history.undo(&mut order));
+        if changed {
+            self.set_order(&order);
         }
+    }
+
+    pub fn redo_order(&self) {
+        let mut order = self.load_order();
+        let mut changed = false;
+        self.order_history
+            .update(|history| changed = history.redo(&mut order));
+        if changed {
+            self.set_order(&order);
+        }
+    }
+
+    /// Check the active mods for problems, such as missing dependencies.
+    /// Note: this will track the signals required
+    pub fn validate_order(&self) -> Vec<ValidationIssue> {
+        let hide_missing = self
+            .settings
+            .with(|settings| settings.disable_missing_mod_warnings);
+        let (order, installed) = self.mods.with(|mods| {
+            let order: Vec<_> = mods
+                .iter()
+                .filter(|m| m.is_listed() && m.is_active)
+                .map(|m| DivinityModDependencyData::from_mod_data(&m.data))
+                .collect();
+            let installed: Vec<_> = mods
+                .iter()
+                .map(|m| ValidationMod {
+                    data: m.data.clone(),
+                    dependencies: m.dependencies.iter().cloned().collect(),
+                    extender_status: m.extender_status,
+                    required_extender_version: m.extender_data.required_extension_version,
+                })
+                .collect();
+            (order, installed)
+        });
+
+        validate_load_order(&order, &installed, &self.ignored_uuids())
+            .into_iter()
+            .filter(|issue| !(hide_missing && issue.is_missing_mod()))
+            .collect()
+    }
 
-        let mod_pak_folder = larian_documents_folder.join("MOds");
-        let gm_campaign_folder = larian_documents_folder.join("GMCampaigns");
-        let profile_folder = larian_documents_folder.join("PlayerProfiles");
+    /// Change the load order to fix a problem found by [`MainData::validate_order`].
+    pub fn apply_fix(&self, fix: &ValidationFix) {
+        self.change_order(|order| fix.apply(order));
+    }
+
+    /// Update the selection for a click on the mod, see [`Selection::click`].
+    pub fn click_mod(&self, uuid: &str, shift: bool, ctrl: bool) {
+        let order = self.load_order();
+        self.selection
+            .update(|selection| selection.click(&order, uuid, shift, ctrl));
+    }
 
-        let mut pathway = PathwayData {
-            larian_documents_folder,
-            documents_mods_path: mod_pak_folder,
-            documents_gm_campaigns_path: gm_campaign_folder,
-            documents_profiles_path: profile_folder,
+    /// Move the selected mods to where they were dropped: onto a mod, or onto the end of a list.
+    pub fn drop_selected(&self, onto: Option<&str>, list: ModList) {
+        let uuids = self
+            .selection
+            .with_untracked(|selection| selection.ordered(&self.load_order()));
+        if uuids.is_empty() {
+            return;
+        }
 
-            install_path: PathBuf::default(),
+        self.change_order(|order| match onto {
+            Some(onto) => order.drop_mods(&uuids, onto),
+            None => order.move_mods(&uuids, list, order.list(list).len()),
+        });
+        let order = self.load_order();
+        self.selection.update(|selection| {
+            if let Some((list, _)) = order.find(&uuids[0]) {
+                selection.select(list, uuids);
+            }
+        });
+    }
+
+    /// Move the selected mods to the other list.  
+    /// With `shift_focus_on_swap` the selection follows them, otherwise it moves to the next mod
+    /// in the list they left.
+    pub fn swap_selected(&self) {
+        let order = self.load_order();
+        let uuids = self
+            .selection
+            .with_untracked(|selection| selection.ordered(&order));
+        let Some((_, index)) = uuids.first().and_then(|uuid| order.find(uuid)) else {
+            return;
         };
 
-        if documents_folder.is_dir() {
-            std::fs::create_dir(&pathway.larian_documents_folder)
-                .expect("Failed to create larian game documents folder");
-            if !pathway.documents_mods_path.is_dir() {
-                eprintln!(
-                    "No mods folder found at {:?}. Creating folder.",
-                    pathway.documents_mods_path
-                );
-                std::fs::create_dir(&pathway.documents_mods_path)
-                    .expect("Failed to create mods folder");
+        self.change_order(|order| order.swap_lists(&uuids));
+        let follow = self
+            .settings
+            .with_untracked(|settings| settings.shift_focus_on_swap);
+        let order = self.load_order();
+        self.selection
+            .update(|selection| selection.swapped(&order, index, follow));
+    }
+
+    /// Move the selected mods one place up or down in their list.
+    pub fn shift_selected(&self, up: bool) {
+        let uuids = self
+            .selection
+            .with_untracked(|selection| selection.ordered(&self.load_order()));
+        self.change_order(|order| order.shift_mods(&uuids, up));
+    }
+
+    /// Write the active mods to the active profile's `modsettings.lsx`.
+    pub fn save_load_order(&self) {
+        let Some(profile) = self.active_profile.get_untracked().and_then(|idx| {
+            self.profiles
+                .with_untracked(|profiles| profiles.get(idx).cloned())
+        }) else {
+            // TODO: show an alert
+            eprintln!("No profile selected to save the load order to");
+            return;
+        };
+
+        if self.dry {
+            eprintln!(
+                "Dry run, not saving load order to {:?}",
+                profile.mod_settings_path()
+            );
+            return;
+        }
+
+        let mut md5_cache = Md5Cache::load();
+        if let Err(err) = self.backup_load_order(&profile, &mut md5_cache) {
+            // TODO: ask the user whether to save anyway
+            eprintln!("Not saving load order, as backing up the current one failed: {err}");
+            return;
+        }
+
+        let mut active: Vec<ModData> = self.mods.with_untracked(|mods| {
+            mods.iter()
+                .filter(|m| m.is_active)
+                .map(|m| m.data.clone())
+                .collect()
+        });
+        // Hash through the cache rather than keeping the md5 that was read, so that paks which were
+        // updated since then get their new hash. The game's own paks are huge and aren't hashed.
+        for mod_data in active.iter_mut().filter(|m| !m.is_larian_mod) {
+            if let Err(err) = md5_cache.fill_mod_data(mod_data) {
+                eprintln!("Failed to hash {}: {err}", mod_data.display_name());
             }
+        }
 
-            if !pathway.documents_gm_campaigns_path.is_dir() {
-                eprintln!(
-                    "No GM campaigns folder found at {:?}. Creating folder.",
-                    pathway.documents_gm_campaigns_path
-                );
-                std::fs::create_dir(&pathway.documents_gm_campaigns_path)
-                    .expect("Failed to create GM campaigns folder");
+        match profile.write_load_order(&active) {
+            Ok(()) => eprintln!("Saved load order to {:?}", profile.mod_settings_path()),
+            Err(err) => eprintln!("Failed to save load order: {err}"),
+        }
+
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
+    }
+
+    /// Write the active mods to an order file in the saved load orders folder, named after the
+    /// profile and the current time.
+    pub fn export_load_order(&self) {
+        let name = self
+            .active_profile
+            .get_untracked()
+            .and_then(|idx| {
+                self.profiles
+                    .with_untracked(|profiles| Some(profiles.get(idx)?.name.clone()))
+            })
+            .unwrap_or_else(|| "Export".to_string());
+        let order: Vec<DivinityModDependencyData> = self.mods.with_untracked(|mods| {
+            mods.iter()
+                .filter(|m| m.is_active)
+                .map(|m| DivinityModDependencyData::from_mod_data(&m.data))
+                .collect()
+        });
+
+        let created = now_secs();
+        let dir = self
+            .settings
+            .with_untracked(|settings| settings.saved_load_orders_path.clone());
+        let file_name = name.replace(|c: char| !c.is_alphanumeric() && c != '-', "_");
+        let path = dir.join(format!("{file_name}_{}.json", timestamp_name(created)));
+        if self.dry {
+            eprintln!("Dry run, not exporting load order to {path:?}");
+            return;
+        }
+
+        let result = OrderFile::from_order(name, &order)
+            .to_json()
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                std::fs::create_dir_all(&dir)?;
+                std::fs::write(&path, json)?;
+                Ok(())
+            });
+        match result {
+            Ok(()) => eprintln!("Exported load order to {path:?}"),
+            // TODO: show an alert.
+            Err(err) => eprintln!("Failed to export load order to {path:?}: {err}"),
+        }
+    }
+
+    /// Snapshot the profile's current load order before it is overwritten.
+    fn backup_load_order(
+        &self,
+        profile: &Profile,
+        md5_cache: &mut Md5Cache,
+    ) -> Result<backups::Snapshot, backups::BackupError> {
+        let mods_dir = self
+            .pathway
+            .with_untracked(|pathway| pathway.documents_mods_path.clone());
+        let paks = backups::hash_paks(&mods_dir, md5_cache)?;
+        Backups::new().snapshot(profile, paks)
+    }
+
+    /// The backed up load orders, newest first
+    pub fn list_backups(&self) -> Vec<backups::Snapshot> {
+        Backups::new().list().unwrap_or_else(|err| {
+            eprintln!("Failed to list backups: {err}");
+            Vec::new()
+        })
+    }
+
+    /// Restore a backed up load order to the profile it was taken of, and select that profile.
+    pub fn restore_backup(&self, id: &str) {
+        let backups = Backups::new();
+        let snapshot = match backups.find(id) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                eprintln!("Failed to read backup: {err}");
+                return;
             }
+        };
 
-            if !pathway.documents_profiles_path.is_dir() {
-                eprintln!(
-                    "No player profiles folder found at {:?}. Creating folder.",
-                    pathway.documents_profiles_path
-                );
-                std::fs::create_dir(&pathway.documents_profiles_path)
-                    .expect("Failed to create player profiles folder");
+        let Some(idx) = self
+            .profiles
+            .with_untracked(|profiles| profiles.iter().position(|p| snapshot.is_for(p)))
+        else {
+            eprintln!(
+                "The profile {} that was backed up no longer exists",
+                snapshot.info.profile_name
+            );
+            return;
+        };
+        let profile = self
+            .profiles
+            .with_untracked(|profiles| profiles[idx].clone());
+
+        if self.dry {
+            eprintln!("Dry run, not restoring backup {id} to {}", profile.title());
+            return;
+        }
+
+        let mods_dir = self
+            .pathway
+            .with_untracked(|pathway| pathway.documents_mods_path.clone());
+        let mut md5_cache = Md5Cache::load();
+        let result = backups::hash_paks(&mods_dir, &mut md5_cache)
+            .map_err(backups::BackupError::from)
+            .and_then(|paks| backups.restore(&snapshot, &profile, paks));
+        if let Err(err) = md5_cache.save() {
+            eprintln!("Failed to save MD5 cache: {err}");
+        }
+
+        match result {
+            Ok(report) => {
+                for pak in &report.paks.missing {
+                    eprintln!("{pak} was in the mods folder when backed up, but is now missing");
+                }
+                for pak in &report.paks.changed {
+                    eprintln!("{pak} has changed since it was backed up");
+                }
+                self.select_profile(idx);
             }
-        } else {
-            // TODO: show the error dialog so it isn't hidden
-            eprintln!("Failed to find %USERPROFILE%\\Documents folder. This is weird. Got path: {documents_folder:?}");
+            Err(err) => eprintln!("Failed to restore backup: {err}"),
+        }
+    }
+
+    /// The uuids of the game's own modules, which are never in the mods folder
+    pub fn ignored_uuids(&self) -> Vec<String> {
+        let mut ignored: Vec<String> = IGNORE_DEPENDENCIES.iter().map(|u| u.to_string()).collect();
+        self.ignored_mods.with_untracked(|ignored_mods| {
+            ignored.extend(ignored_mods.iter().map(|m| m.data.uuid.clone()))
+        });
+
+        ignored
+    }
+
+    /// Write a diagnostics report to the settings folder and open the folder, so that the user can
+    /// share it when asking for help. The paks are hashed on a background thread.
+    pub fn export_diagnostics(&self) {
+        if self.exporting_diagnostics.get_untracked() {
+            return;
         }
 
-        // If the current game data path isn't valid then we'll try to find it.
-        if current_game_data_path.as_os_str().is_empty() || !current_game_data_path.is_dir() {
-            let install_path = get_game_install_path(
-                default_paths::GOG_INFO.registry_32,
-                default_paths::GOG_INFO.registry_64,
-                BG3_STEAM_ID,
+        let profile = self.active_profile.get_untracked().and_then(|idx| {
+            self.profiles
+                .with_untracked(|profiles| profiles.get(idx).cloned())
+        });
+        let paths = self.pathway.with_untracked(PathwayData::diagnostic_paths);
+        let settings = self.settings.get_untracked();
+        let ignored = self.ignored_uuids();
+        let dry = self.dry;
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut md5_cache = Md5Cache::load();
+            let report = diagnostics::collect_diagnostics(
+                &settings,
+                paths,
+                profile.as_ref(),
+                &ignored,
+                &mut md5_cache,
             );
+            if let Err(err) = md5_cache.save() {
+                eprintln!("Failed to save MD5 cache: {err}");
+            }
 
-            if let Some(install_path) = install_path {
-                if !install_path.as_os_str().is_empty() && install_path.is_dir() {
-                    pathway.install_path = install_path;
-
-                    if !game_executable_path.is_file() {
-                        let exe_path = if !divinity_registry_helper::is_gog() {
-                            pathway
-                                .install_path
-                                .join(default_paths::STEAM_INFO.exe_path)
-                        } else {
-                            pathway.install_path.join(default_paths::GOG_INFO.exe_path)
-                        };
-
-                        if exe_path.is_file() {
-                            settings.update(|settings| {
-                                settings.game_executable_path = exe_path;
-                                eprintln!("Exe path set to {:?}", settings.game_executable_path);
-                            })
-                        }
-                    }
+            let dir = mod_mgr_lib::settings::settings_dir();
+            let path = dir.join(format!("diagnostics_{}.zip", report.created));
+            let result = if dry {
+                eprintln!("Dry run, not writing diagnostics to {path:?}");
+                Ok(None)
+            } else {
+                std::fs::create_dir_all(&dir)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| report.write_to(&path))
+                    .map(|()| Some(path.clone()))
+                    .map_err(|err| err.context(format!("Failed to write diagnostics to {path:?}")))
+            };
+            // The receiver is gone if the window was closed
+            let _ = tx.send(result);
+        });
+
+        self.exporting_diagnostics.set(true);
+        self.poll_diagnostics(rx);
+    }
+
+    /// Open the settings folder once the diagnostics are written, checking again shortly if they
+    /// aren't.
+    fn poll_diagnostics(&self, rx: Receiver<anyhow::Result<Option<PathBuf>>>) {
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => {
+                let main_data = self.clone();
+                floem::action::exec_after(LOAD_POLL_INTERVAL, move |_| {
+                    main_data.poll_diagnostics(rx)
+                });
+                return;
+            }
+            Err(TryRecvError::Disconnected) => {
+                Err(anyhow::anyhow!("Writing diagnostics stopped unexpectedly"))
+            }
+        };
+        self.exporting_diagnostics.set(false);
+
+        match result {
+            Ok(Some(path)) => {
+                eprintln!("Wrote diagnostics to {path:?}");
+                let dir = mod_mgr_lib::settings::settings_dir();
+                if let Err(err) = open::that_detached(&dir) {
+                    eprintln!("Failed to open settings folder: {err}");
                 }
             }
+            Ok(None) => {}
+            // TODO: show an alert.
+            Err(err) => eprintln!("{err:#}"),
+        }
+    }
+
+    pub fn load_workshop_mods(&self) {
+        todo!()
+    }
+
+    /// Check the installed mods against the update manifest from the settings.
+    pub fn check_for_mod_updates(&self) {
+        let location = self
+            .settings
+            .with_untracked(|settings| settings.mod_update_manifest.clone());
+        if location.trim().is_empty() {
+            eprintln!("No mod update manifest is set, not checking for mod updates");
+            return;
+        }
+
+        let installed: Vec<ModData> = self
+            .mods
+            .with_untracked(|mods| mods.iter().map(|m| m.data.clone()).collect());
+
+        // TODO: this blocks the UI while downloading the manifest
+        let source = mod_updates::manifest_source(&location);
+        let updates = match mod_updates::check_for_mod_updates(source.as_ref(), &installed) {
+            Ok(updates) => updates,
+            Err(err) => {
+                // TODO: show an alert.
+                eprintln!("Failed to check for mod updates: {err}");
+                return;
+            }
+        };
+
+        for update in &updates {
+            eprintln!("{}: {}", update.name, update.description());
+        }
+
+        self.mod_updates.set(updates.into_iter().collect());
+    }
+
+    /// Read the game's modules and the mods in the mods folder on a background thread, replacing
+    /// the mods once it is done. Loading that is already running is cancelled.
+    pub fn load_mods(&self) {
+        // Already being polled, which carries on with the new loader
+        let is_polling = self.mod_loader.with_untracked(Option::is_some);
+        self.cancel_loading();
+
+        let game_data_path = self
+            .settings
+            .with_untracked(|settings| settings.game_data_path.clone());
+        let paths = LoadPaths {
+            game_data_path,
+            mods_path: self
+                .pathway
+                .with_untracked(|pathway| pathway.documents_mods_path.clone()),
+        };
+
+        self.load_errors.set(im::Vector::new());
+        self.load_progress
+            .set(Some(LoadProgress::new("Loading mods...", 0, 0)));
+        self.mod_loader
+            .set(Some(ModLoader::start(paths, DEFAULT_LOAD_TIMEOUT)));
+        if !is_polling {
+            self.poll_mod_loader();
+        }
+    }
+
+    /// Stop loading the mods, keeping the ones that were loaded before.
+    pub fn cancel_loading(&self) {
+        self.mod_loader.with_untracked(|loader| {
+            if let Some(loader) = loader {
+                loader.cancel();
+            }
+        });
+    }
+
+    /// Handle the messages from the loading thread, checking again shortly if it isn't done.
+    fn poll_mod_loader(&self) {
+        let mut msgs = Vec::new();
+        self.mod_loader.update(|loader| {
+            if let Some(loader) = loader {
+                msgs = loader.poll();
+            }
+        });
+
+        let mut done = msgs.is_empty() && self.mod_loader.with_untracked(Option::is_none);
+        for msg in msgs {
+            done |= msg.is_last();
+            match msg {
+                LoadModMsg::Progress(progress) => self.load_progress.set(Some(progress)),
+                LoadModMsg::Error(err) => {
+                    eprintln!("{err}");
+                    self.load_errors
+                        .update(|errors| errors.push_back(err.to_string()));
+                }
+                LoadModMsg::Finished(loaded) => self.finish_loading(loaded),
+                LoadModMsg::Cancelled => eprintln!("Cancelled loading the mods"),
+                LoadModMsg::TimedOut => {
+                    let err = "Loading the mods stopped making progress, so it was stopped";
+                    eprintln!("{err}");
+                    self.load_errors
+                        .update(|errors| errors.push_back(err.to_string()));
+                }
+            }
+        }
+
+        if done {
+            self.mod_loader.set(None);
+            self.load_progress.set(None);
         } else {
-            // TODO: check how it is handling canonicalize errors in C# code
-            let install_path = game_data_path.join("../..").canonicalize().unwrap();
-            pathway.install_path = install_path;
-
-            if !game_executable_path.is_file() {
-                let exe_path = if !divinity_registry_helper::is_gog() {
-                    pathway
-                        .install_path
-                        .join(default_paths::STEAM_INFO.exe_path)
-                } else {
-                    pathway.install_path.join(default_paths::GOG_INFO.exe_path)
-                };
+            let main_data = self.clone();
+            floem::action::exec_after(LOAD_POLL_INTERVAL, move |_| main_data.poll_mod_loader());
+        }
+    }
 
-                if exe_path.is_file() {
-                    settings.update(|settings| {
-                        settings.game_executable_path = exe_path;
-                        eprintln!("Exe path set to {:?}", settings.game_executable_path);
-                    });
+    /// Replace the mods with the ones that were loaded, and apply the profile's load order to them.
+    fn finish_loading(&self, loaded: LoadedMods) {
+        eprintln!(
+            "Loaded {} base game mods and {} user mods",
+            loaded.base_mods.len(),
+            loaded.user_mods.len()
+        );
+
+        let base_mods = loaded.base_mods.into_iter().map(|meta| {
+            let mut mod_data = UIModData::new(true, meta.data);
+            mod_data.dependencies = meta.dependencies.into_iter().collect();
+            mod_data
+        });
+        let user_mods = loaded
+            .user_mods
+            .into_iter()
+            .map(|pak_mod| self.user_mod_data(pak_mod.meta, pak_mod.extender));
+
+        // User mods replace base game mods with the same uuid if they're newer
+        let mut final_mods = im::Vector::new();
+        self.merge_mod_lists(&mut final_mods, base_mods);
+        self.merge_mod_lists(&mut final_mods, user_mods);
+        self.set_loaded_mods(final_mods.into_iter());
+
+        match self.active_profile.get_untracked() {
+            Some(idx) => self.select_profile(idx),
+            None => self.load_profiles(),
+        }
+
+        self.watch_mods();
+    }
+
+    /// Start watching the mods folder and the profiles for changes, so that they're picked up
+    /// without reloading everything. Restarts the watcher if it is already running.
+    pub fn watch_mods(&self) {
+        let (mods_dir, profiles_dir) = self.pathway.with_untracked(|pathway| {
+            (
+                pathway.documents_mods_path.clone(),
+                pathway.documents_profiles_path.clone(),
+            )
+        });
+        let force_polling = self
+            .settings
+            .with_untracked(|settings| settings.poll_for_mod_changes);
+
+        let watcher = match ModsWatcher::new(&mods_dir, &profiles_dir, force_polling) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                eprintln!("Failed to watch the mods folder for changes: {err}");
+                self.mods_watcher.set(None);
+                return;
+            }
+        };
+        if watcher.is_polling() {
+            eprintln!("Polling the mods folder for changes");
+        }
+
+        let was_watching = self.mods_watcher.with_untracked(Option::is_some);
+        self.mods_watcher.set(Some(watcher));
+        if !was_watching {
+            self.poll_mods_watcher();
+        }
+    }
+
+    /// Apply the changes the watcher has seen, checking again shortly while it is watching.
+    fn poll_mods_watcher(&self) {
+        let mut changes = None;
+        let mut watching = false;
+        self.mods_watcher.update(|watcher| {
+            if let Some(watcher) = watcher {
+                watching = true;
+                changes = watcher.poll();
+            }
+        });
+
+        if let Some(changes) = changes {
+            self.apply_watched_changes(&changes);
+        }
+
+        if watching {
+            let main_data = self.clone();
+            floem::action::exec_after(WATCH_POLL_INTERVAL, move |_| main_data.poll_mods_watcher());
+        }
+    }
+
+    /// Replace the mods whose paks were read again, and remove the ones whose paks are gone.
+    fn apply_pak_changes(&self, pak_changes: Vec<PakRescan>) {
+        let mut updated = Vec::new();
+        let mut removed = Vec::new();
+        for change in pak_changes {
+            match change {
+                PakRescan::Updated(pak_mod) => updated.push(*pak_mod),
+                PakRescan::Removed(pak) => removed.push(pak),
+                PakRescan::Failed { pak, error } => eprintln!("{pak:?}: {error}"),
+            }
+        }
+
+        let updated_uuids: Vec<String> = updated
+            .iter()
+            .map(|pak_mod| pak_mod.meta.data.uuid.clone())
+            .collect();
+
+        // Updates go first, so that a mod whose pak was renamed keeps its place rather than being
+        // removed and added again at the end
+        self.mods.update(|mods| {
+            for pak_mod in updated {
+                eprintln!("Reloaded {:?}", pak_mod.meta.data.file_path);
+                self.pak_files.update(|pak_files| {
+                    pak_files.remove(&pak_mod.meta.data.uuid);
+                });
+                replace_mod(mods, self.user_mod_data(pak_mod.meta, pak_mod.extender));
+            }
+
+            for pak in &removed {
+                let Some(idx) = mods.iter().position(|m| &m.data.file_path == pak) else {
+                    continue;
+                };
+                let uuid = mods[idx].data.uuid.clone();
+                if updated_uuids.iter().any(|u| u.eq_ignore_ascii_case(&uuid)) {
+                    continue;
                 }
+
+                eprintln!("{pak:?} was removed");
+                mods.remove(idx);
+                self.pak_files.update(|pak_files| {
+                    pak_files.remove(&uuid);
+                });
+                self.selection.update(|selection| selection.deselect(&uuid));
             }
+        });
+    }
+
+    /// Read the changed paks again, and apply the active profile's load order if the game
+    /// rewrote it.
+    fn apply_watched_changes(&self, changes: &WatchedChanges) {
+        // A full load will see the paks anyway
+        let loading = self.mod_loader.with_untracked(Option::is_some);
+        if !changes.paks.is_empty() && !loading {
+            self.apply_pak_changes(rescan_paks(&changes.paks));
+        }
 
-            todo!()
+        let profile = self.active_profile.get_untracked().and_then(|idx| {
+            self.profiles
+                .with_untracked(|profiles| profiles.get(idx).cloned())
+        });
+        let Some(profile) = profile else {
+            return;
+        };
+        if !changes.mod_settings.contains(&profile.mod_settings_path()) {
+            return;
         }
 
-        // The check at the end of the C# version of this function is not done here because we
-        // can't (nicely) synchronously open a file dialogue. We have a separate view that is shown
-        // on startup for that.
+        let file_order = match profile.read_load_order() {
+            Ok(order) => order,
+            Err(err) => {
+                eprintln!(
+                    "Failed to read load order of profile {}: {err}",
+                    profile.name
+                );
+                return;
+            }
+        };
+
+        eprintln!("The load order of profile {} changed", profile.title());
+        self.change_order(|order| {
+            let mut listed: Vec<String> = order.active.drain(..).collect();
+            listed.append(&mut order.inactive);
+
+            for entry in &file_order {
+                let idx = listed
+                    .iter()
+                    .position(|uuid| uuid.eq_ignore_ascii_case(&entry.uuid));
+                if let Some(idx) = idx {
+                    order.active.push(listed.remove(idx));
+                }
+            }
+            order.inactive = listed;
+        });
+    }
+}
 
-        pathway
+/// Put the mod in place of the one with the same uuid, keeping its position and whether it is
+/// active, or add it to the end if it is new.
+fn replace_mod(mods: &mut im::Vector<UIModData>, mut mod_data: UIModData) {
+    let existing_idx = mods
+        .iter()
+        .position(|m| m.data.uuid.eq_ignore_ascii_case(&mod_data.data.uuid));
+    match existing_idx {
+        Some(idx) => {
+            mod_data.is_active = mods[idx].is_active;
+            mods.set(idx, mod_data);
+        }
+        None => mods.push_back(mod_data),
     }
 }
 
@@ -822,14 +1673,3 @@ impl GameDataInfo {
         }
     }
 }
-
-/// Headers/footers
-pub const DARK0_BG: Color = Color::rgb8(42, 43, 52);
-/// Inputs
-pub const DARK1_BG: Color = Color::rgb8(51, 51, 64);
-/// Main background
-pub const DARK2_BG: Color = Color::rgb8(61, 61, 76);
-/// Selected option background
-pub const DARK3_BG: Color = Color::rgb8(76, 79, 98);
-/// Text in the dark theme
-pub const DARK_TEXT: Color = Color::rgb8(209, 209, 212);
@@ -4,27 +4,38 @@ use std::path::PathBuf;
 
 use floem::{
     cosmic_text::Weight,
+    event::EventListener,
     glazier::FileDialogOptions,
-    id::WindowId,
-    peniko::Color,
     reactive::{create_effect, create_rw_signal, RwSignal},
+    responsive::ScreenSize,
     style::Style,
     view::View,
-    views::{container, container_box, label, stack, Decorators},
+    views::{container, container_box, empty, label, list, scroll, stack, Decorators},
     ViewContext,
 };
-use mod_mgr_lib::settings::Settings;
+use mod_mgr_lib::{
+    backups::Snapshot,
+    settings::Settings,
+    util::time::timestamp_name,
+    validate::{ValidationFix, ValidationIssue},
+};
 
 use crate::{
-    mod_table_view::{active_mods, inactive_mods},
-    settings_view::settings_view,
+    app_keys::handle_key,
+    details_view::details_panel,
+    mod_table_view::{active_mods, inactive_mods, override_mods},
+    settings_view::open_settings_window,
+    theme::theme,
     view_util::{
-        forward_icon, hard_disk_icon, save_as_icon, save_icon, settings_icon, simple_form_input,
-        svg_button,
+        button, forward_icon, hard_disk_icon, save_as_icon, save_icon, settings_icon,
+        simple_form_input, svg_button,
     },
-    GameDataInfo, MainData, DARK0_BG, DARK2_BG, DARK_TEXT,
+    GameDataInfo, MainData,
 };
 
+/// Window sizes too narrow to show the mod lists side by side
+const NARROW: ScreenSize = ScreenSize::XS.union(ScreenSize::SM).union(ScreenSize::MD);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StartupStage {
     Loading,
@@ -88,7 +99,7 @@ fn loading_view() -> impl View {
             .flex_col()
             .items_center()
             .justify_center()
-            .background(DARK0_BG)
+            .background(theme().header_bg)
     })
 }
 
@@ -99,6 +110,7 @@ fn ask_game_path_view(main_data: MainData) -> impl View {
     let game_data_info: RwSignal<Option<GameDataInfo>> = create_rw_signal(None);
     let settings = main_data.settings.clone();
     let pathway = main_data.pathway.clone();
+    let load_data = main_data.clone();
 
     // Whenever the game path changes, update game data info.
     create_effect(move |prev_game_path| {
@@ -122,14 +134,14 @@ fn ask_game_path_view(main_data: MainData) -> impl View {
                     Style::BASE
                         .font_size(20.0)
                         .font_weight(Weight::BOLD)
-                        .color(DARK_TEXT)
+                        .color(theme().text)
                 }),
             // Path input and file open button
             stack(|| {
                 (
                     simple_form_input("Path:", game_path, 300.0, 24.0)
-                        .style(|| Style::BASE.font_size(20.0).color(DARK_TEXT)),
-                    svg_button(hard_disk_icon(), move || {
+                        .style(|| Style::BASE.font_size(20.0).color(theme().text)),
+                    svg_button(hard_disk_icon, move || {
                         // TODO(minor): Set starting path to the current settings path / current guessed path?
                         let options = FileDialogOptions::new()
                             // just in case their steam folder is hidden, like it is commonly named
@@ -159,7 +171,7 @@ fn ask_game_path_view(main_data: MainData) -> impl View {
             }),
             // Move forward arrow
             container(move || {
-                svg_button(forward_icon(), move || {
+                svg_button(forward_icon, move || {
                     startup_stage.set(StartupStage::Ready);
 
                     if let Some(game_data_info) = game_data_info.get_untracked() {
@@ -188,6 +200,7 @@ fn ask_game_path_view(main_data: MainData) -> impl View {
                         eprintln!("User moved to next stage without providing game data path");
                     }
 
+                    load_data.load_mods();
                     true
                 })
             })
@@ -206,49 +219,340 @@ fn ask_game_path_view(main_data: MainData) -> impl View {
             .flex_col()
             .items_center()
             .justify_center()
-            .background(DARK0_BG)
+            .background(theme().header_bg)
             .border(1.0)
-            .border_color(Color::GREEN)
+            .border_color(theme().border)
     })
 }
 
 fn main_view(main_data: MainData) -> impl View {
-    let settings = main_data.settings.clone();
+    let mods = main_data.mods.clone();
+    // The mod that is being dragged, see `mod_table_view`
+    let drag = create_rw_signal(None);
+    let key_root_data = main_data.clone();
+    let backups_open = create_rw_signal(false);
 
     stack(move || {
+        let list_main_data = main_data.clone();
+        let key_main_data = main_data.clone();
         (
-            operation_bar(main_data.clone()),
+            operation_bar(main_data.clone(), backups_open),
+            backups_banner(main_data.clone(), backups_open),
+            loading_banner(main_data.clone()),
+            validation_banner(main_data.clone()),
             stack(move || {
                 (
-                    // TODO: Currently if we resize the window small enough then the inactive mods will intersect with the active mods. We should use the response feature or something like it to check for if the screen is small and then just put the inactive mods below.
-                    active_mods(settings.clone()).style(|| Style::BASE.width_pct(50.0)),
-                    inactive_mods(settings.clone())
-                        .style(|| Style::BASE.width_pct(50.0).items_end().justify_end()),
+                    active_mods(list_main_data.clone(), drag)
+                        .style(|| Style::BASE.width_pct(38.0))
+                        .responsive_style(NARROW, || Style::BASE.width_pct(100.0)),
+                    inactive_mods(list_main_data.clone(), drag)
+                        .style(|| Style::BASE.width_pct(38.0).items_end().justify_end())
+                        .responsive_style(NARROW, || Style::BASE.width_pct(100.0)),
+                    details_panel(list_main_data)
+                        .style(|| Style::BASE.width_pct(24.0))
+                        .responsive_style(NARROW, || Style::BASE.width_pct(100.0)),
                 )
             })
-            .style(|| Style::BASE.width_pct(100.0).margin_top_pct(1.0).flex_row()),
+            .keyboard_navigatable()
+            .on_event(EventListener::KeyDown, move |event| {
+                handle_key(&key_main_data, event)
+            })
+            .style(|| Style::BASE.width_pct(100.0).margin_top_pct(1.0).flex_row())
+            // The lists would overlap side by side, so put them below each other instead
+            .responsive_style(NARROW, || Style::BASE.flex_col()),
+            override_mods(mods),
         )
     })
+    // Dropped somewhere other than the lists
+    .on_event(EventListener::PointerUp, move |_| {
+        drag.set(None);
+        false
+    })
+    .keyboard_navigatable()
+    .on_event(EventListener::KeyDown, move |event| {
+        handle_key(&key_root_data, event)
+    })
     .base_style(|| {
         Style::BASE
             .size_pct(100.0, 100.0)
             .flex_col()
             .items_start()
-            .background(DARK0_BG)
+            .background(theme().header_bg)
+    })
+}
+
+/// Problems with the load order, each with a button to fix it if we know how. Rechecked whenever
+/// the mods change.
+fn validation_banner(main_data: MainData) -> impl View {
+    let issues = create_rw_signal(Vec::new());
+    let effect_data = main_data.clone();
+    create_effect(move |_| issues.set(effect_data.validate_order()));
+
+    stack(move || {
+        (
+            label(move || match issues.with(Vec::len) {
+                1 => "1 problem with the load order".to_string(),
+                count => format!("{count} problems with the load order"),
+            })
+            .style(|| Style::BASE.font_weight(Weight::BOLD).margin_bottom_px(4.0)),
+            list(
+                move || issues.get().into_iter().enumerate(),
+                |(idx, issue)| (*idx, issue.to_string()),
+                move |(_, issue)| validation_issue(main_data.clone(), issue),
+            )
+            .style(|| Style::BASE.flex_col()),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_col()
+            .width_pct(100.0)
+            .padding_px(6.0)
+            .font_size(theme().font_size)
+            .background(theme().input_bg)
+            .color(theme().warning_text)
+            .apply_if(issues.with(Vec::is_empty), |s| s.hide())
+    })
+}
+
+fn validation_issue(main_data: MainData, issue: ValidationIssue) -> impl View {
+    let text = issue.to_string();
+    let fix = issue.fix();
+    let fix_title = fix.as_ref().map(ValidationFix::title).unwrap_or_default();
+    let has_fix = fix.is_some();
+
+    stack(move || {
+        (
+            label(move || text.clone()).style(|| Style::BASE.margin_right_px(8.0)),
+            button(fix_title, move || {
+                if let Some(fix) = &fix {
+                    main_data.apply_fix(fix);
+                }
+                true
+            })
+            .style(move || Style::BASE.apply_if(!has_fix, |s| s.hide())),
+        )
     })
+    .style(|| Style::BASE.flex_row().items_center().margin_bottom_px(2.0))
+}
+
+/// The progress of loading the mods, and the paks that couldn't be read.
+fn loading_banner(main_data: MainData) -> impl View {
+    let progress = main_data.load_progress;
+    let errors = main_data.load_errors;
+    let is_loading = move || progress.with(Option::is_some);
+    let cancel_data = main_data.clone();
+
+    stack(move || {
+        (
+            stack(move || {
+                (
+                    label(move || {
+                        progress.with(|progress| match progress {
+                            Some(progress) if progress.total > 0 => format!(
+                                "{} ({}/{})",
+                                progress.message, progress.done, progress.total
+                            ),
+                            Some(progress) => progress.message.to_string(),
+                            None => match errors.with(im::Vector::len) {
+                                1 => "1 problem while loading the mods".to_string(),
+                                count => format!("{count} problems while loading the mods"),
+                            },
+                        })
+                    })
+                    .style(|| Style::BASE.font_weight(Weight::BOLD).margin_right_px(8.0)),
+                    button("Cancel", move || {
+                        cancel_data.cancel_loading();
+                        true
+                    })
+                    .style(move || Style::BASE.apply_if(!is_loading(), |s| s.hide())),
+                    button("Dismiss", move || {
+                        errors.set(im::Vector::new());
+                        true
+                    })
+                    .style(move || Style::BASE.apply_if(is_loading(), |s| s.hide())),
+                )
+            })
+            .style(|| Style::BASE.flex_row().items_center().margin_bottom_px(4.0)),
+            // Progress bar
+            container(|| {
+                empty().style(move || {
+                    let fraction = progress.with(|p| p.as_ref().map_or(0.0, |p| p.fraction()));
+                    Style::BASE
+                        .height_pct(100.0)
+                        .width_pct(fraction * 100.0)
+                        .background(theme().selected_bg)
+                })
+            })
+            .style(move || {
+                Style::BASE
+                    .width_pct(100.0)
+                    .height_px(4.0)
+                    .margin_bottom_px(4.0)
+                    .background(theme().main_bg)
+                    .apply_if(!is_loading(), |s| s.hide())
+            }),
+            list(
+                move || errors.get().into_iter().enumerate(),
+                |(idx, error)| (*idx, error.clone()),
+                |(_, error)| {
+                    label(move || error.clone()).style(|| Style::BASE.color(theme().warning_text))
+                },
+            )
+            .style(|| Style::BASE.flex_col()),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_col()
+            .width_pct(100.0)
+            .padding_px(6.0)
+            .font_size(theme().font_size)
+            .background(theme().input_bg)
+            .color(theme().text)
+            .apply_if(!is_loading() && errors.with(im::Vector::is_empty), |s| {
+                s.hide()
+            })
+    })
+}
+
+/// The backed up load orders, each with a button to restore it. Listed again whenever it is opened.
+fn backups_banner(main_data: MainData, open: RwSignal<bool>) -> impl View {
+    let snapshots = create_rw_signal(Vec::new());
+    let effect_data = main_data.clone();
+    create_effect(move |_| {
+        if open.get() {
+            snapshots.set(effect_data.list_backups());
+        }
+    });
+
+    stack(move || {
+        (
+            stack(move || {
+                (
+                    label(move || match snapshots.with(Vec::len) {
+                        0 => "No backups".to_string(),
+                        1 => "1 backup".to_string(),
+                        count => format!("{count} backups"),
+                    })
+                    .style(|| Style::BASE.font_weight(Weight::BOLD).margin_right_px(8.0)),
+                    button("Close", move || {
+                        open.set(false);
+                        true
+                    }),
+                )
+            })
+            .style(|| Style::BASE.flex_row().items_center().margin_bottom_px(4.0)),
+            scroll(move || {
+                list(
+                    move || snapshots.get().into_iter(),
+                    |snapshot: &Snapshot| snapshot.id.clone(),
+                    move |snapshot| backup_row(main_data.clone(), snapshots, snapshot),
+                )
+                .style(|| Style::BASE.flex_col())
+            })
+            .style(|| Style::BASE.max_height_px(160.0)),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_col()
+            .width_pct(100.0)
+            .padding_px(6.0)
+            .font_size(theme().font_size)
+            .background(theme().input_bg)
+            .color(theme().text)
+            .apply_if(!open.get(), |s| s.hide())
+    })
+}
+
+fn backup_row(
+    main_data: MainData,
+    snapshots: RwSignal<Vec<Snapshot>>,
+    snapshot: Snapshot,
+) -> impl View {
+    let text = format!(
+        "{} of {}, {} paks in the mods folder",
+        timestamp_name(snapshot.info.created),
+        snapshot.info.profile_name,
+        snapshot.info.paks.len()
+    );
+    let id = snapshot.id;
+
+    stack(move || {
+        (
+            label(move || text.clone()).style(|| Style::BASE.margin_right_px(8.0)),
+            button("Restore", move || {
+                main_data.restore_backup(&id);
+                // Restoring backs up the load order it replaced
+                snapshots.set(main_data.list_backups());
+                true
+            }),
+        )
+    })
+    .style(|| Style::BASE.flex_row().items_center().margin_bottom_px(2.0))
+}
+
+/// The player profiles, with the active one highlighted. Clicking one switches to its load order.
+// TODO: this should be a dropdown once floem has one
+fn profile_selector(main_data: MainData) -> impl View {
+    let profiles = main_data.profiles;
+    let active_profile = main_data.active_profile;
+    stack(move || {
+        (
+            label(|| "Profile:".to_string())
+                .style(|| Style::BASE.margin_right_px(4.0).color(theme().text)),
+            list(
+                move || profiles.get().into_iter().enumerate().collect::<Vec<_>>(),
+                |(_, profile)| profile.folder.clone(),
+                move |(idx, profile)| {
+                    let main_data = main_data.clone();
+                    let title = profile.title().to_string();
+                    label(move || title.clone())
+                        .on_click(move |_| {
+                            main_data.select_profile(idx);
+                            true
+                        })
+                        .style(move || {
+                            let is_active = active_profile.get() == Some(idx);
+                            Style::BASE
+                                .padding_horiz_px(6.0)
+                                .color(theme().text)
+                                .apply_if(is_active, |s| s.font_weight(Weight::BOLD))
+                        })
+                },
+            )
+            .style(|| Style::BASE.flex_row()),
+        )
+    })
+    .style(|| Style::BASE.flex_row().items_center().margin_right_px(8.0))
 }
 
 /// Top bar with 'profile' and such
-fn operation_bar(main_data: MainData) -> impl View {
+fn operation_bar(main_data: MainData, backups_open: RwSignal<bool>) -> impl View {
+    let install_data = main_data.clone();
+    let profile_data = main_data.clone();
+    let save_data = main_data.clone();
+    let diagnostics_data = main_data.clone();
     stack(move || {
-        // TODO: profile dropdown
         // TODO: mod order dropdown
         (
             // left side
             stack(|| {
                 (
-                    svg_button(save_icon(), || todo!()).style(|| Style::BASE.flex_row()),
-                    svg_button(save_as_icon(), || todo!()).style(|| Style::BASE.flex_row()),
+                    profile_selector(profile_data),
+                    svg_button(save_icon, move || {
+                        save_data.save_load_order();
+                        true
+                    })
+                    .style(|| Style::BASE.flex_row()),
+                    svg_button(save_as_icon, || todo!()).style(|| Style::BASE.flex_row()),
+                    // TODO: drag and drop onto the window, once floem supports dropped files
+                    button("Install Mod", move || {
+                        install_data.choose_mod_to_install();
+                        true
+                    })
+                    .style(|| Style::BASE.margin_left_px(8.0)),
                 )
             })
             .style(|| {
@@ -259,18 +563,19 @@ fn operation_bar(main_data: MainData) -> impl View {
             }),
             stack(|| {
                 (
+                    button("Backups", move || {
+                        backups_open.update(|open| *open = !*open);
+                        true
+                    })
+                    .style(|| Style::BASE.margin_right_px(8.0)),
+                    button("Diagnostics", move || {
+                        diagnostics_data.export_diagnostics();
+                        true
+                    })
+                    .style(|| Style::BASE.margin_right_px(8.0)),
                     // Settings button. Opens settings window.
-                    svg_button(settings_icon(), move || {
-                        // TODO: should we pause actions in the main display while settings is open?
-                        // TODO: if the main window is closed, close the settings window
-                        // TODO: should be floating window
-                        let main_data = main_data.clone();
-                        floem::new_window(
-                            WindowId::next(),
-                            move || settings_view(main_data.clone()),
-                            None,
-                        );
-
+                    svg_button(settings_icon, move || {
+                        open_settings_window(main_data.clone());
                         true
                     }),
                 )
@@ -289,8 +594,8 @@ fn operation_bar(main_data: MainData) -> impl View {
             .size_pct(100.0, 5.0)
             .min_height_px(32.0)
             .flex_row()
-            .background(DARK0_BG)
+            .background(theme().header_bg)
             .border_bottom(0.8)
-            .border_color(Color::SILVER)
+            .border_color(theme().border)
     })
 }
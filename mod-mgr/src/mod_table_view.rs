@@ -1,14 +1,32 @@
-// TODO: light theme, it would basically just be a matter of threading the style info around
+use std::rc::Rc;
 
 use floem::{
-    reactive::{create_rw_signal, RwSignal},
+    event::{Event, EventListener},
+    reactive::{create_effect, create_rw_signal, RwSignal},
     style::Style,
     view::View,
-    views::{container, empty, label, stack, Decorators},
+    views::{container, label, list, stack, Decorators},
+};
+use mod_mgr_lib::{
+    load_order::ModList,
+    mod_filter::{FilterField, ModFilter},
+    mod_updates::{ModUpdate, UpdateKind},
+    pager::Pager,
+    util::time::date_string,
+};
+
+use crate::{
+    table::table,
+    theme::theme,
+    ui_mod_data::UIModData,
+    view_util::{button, form_item, form_text_input, highlighted_text},
+    MainData,
 };
-use mod_mgr_lib::settings::Settings;
 
-use crate::{table::table, view_util::simple_form_input, DARK0_BG, DARK_TEXT};
+/// Height of the rows in handheld mode, big enough to tap
+const HANDHELD_ROW_HEIGHT: f32 = 40.0;
+/// Rows shown at a time in handheld mode. This fits the Steam Deck's screen.
+const HANDHELD_ROWS_PER_PAGE: usize = 10;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum ModTableEntry {
@@ -43,84 +61,286 @@ const ACTIVE_MOD_TABLE_ENTRIES: [ModTableEntry; 6] = [
     ModTableEntry::Blank,
 ];
 
-#[derive(Debug, Clone)]
-struct ModRow {
-    pub name: String,
-    pub version: String,
-    pub author: String,
-    pub last_updated: String,
+/// The mods shown in one of the tables, paired with their load order index.  
+/// The index is taken before filtering by the search, so it stays the mod's position in the load
+/// order.
+fn filtered_mods(
+    mods: RwSignal<im::Vector<UIModData>>,
+    search: RwSignal<String>,
+    list: ModList,
+) -> impl Fn() -> Vec<(usize, UIModData)> {
+    let active = list == ModList::Active;
+    move || {
+        let filter = search.with(|search| ModFilter::parse(search));
+        mods.with(|mods| {
+            mods.iter()
+                .filter(|m| m.is_active == active && m.is_listed())
+                .enumerate()
+                .filter(|(_, m)| filter.matches(&m.data, m.extender_data.requires_extender()))
+                .map(|(idx, m)| (idx, m.clone()))
+                .collect()
+        })
+    }
 }
-impl ModRow {
-    fn value(&self, idx: usize, entry: ModTableEntry) -> String {
-        match entry {
-            ModTableEntry::Index => idx.to_string(),
-            ModTableEntry::Name => self.name.clone(),
-            ModTableEntry::Version => self.version.clone(),
-            ModTableEntry::Author => self.author.clone(),
-            ModTableEntry::LastUpdated => self.last_updated.clone(),
-            ModTableEntry::Blank => String::new(),
+
+/// Selecting and dragging the mods in a table.  
+/// `drag` is the mod the pointer was pressed on, shared between the tables so that mods can be
+/// dragged from one to the other.
+fn mod_row_event(
+    main_data: &MainData,
+    drag: RwSignal<Option<String>>,
+    touch: RwSignal<Option<TouchDrag>>,
+    list: ModList,
+    m: &UIModData,
+    event: &Event,
+) -> bool {
+    let uuid = &m.data.uuid;
+    if main_data.is_handheld() {
+        // Dragging moves through the list instead, so a mod is only selected by tapping it
+        let Event::PointerUp(_) = event else {
+            return false;
+        };
+        let moved = touch
+            .get_untracked()
+            .is_some_and(|touch_drag| touch_drag.moved);
+        touch.set(None);
+        if !moved {
+            main_data.click_mod(uuid, false, false);
         }
+        return true;
+    }
+
+    match event {
+        Event::PointerDown(pointer) => {
+            let shift = pointer.modifiers.shift();
+            let ctrl = pointer.modifiers.ctrl() || pointer.modifiers.meta();
+            // Pressing a selected mod keeps the rest of the selection, so that they can all be
+            // dragged.
+            if shift || ctrl || !main_data.selection.with_untracked(|s| s.is_selected(uuid)) {
+                main_data.click_mod(uuid, shift, ctrl);
+            }
+            drag.set(Some(uuid.clone()));
+            // Not handled, so that the lists get keyboard focus
+            false
+        }
+        Event::PointerUp(pointer) => {
+            let Some(pressed) = drag.get_untracked() else {
+                return false;
+            };
+            drag.set(None);
+
+            if &pressed != uuid {
+                main_data.drop_selected(Some(uuid), list);
+            } else if !pointer.modifiers.shift()
+                && !pointer.modifiers.ctrl()
+                && !pointer.modifiers.meta()
+            {
+                // A click rather than a drag
+                main_data.click_mod(uuid, false, false);
+            }
+            true
+        }
+        _ => false,
     }
 }
 
-// TODO: searching
-pub fn active_mods(settings: RwSignal<Settings>) -> impl View {
-    let mods_filter = create_rw_signal(String::new());
-    // TODO: I think it optionally has more fields you can show
-    let rows = im::Vector::from_iter(
-        [
-            ModRow {
-                name: "DndRebalancing".to_string(),
-                version: "1.0.0.0".to_string(),
-                author: "Zerd".to_string(),
-                last_updated: "11/5/2020".to_string(),
-            },
-            ModRow {
-                name: "8 More Short Rests".to_string(),
-                version: "-1.15".to_string(),
-                author: "Logos".to_string(),
-                last_updated: "11/5/2020".to_string(),
-            },
-            ModRow {
-                name: "Customizer".to_string(),
-                version: "1.0.0.0".to_string(),
-                author: "AlanaSP".to_string(),
-                last_updated: "12/1/2020".to_string(),
-            },
-        ]
-        .into_iter()
-        .enumerate(),
-    );
-    stack(|| {
+/// A drag over the rows in handheld mode, which moves through the list rather than moving mods.
+#[derive(Debug, Clone, Copy)]
+struct TouchDrag {
+    start_y: f64,
+    start: Pager,
+    /// Whether it has moved far enough to scroll, so it isn't a tap on a mod
+    moved: bool,
+}
+
+/// A table of mods with a search box above it.
+/// In handheld mode the table is shown a page at a time, see [`Pager`].
+fn mod_table<const N: usize>(
+    title: &'static str,
+    entries: [ModTableEntry; N],
+    main_data: MainData,
+    drag: RwSignal<Option<String>>,
+    list: ModList,
+) -> impl View {
+    let search = create_rw_signal(String::new());
+    let mods = main_data.mods;
+    let selection = main_data.selection;
+    let focus_search = main_data.focus_search;
+    let settings = main_data.settings;
+    let updates = main_data.mod_updates;
+    let handheld = move || settings.with(|settings| settings.handheld_mode.is_enabled());
+    let row_main_data = main_data.clone();
+
+    let pager = create_rw_signal(Pager::new(HANDHELD_ROWS_PER_PAGE));
+    let touch: RwSignal<Option<TouchDrag>> = create_rw_signal(None);
+    let all_rows = Rc::new(filtered_mods(mods, search, list));
+    let rows = {
+        let all_rows = all_rows.clone();
+        move || {
+            let rows = all_rows();
+            if !handheld() {
+                return rows;
+            }
+            let visible = pager.with(|pager| pager.visible(rows.len()));
+            rows[visible].to_vec()
+        }
+    };
+    let row_count = move || all_rows().len();
+    let drag_row_count = row_count.clone();
+
+    // Go back to the start when the search changes, so the matches aren't on a later page
+    create_effect(move |_| {
+        search.with(|_| ());
+        pager.update(|pager| pager.offset = 0);
+    });
+
+    stack(move || {
         (
-            container(|| {
+            container(move || {
                 // TODO: background text
-                simple_form_input("Active Mods", mods_filter, 400.0, 14.0)
-                    .style(|| Style::BASE.font_size(12.0).flex_col())
+                form_item(title.to_string(), 400.0, move || {
+                    let input = form_text_input(search, 14.0);
+                    // Focused by the key binding for searching
+                    let input_id = input.id();
+                    create_effect(move |_| {
+                        if focus_search.get() == Some(list) {
+                            input_id.request_focus();
+                            focus_search.set(None);
+                        }
+                    });
+                    input
+                })
+                .style(|| Style::BASE.font_size(theme().font_size).flex_col())
             })
             .style(|| {
                 Style::BASE
                     .width_pct(100.0)
                     .flex_col()
                     .margin_bottom_px(10.0)
-                    .background(DARK0_BG)
-                    .color(DARK_TEXT)
+                    .background(theme().header_bg)
+                    .color(theme().text)
             }),
             table(
-                move || ACTIVE_MOD_TABLE_ENTRIES,
+                move || entries,
                 Clone::clone,
                 mod_table_text,
-                move || rows.clone(),
-                |(idx, _)| *idx,
-                mod_entry_text,
+                rows,
+                // Keyed by position too, so that the index column is rebuilt when mods move
+                |(idx, m)| (*idx, m.data.uuid.clone()),
+                move |entry, row| mod_entry_text(entry, row, mods, search, updates, handheld),
+                move |(_, m)| selection.with(|s| s.list == list && s.is_selected(&m.data.uuid)),
+                move |(_, m), event| mod_row_event(&row_main_data, drag, touch, list, m, event),
                 mod_table_entry_sizes,
             )
+            .on_event(EventListener::PointerDown, move |event| {
+                let Event::PointerDown(pointer) = event else {
+                    return false;
+                };
+                if !handheld() {
+                    return false;
+                }
+                touch.set(Some(TouchDrag {
+                    start_y: pointer.pos.y,
+                    start: pager.get_untracked(),
+                    moved: false,
+                }));
+                false
+            })
+            .on_event(EventListener::PointerMove, move |event| {
+                let (Event::PointerMove(pointer), Some(touch_drag)) =
+                    (event, touch.get_untracked())
+                else {
+                    return false;
+                };
+                let moved = Pager::dragged(
+                    touch_drag.start,
+                    pointer.pos.y - touch_drag.start_y,
+                    HANDHELD_ROW_HEIGHT as f64,
+                    drag_row_count(),
+                );
+                if moved != pager.get_untracked() {
+                    pager.set(moved);
+                    touch.set(Some(TouchDrag {
+                        moved: true,
+                        ..touch_drag
+                    }));
+                }
+                true
+            })
             .style(|| Style::BASE.width_pct(100.0)),
+            pager_bar(pager, row_count, handheld),
         )
     })
+    // Dropping mods below the rows puts them at the end of the list
+    .on_event(EventListener::PointerUp, move |_| {
+        touch.set(None);
+        if drag.get_untracked().is_none() {
+            return false;
+        }
+        drag.set(None);
+        main_data.drop_selected(None, list);
+        true
+    })
     .base_style(|| Style::BASE.flex_col())
 }
 
+/// Buttons for moving between the pages of a table, shown in handheld mode.
+fn pager_bar(
+    pager: RwSignal<Pager>,
+    row_count: impl Fn() -> usize + Clone + 'static,
+    handheld: impl Fn() -> bool + 'static,
+) -> impl View {
+    let previous_count = row_count.clone();
+    let next_count = row_count.clone();
+    let big = || {
+        Style::BASE
+            .min_height_px(HANDHELD_ROW_HEIGHT)
+            .font_size(theme().large_font_size + 4.0)
+    };
+    stack(move || {
+        (
+            button("Previous", move || {
+                pager.update(|pager| pager.previous_page(previous_count()));
+                true
+            })
+            .style(big),
+            label(move || pager.with(|pager| pager.page_text(row_count()))).style(|| {
+                Style::BASE
+                    .margin_left_px(12.0)
+                    .margin_right_px(12.0)
+                    .font_size(theme().large_font_size + 4.0)
+            }),
+            button("Next", move || {
+                pager.update(|pager| pager.next_page(next_count()));
+                true
+            })
+            .style(big),
+        )
+    })
+    .style(move || {
+        Style::BASE
+            .flex_row()
+            .items_center()
+            .margin_top_px(6.0)
+            .color(theme().text)
+            .apply_if(!handheld(), |s| s.hide())
+    })
+}
+
+// TODO: I think it optionally has more fields you can show
+/// The mods in the load order.  
+/// See [`ModFilter`] for the search syntax. `drag` is shared with the inactive mods, so that mods
+/// can be dragged between them.
+pub fn active_mods(main_data: MainData, drag: RwSignal<Option<String>>) -> impl View {
+    mod_table(
+        "Active Mods",
+        ACTIVE_MOD_TABLE_ENTRIES,
+        main_data,
+        drag,
+        ModList::Active,
+    )
+}
+
 const INACTIVE_MOD_TABLE_ENTRIES: [ModTableEntry; 5] = [
     ModTableEntry::Name,
     ModTableEntry::Version,
@@ -129,24 +349,146 @@ const INACTIVE_MOD_TABLE_ENTRIES: [ModTableEntry; 5] = [
     ModTableEntry::Blank,
 ];
 
-pub fn inactive_mods(settings: RwSignal<Settings>) -> impl View {
-    // TODO: searching
-    // table(
-    //     move || INACTIVE_MOD_TABLE_ENTRIES,
-    //     Clone::clone,
-    //     mod_table_text,
-    //     mod_table_entry_sizes,
-    // )
-    empty()
+/// The installed mods that aren't in the load order.
+pub fn inactive_mods(main_data: MainData, drag: RwSignal<Option<String>>) -> impl View {
+    mod_table(
+        "Inactive Mods",
+        INACTIVE_MOD_TABLE_ENTRIES,
+        main_data,
+        drag,
+        ModList::Inactive,
+    )
+}
+
+/// Mods that the game always loads, which can't be placed in the load order.
+pub fn override_mods(mods: RwSignal<im::Vector<UIModData>>) -> impl View {
+    stack(move || {
+        (
+            label(|| "Override Mods".to_string()).style(|| {
+                Style::BASE
+                    .color(theme().text)
+                    .font_size(theme().large_font_size)
+                    .margin_bottom_px(4.0)
+            }),
+            list(
+                move || {
+                    mods.get()
+                        .into_iter()
+                        .filter(UIModData::is_override_mod)
+                        .collect::<Vec<_>>()
+                },
+                |m| m.data.uuid.clone(),
+                |m| {
+                    let name = m.display_name();
+                    let override_text = m.data.builtin_override_mods_text;
+                    label(move || {
+                        if override_text.is_empty() {
+                            name.clone()
+                        } else {
+                            format!("{name} - {override_text}")
+                        }
+                    })
+                    .style(|| Style::BASE.color(theme().text).font_size(theme().font_size))
+                },
+            )
+            .style(|| Style::BASE.flex_col()),
+        )
+    })
+    .style(move || {
+        let has_override_mods = mods.with(|mods| mods.iter().any(UIModData::is_override_mod));
+        Style::BASE
+            .flex_col()
+            .width_pct(100.0)
+            .padding_horiz_px(6.0)
+            .padding_vert_px(6.0)
+            .apply_if(!has_override_mods, |s| s.hide())
+    })
 }
 
 fn mod_table_text(x: ModTableEntry) -> impl View {
-    label(move || x.title().to_string()).style(|| Style::BASE.color(DARK_TEXT).font_size(14.0))
+    label(move || x.title().to_string()).style(|| {
+        Style::BASE
+            .color(theme().text)
+            .font_size(theme().large_font_size)
+    })
+}
+
+/// The text of the mod's cell in the column
+fn mod_entry_value(x: ModTableEntry, idx: usize, m: &UIModData) -> String {
+    match x {
+        ModTableEntry::Index => idx.to_string(),
+        ModTableEntry::Name => m.display_name(),
+        ModTableEntry::Version => m.data.version.to_string(),
+        ModTableEntry::Author => m.data.author.clone(),
+        ModTableEntry::LastUpdated => m.data.last_modified.map(date_string).unwrap_or_default(),
+        ModTableEntry::Blank => String::new(),
+    }
 }
 
-fn mod_entry_text(x: &ModTableEntry, (idx, row): &(usize, ModRow)) -> impl View {
-    let row_value = row.value(*idx, *x);
-    label(move || row_value.clone()).style(|| Style::BASE.color(DARK_TEXT).font_size(14.0))
+/// A cell of the mod's row. The mod is looked up by uuid, so that the cell shows the mod's
+/// current data when it is reloaded.
+fn mod_entry_text(
+    x: &ModTableEntry,
+    (idx, m): &(usize, UIModData),
+    mods: RwSignal<im::Vector<UIModData>>,
+    search: RwSignal<String>,
+    updates: RwSignal<im::Vector<ModUpdate>>,
+    handheld: impl Fn() -> bool + 'static,
+) -> impl View {
+    let (x, idx) = (*x, *idx);
+    let uuid = m.data.uuid.clone();
+    let value_uuid = uuid.clone();
+    let value = move || {
+        mods.with(|mods| {
+            mods.iter()
+                .find(|m| m.data.uuid == value_uuid)
+                .map(|m| mod_entry_value(x, idx, m))
+                .unwrap_or_default()
+        })
+    };
+    // Shown after the version, so that the mods with updates stand out. The details panel says
+    // more about the update.
+    let update = move || {
+        updates.with(|updates| {
+            let update = updates
+                .iter()
+                .find(|update| update.uuid.eq_ignore_ascii_case(&uuid))?;
+            Some(match update.kind {
+                UpdateKind::NewerVersion => format!("→ {}", update.latest),
+                UpdateKind::PublishVersionChanged => "republished".to_string(),
+            })
+        })
+    };
+    let is_version = x == ModTableEntry::Version;
+    let field = match x {
+        ModTableEntry::Name => Some(FilterField::Name),
+        ModTableEntry::Author => Some(FilterField::Author),
+        _ => None,
+    };
+
+    let has_update = update.clone();
+    let text_fn = move || match update() {
+        Some(update) if is_version => format!("{} {update}", value()),
+        _ => value(),
+    };
+    highlighted_text(text_fn, move |text| match field {
+        Some(field) => search.with(|search| ModFilter::parse(search).highlights(field, text)),
+        None => Vec::new(),
+    })
+    .style(move || {
+        let handheld = handheld();
+        let has_update = is_version && has_update().is_some();
+        Style::BASE
+            .color(if has_update {
+                theme().warning_text
+            } else {
+                theme().text
+            })
+            .font_size(theme().large_font_size + if handheld { 4.0 } else { 0.0 })
+            .apply_if(handheld, |s| {
+                s.min_height_px(HANDHELD_ROW_HEIGHT).items_center()
+            })
+    })
 }
 
 fn mod_table_entry_sizes(x: &ModTableEntry) -> f32 {
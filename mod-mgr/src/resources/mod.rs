@@ -1,2 +1 @@
-pub mod default_paths;
 pub mod ignored_mods;
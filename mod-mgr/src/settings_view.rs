@@ -1,24 +1,60 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use floem::{
+    event::{Event, EventListener},
+    glazier::{FileDialogOptions, KbKey},
+    id::WindowId,
     reactive::{create_rw_signal, RwSignal},
     style::Style,
     view::View,
-    views::{container, container_box, label, scroll, stack, Decorators},
+    views::{container, container_box, label, list, scroll, stack, svg, Decorators},
+};
+use mod_mgr_lib::{
+    keybindings::{AppAction, KeyBindings},
+    settings::{
+        check_directory, check_game_data_path, check_game_executable_path, HandheldMode,
+        PathProblem, ScriptExtenderSettings, Settings, ThemeKind,
+    },
 };
-use mod_mgr_lib::settings::Settings;
 
 use crate::{
-    tab_view::{self, TabButtonStyle, TabSwitcherStyle},
-    view_util::{auto_checkbox, button, form, form_item, save_icon, simple_form_input, svg_button},
-    MainData, DARK0_BG, DARK2_BG, DARK3_BG, DARK_TEXT,
+    app_keys::key_combo,
+    tab_view,
+    theme::theme,
+    view_util::{
+        auto_checkbox, button, dyn_button, form, form_item, form_text_input, hard_disk_icon,
+        important_icon, ok_icon, save_icon, svg_button, with_tooltip,
+    },
+    MainData,
 };
 
+pub fn open_settings_window(main_data: MainData) {
+    // TODO: should we pause actions in the main display while settings is open?
+    // TODO: if the main window is closed, close the settings window
+    // TODO: should be floating window
+    floem::new_window(
+        WindowId::next(),
+        move || settings_view(main_data.clone()),
+        None,
+    );
+}
+
 fn save_config(
-    config: RwSignal<Settings>,
+    main_data: &MainData,
     general: GeneralSettingData,
     keyboard: KeyboardSettingData,
+    extender: ExtenderSettingData,
 ) {
+    if let Some(err) = general.path_error() {
+        eprintln!("Not saving the settings: {err}");
+        return;
+    }
+
+    let config = main_data.settings;
+    let was_polling = config.with_untracked(|config| config.poll_for_mod_changes);
     config.update(|config| {
         // TODO: can path buf conversion panic if it is bad?
         config.game_data_path = PathBuf::from(general.game_data_path.get_untracked());
@@ -34,14 +70,32 @@ fn save_config(
         config.launch_dx11 = general.enable_dx11_mode.get_untracked();
         config.skip_launcher = general.skip_launcher.get_untracked();
         config.check_for_updates = general.automatic_updates.get_untracked();
+        config.handheld_mode = general.handheld_mode.get_untracked();
+        config.theme = general.theme.get_untracked();
+        config.poll_for_mod_changes = general.poll_for_mod_changes.get_untracked();
         config.telemetry_disabled = general.telemetry_disabled.get_untracked();
-
-        // TODO: keyboard shortcuts
+        config.export_default_extender_settings = extender.export_defaults.get_untracked();
 
         if let Err(err) = config.save() {
             eprintln!("Failed to save the config: {:?}", err);
         }
     });
+
+    main_data.set_keybindings(keyboard.bindings.get_untracked());
+
+    let is_watching = main_data.mods_watcher.with_untracked(Option::is_some);
+    if is_watching && was_polling != general.poll_for_mod_changes.get_untracked() {
+        main_data.watch_mods();
+    }
+
+    if main_data.extender_settings.with_untracked(Option::is_some) {
+        main_data.extender_settings.update(|ext| {
+            if let Some(ext) = ext {
+                extender.apply(ext);
+            }
+        });
+        main_data.export_extender_settings();
+    }
 }
 
 // TODO: do we need to store the field stuff on this so that we can restore it when you swap around the tabs? We could also make structs of rw signals to avoid that bother
@@ -50,13 +104,15 @@ fn save_config(
 enum SettingTab {
     General = 0,
     KeyboardShortcuts = 1,
-    // Advanced = 2,
+    ScriptExtender = 2,
+    // Advanced = 3,
 }
 impl SettingTab {
     fn title(&self) -> String {
         match self {
             SettingTab::General => "General".to_string(),
             SettingTab::KeyboardShortcuts => "Keyboard Shortcuts".to_string(),
+            SettingTab::ScriptExtender => "Script Extender".to_string(),
             // SettingTab::Advanced => "Advanced".to_string(),
         }
     }
@@ -74,17 +130,35 @@ pub fn settings_view(main_data: MainData) -> impl View {
         [
             SettingTab::General,
             SettingTab::KeyboardShortcuts,
+            SettingTab::ScriptExtender,
             // SettingTab::Advanced,
         ]
         .into_iter(),
     );
     let active_tab = create_rw_signal(0);
     let general = config.with(GeneralSettingData::from_config);
-    let keyboard = config.with(KeyboardSettingData::from_config);
+    let keyboard = main_data
+        .keybindings
+        .with(|bindings| KeyboardSettingData::new(bindings.clone()));
+    let extender = config.with(|config| {
+        main_data
+            .extender_settings
+            .with(|ext| ExtenderSettingData::new(config, ext.as_ref()))
+    });
 
     stack(move || {
-        let header = settings_view_header(config, general.clone(), keyboard.clone());
-        let footer = settings_view_footer(config, general.clone(), keyboard.clone());
+        let header = settings_view_header(
+            main_data.clone(),
+            general.clone(),
+            keyboard.clone(),
+            extender.clone(),
+        );
+        let footer = settings_view_footer(
+            main_data.clone(),
+            general.clone(),
+            keyboard.clone(),
+            extender.clone(),
+        );
         (
             header,
             tab_view::horiz_tab_switcher(
@@ -98,28 +172,21 @@ pub fn settings_view(main_data: MainData) -> impl View {
                     }
                     SettingTab::KeyboardShortcuts => {
                         container_box(|| Box::new(keyboard_settings_view(keyboard.clone())))
+                    }
+                    SettingTab::ScriptExtender => {
+                        container_box(|| Box::new(extender_settings_view(extender.clone())))
+                            .style(|| Style::BASE.size_pct(100.0, 100.0))
                     } // SettingTab::Advanced => todo!(),
                 },
-                TabSwitcherStyle {
-                    separator_background: DARK3_BG,
-                    button_area_background: DARK0_BG,
-                    button: TabButtonStyle {
-                        background_color: DARK2_BG,
-                        active_background_color: DARK3_BG,
-                        color: DARK_TEXT,
-                        active_color: DARK_TEXT,
-                        border_color: DARK3_BG,
-                        ..Default::default()
-                    },
-                },
+                || theme().tab_switcher_style(),
             )
             .style(|| {
                 Style::BASE
                     .flex_col()
                     .border(1.0)
-                    .border_color(DARK3_BG)
-                    .background(DARK2_BG)
-                    .color(DARK_TEXT)
+                    .border_color(theme().border)
+                    .background(theme().main_bg)
+                    .color(theme().text)
                     .width_pct(100.0)
                     .height_pct(80.0)
                 // .max_height_pct(95.0)
@@ -135,15 +202,21 @@ pub fn settings_view(main_data: MainData) -> impl View {
 }
 
 fn settings_view_header(
-    config: RwSignal<Settings>,
+    main_data: MainData,
     general: GeneralSettingData,
     keyboard: KeyboardSettingData,
+    extender: ExtenderSettingData,
 ) -> impl View {
     stack(|| {
         // TODO: There's another button in the view. What does it do?
-        (svg_button(save_icon(), move || {
+        (svg_button(save_icon, move || {
             // TODO: show that it has been saving by flashing the button or showing a checkmark or something.
-            save_config(config, general.clone(), keyboard.clone());
+            save_config(
+                &main_data,
+                general.clone(),
+                keyboard.clone(),
+                extender.clone(),
+            );
 
             true
         }),)
@@ -154,32 +227,52 @@ fn settings_view_header(
             // .min_height_px(32.0)
             // .max_height_px(60.0)
             .width_pct(100.0)
-            .background(DARK0_BG)
+            .background(theme().header_bg)
             .items_start()
             .flex_col()
     })
 }
 
 fn settings_view_footer(
-    config: RwSignal<Settings>,
+    main_data: MainData,
     general: GeneralSettingData,
     keyboard: KeyboardSettingData,
+    extender: ExtenderSettingData,
 ) -> impl View {
-    container(|| {
-        // TODO: decent button view?
-        button("Save", move || {
-            save_config(config, general.clone(), keyboard.clone());
+    let save_error = {
+        let general = general.clone();
+        move || general.path_error().map(|err| format!("Can't save: {err}"))
+    };
+    let save_error_shown = save_error.clone();
+    stack(|| {
+        (
+            label(move || save_error_shown().unwrap_or_default()).style(move || {
+                Style::BASE
+                    .margin_right_pct(20.0)
+                    .margin_top_pct(1.0)
+                    .color(theme().warning_text)
+                    .apply_if(save_error().is_none(), |s| s.hide())
+            }),
+            // TODO: decent button view?
+            button("Save", move || {
+                save_config(
+                    &main_data,
+                    general.clone(),
+                    keyboard.clone(),
+                    extender.clone(),
+                );
 
-            true
-        })
-        .style(|| Style::BASE.margin_right_pct(20.0).margin_top_pct(2.0))
+                true
+            })
+            .style(|| Style::BASE.margin_right_pct(20.0).margin_top_pct(2.0)),
+        )
     })
     .style(|| {
         Style::BASE
             .size_pct(100.0, 10.0)
             // .min_height_px(40.0)
             // .max_height_px(60.0)
-            .background(DARK0_BG)
+            .background(theme().header_bg)
             .items_end()
             .flex_col()
     })
@@ -200,6 +293,9 @@ struct GeneralSettingData {
     skip_launcher: RwSignal<bool>,
     telemetry_disabled: RwSignal<bool>,
     automatic_updates: RwSignal<bool>,
+    handheld_mode: RwSignal<HandheldMode>,
+    theme: RwSignal<ThemeKind>,
+    poll_for_mod_changes: RwSignal<bool>,
 }
 impl GeneralSettingData {
     fn from_config(config: &Settings) -> GeneralSettingData {
@@ -218,59 +314,517 @@ impl GeneralSettingData {
             skip_launcher: create_rw_signal(config.skip_launcher),
             telemetry_disabled: create_rw_signal(config.telemetry_disabled),
             automatic_updates: create_rw_signal(config.check_for_updates),
+            handheld_mode: create_rw_signal(config.handheld_mode),
+            theme: create_rw_signal(config.theme),
+            poll_for_mod_changes: create_rw_signal(config.poll_for_mod_changes),
+        }
+    }
+
+    /// The label, name in the settings file, value and kind of each path setting
+    fn path_settings(&self) -> [(&'static str, &'static str, RwSignal<String>, PathKind); 3] {
+        [
+            (
+                "Game Data Path",
+                "GameDataPath",
+                self.game_data_path,
+                PathKind::GameData,
+            ),
+            (
+                "Game Executable Path",
+                "GameExecutablePath",
+                self.game_executable_path,
+                PathKind::Executable,
+            ),
+            (
+                "Saved Load Orders Path",
+                "SavedLoadOrdersPath",
+                self.saved_load_orders_path,
+                PathKind::Folder,
+            ),
+        ]
+    }
+
+    /// The first path that is invalid, which stops the settings from being saved.  
+    /// This tracks the paths, so a view using it is updated as they're typed.
+    fn path_error(&self) -> Option<String> {
+        self.path_settings()
+            .into_iter()
+            .find_map(|(text, _, signal, kind)| {
+                let problem = signal.with(|path| kind.problem(path))?;
+                kind.blocks_saving(problem)
+                    .then(|| format!("{text} {problem}"))
+            })
+    }
+}
+
+/// What a path setting should point at, for choosing and checking it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathKind {
+    /// The game's `Data` folder
+    GameData,
+    Executable,
+    /// A folder that is created when it is needed
+    Folder,
+}
+impl PathKind {
+    /// The problem with the path. Empty paths are unset, so they have none.
+    fn problem(self, path: &str) -> Option<PathProblem> {
+        if path.is_empty() {
+            return None;
         }
+
+        let path = Path::new(path);
+        let checked = match self {
+            PathKind::GameData => check_game_data_path(path),
+            PathKind::Executable => check_game_executable_path(path),
+            PathKind::Folder => check_directory(path),
+        };
+        checked.err()
+    }
+
+    fn blocks_saving(self, problem: PathProblem) -> bool {
+        !(self == PathKind::Folder && problem == PathProblem::Missing)
+    }
+
+    fn problem_text(self, problem: PathProblem) -> String {
+        if self.blocks_saving(problem) {
+            format!("This path {problem}")
+        } else {
+            "This folder will be created when it is needed".to_string()
+        }
+    }
+
+    /// Open a file dialog to choose the path
+    fn choose(self, signal: RwSignal<String>) {
+        let options = FileDialogOptions::new().show_hidden();
+        let options = match self {
+            PathKind::GameData | PathKind::Folder => options.select_directories(),
+            PathKind::Executable => options,
+        };
+
+        floem::action::open_file(options, move |file_info| {
+            if let Some(file_info) = file_info {
+                signal.set(file_info.path.to_string_lossy().to_string());
+            }
+        });
     }
 }
 
 fn general_settings_view(g: GeneralSettingData) -> impl View {
     const LABEL_WIDTH: f32 = 400.0;
-    fn chk(text: &str, signal: RwSignal<bool>) -> impl View {
-        form_item(text.to_string(), LABEL_WIDTH, move || auto_checkbox(signal))
+
+    /// `field` is the setting's name in the settings file, for its description
+    fn described<V: View + 'static>(
+        text: &str,
+        field: &'static str,
+        view_fn: impl Fn() -> V,
+    ) -> impl View {
+        let text = text.to_string();
+        with_tooltip(
+            Settings::description(field).unwrap_or_default(),
+            move || form_item(text, LABEL_WIDTH, view_fn),
+        )
     }
 
-    let inp = |x, y| simple_form_input(x, y, LABEL_WIDTH, 24.0);
+    fn chk(text: &str, field: &'static str, signal: RwSignal<bool>) -> impl View {
+        described(text, field, move || auto_checkbox(signal))
+    }
 
+    /// An input for a path, with a button to choose it and an icon for whether it is valid
+    fn path_input(
+        text: &str,
+        field: &'static str,
+        signal: RwSignal<String>,
+        kind: PathKind,
+    ) -> impl View {
+        let problem = move || signal.with(|path| kind.problem(path));
+        described(text, field, move || {
+            stack(move || {
+                (
+                    form_text_input(signal, 24.0),
+                    svg_button(hard_disk_icon, move || {
+                        kind.choose(signal);
+                        true
+                    }),
+                    svg(move || match problem() {
+                        Some(problem) if kind.blocks_saving(problem) => important_icon(),
+                        _ => ok_icon(),
+                    })
+                    .style(move || {
+                        Style::BASE
+                            .size_px(20.0, 20.0)
+                            .apply_if(signal.with(String::is_empty), |s| s.hide())
+                    }),
+                    label(move || {
+                        problem()
+                            .map(|problem| kind.problem_text(problem))
+                            .unwrap_or_default()
+                    })
+                    .style(move || {
+                        Style::BASE
+                            .margin_left_px(6.0)
+                            .color(theme().warning_text)
+                            .apply_if(problem().is_none(), |s| s.hide())
+                    }),
+                )
+            })
+            .style(|| Style::BASE.flex_row().items_center())
+        })
+    }
+
+    let path_settings = g.path_settings();
+
+    // Split into two forms, as a single one would have more items than a view tuple can hold
     container(|| {
         scroll(|| {
-            form(move || {
+            stack(move || {
                 (
-                    // TODO: let the user open a file dialog for quickly choosing a folder.
-                    // TODO: show an icon next to the path marking whether it actually exists
-                    // TODO: tooltips
-                    inp("Game Data Path", g.game_data_path),
-                    inp("Game Executable Path", g.game_executable_path),
-                    inp("Saved Load Orders Path", g.saved_load_orders_path),
-                    chk("Enable Story Log", g.enable_story_log),
-                    chk("Always Disable Telemetry", g.telemetry_disabled),
-                    chk(
-                        "Auto Add Missing Dependencies When Exporting",
-                        g.auto_add_missing_deps,
-                    ),
-                    chk("Enable Automatic Updates", g.automatic_updates),
-                    chk(
-                        "Disable Missing Mod Warnings",
-                        g.disable_missing_mod_warnings,
-                    ),
-                    chk("Shift Focus on Swap", g.shift_focus_on_swap),
-                    chk("Save Window Location", g.save_window_location),
-                    chk("Enable DirectX 11 Mode", g.enable_dx11_mode),
-                    chk("Skip Launcher", g.skip_launcher),
+                    form(move || {
+                        let [game_data, game_executable, saved_load_orders] =
+                            path_settings.map(|(text, field, signal, kind)| {
+                                path_input(text, field, signal, kind)
+                            });
+                        (
+                            game_data,
+                            game_executable,
+                            saved_load_orders,
+                            chk(
+                                "Poll for Mod Changes",
+                                "PollForModChanges",
+                                g.poll_for_mod_changes,
+                            ),
+                        )
+                    }),
+                    form(move || {
+                        (
+                            chk(
+                                "Enable Story Log",
+                                "GameStoryLogEnabled",
+                                g.enable_story_log,
+                            ),
+                            chk(
+                                "Always Disable Telemetry",
+                                "TelemetryDisabled",
+                                g.telemetry_disabled,
+                            ),
+                            chk(
+                                "Auto Add Missing Dependencies When Exporting",
+                                "AutoAddMissingDependenciesOnExport",
+                                g.auto_add_missing_deps,
+                            ),
+                            chk(
+                                "Enable Automatic Updates",
+                                "CheckForUpdates",
+                                g.automatic_updates,
+                            ),
+                            chk(
+                                "Disable Missing Mod Warnings",
+                                "DisableMissingModWarnings",
+                                g.disable_missing_mod_warnings,
+                            ),
+                            chk(
+                                "Shift Focus on Swap",
+                                "ShiftFocusOnSwap",
+                                g.shift_focus_on_swap,
+                            ),
+                            chk(
+                                "Save Window Location",
+                                "SaveWindowLocation",
+                                g.save_window_location,
+                            ),
+                            chk("Enable DirectX 11 Mode", "LaunchDX11", g.enable_dx11_mode),
+                            chk("Skip Launcher", "SkipLauncher", g.skip_launcher),
+                            described("Handheld Mode", "HandheldMode", move || {
+                                let mode = g.handheld_mode;
+                                dyn_button(
+                                    move || mode.get().title().to_string(),
+                                    move || {
+                                        mode.update(|mode| *mode = mode.next());
+                                        true
+                                    },
+                                )
+                            }),
+                            described("Theme", "Theme", move || {
+                                let theme = g.theme;
+                                dyn_button(
+                                    move || theme.get().title().to_string(),
+                                    move || {
+                                        theme.update(|theme| *theme = theme.next());
+                                        true
+                                    },
+                                )
+                            }),
+                        )
+                    }),
                 )
             })
+            .style(|| Style::BASE.flex_col().width_pct(100.0))
         })
         .style(|| Style::BASE.size_pct(100.0, 100.0))
     })
     .style(|| Style::BASE.size_pct(100.0, 100.0))
 }
 
+/// Data for the 'Keyboard Shortcuts' setting tab.
 #[derive(Debug, Clone)]
-struct KeyboardSettingData {}
+struct KeyboardSettingData {
+    bindings: RwSignal<KeyBindings>,
+    /// The action that the next key press will be bound to
+    capturing: RwSignal<Option<AppAction>>,
+}
 impl KeyboardSettingData {
-    fn from_config(config: &Settings) -> KeyboardSettingData {
-        KeyboardSettingData {}
+    fn new(bindings: KeyBindings) -> KeyboardSettingData {
+        KeyboardSettingData {
+            bindings: create_rw_signal(bindings),
+            capturing: create_rw_signal(None),
+        }
+    }
+
+    /// Bind the key that was pressed to the action that is waiting for one.
+    fn capture_key(&self, action: AppAction, event: &Event) -> bool {
+        if self.capturing.get_untracked() != Some(action) {
+            return false;
+        }
+        let Event::KeyDown(key) = event else {
+            return false;
+        };
+
+        if key.key == KbKey::Escape {
+            self.capturing.set(None);
+            return true;
+        }
+        // Such as a modifier on its own, so keep waiting for the rest of the keys
+        let Some(combo) = key_combo(key) else {
+            return true;
+        };
+
+        self.bindings.update(|bindings| bindings.add(action, combo));
+        self.capturing.set(None);
+        true
     }
 }
-fn keyboard_settings_view(g: KeyboardSettingData) -> impl View {
-    container(|| label(|| "TODO: Nothing here yet :)".to_string()))
-        .style(|| Style::BASE.padding_px(10.0).items_center().justify_center())
+
+fn keyboard_settings_view(k: KeyboardSettingData) -> impl View {
+    let bindings = k.bindings;
+    let capturing = k.capturing;
+    container(|| {
+        scroll(|| {
+            stack(move || {
+                (
+                    label(move || match capturing.get() {
+                        Some(action) => format!(
+                            "Press the keys for '{}', or Escape to cancel.",
+                            action.title()
+                        ),
+                        None => "Single keys, such as F2 or Page Up, are the easiest to map to a \
+                                 controller."
+                            .to_string(),
+                    })
+                    .style(|| Style::BASE.padding_px(10.0)),
+                    list(
+                        || AppAction::ALL,
+                        |action| *action,
+                        move |action| keybinding_row(k.clone(), action),
+                    )
+                    .style(|| {
+                        Style::BASE
+                            .flex_col()
+                            .padding_px(10.0)
+                            .font_size(theme().font_size)
+                    }),
+                    button("Reset All", move || {
+                        bindings.update(KeyBindings::reset_all);
+                        capturing.set(None);
+                        true
+                    })
+                    .style(|| Style::BASE.margin_left_px(10.0)),
+                )
+            })
+            .style(|| Style::BASE.flex_col())
+        })
+        .style(|| Style::BASE.size_pct(100.0, 100.0))
+    })
+    .style(|| Style::BASE.size_pct(100.0, 100.0))
+}
+
+/// The keys bound to the action, with buttons to change them.
+fn keybinding_row(k: KeyboardSettingData, action: AppAction) -> impl View {
+    const LABEL_WIDTH: f32 = 200.0;
+    let bindings = k.bindings;
+    let capturing = k.capturing;
+    form_item(action.title().to_string(), LABEL_WIDTH, move || {
+        stack(move || {
+            (
+                label(move || {
+                    if capturing.get() == Some(action) {
+                        return "Press a key...".to_string();
+                    }
+                    let combos = bindings.with(|bindings| bindings.bindings(action));
+                    if combos.is_empty() {
+                        "Unbound".to_string()
+                    } else {
+                        combos
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }
+                })
+                .style(|| Style::BASE.width_px(200.0)),
+                button("Add", move || {
+                    capturing.set(Some(action));
+                    true
+                }),
+                button("Clear", move || {
+                    bindings.update(|bindings| bindings.set(action, Vec::new()));
+                    true
+                })
+                .style(|| Style::BASE.margin_left_px(4.0)),
+                button("Default", move || {
+                    bindings.update(|bindings| bindings.reset(action));
+                    true
+                })
+                .style(move || {
+                    let overridden = bindings.with(|bindings| bindings.is_overridden(action));
+                    Style::BASE
+                        .margin_left_px(4.0)
+                        .apply_if(!overridden, |s| s.hide())
+                }),
+                label(move || conflict_text(bindings, action))
+                    .style(|| Style::BASE.margin_left_px(10.0).color(theme().warning_text)),
+            )
+        })
+        .style(|| Style::BASE.flex_row().items_center())
+    })
+    .keyboard_navigatable()
+    .on_event(EventListener::KeyDown, move |event| {
+        k.capture_key(action, event)
+    })
+}
+
+/// A description of the other actions that share keys with the action, if any.
+fn conflict_text(bindings: RwSignal<KeyBindings>, action: AppAction) -> String {
+    bindings
+        .with(KeyBindings::conflicts)
+        .into_iter()
+        .filter(|conflict| conflict.actions.contains(&action))
+        .map(|conflict| {
+            let others = conflict
+                .actions
+                .iter()
+                .filter(|other| **other != action)
+                .map(|other| other.title())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} is also bound to {others}", conflict.combo)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Data for the 'Script Extender' setting tab.  
+/// This is loaded from the extender's settings file in the game's `bin` folder.
+#[derive(Debug, Clone)]
+struct ExtenderSettingData {
+    /// Whether the settings file could be read. If not, we don't save over it.
+    loaded: bool,
+    export_defaults: RwSignal<bool>,
+    enable_extensions: RwSignal<bool>,
+    create_console: RwSignal<bool>,
+    enable_logging: RwSignal<bool>,
+    log_compile: RwSignal<bool>,
+    log_runtime: RwSignal<bool>,
+    log_failed_compile: RwSignal<bool>,
+    disable_mod_validation: RwSignal<bool>,
+    enable_achievements: RwSignal<bool>,
+    send_crash_reports: RwSignal<bool>,
+    enable_debugger: RwSignal<bool>,
+    enable_lua_debugger: RwSignal<bool>,
+    developer_mode: RwSignal<bool>,
+    default_to_client_console: RwSignal<bool>,
+    show_perf_warnings: RwSignal<bool>,
+}
+impl ExtenderSettingData {
+    fn new(config: &Settings, ext: Option<&ScriptExtenderSettings>) -> ExtenderSettingData {
+        let loaded = ext.is_some();
+        let default = ScriptExtenderSettings::default();
+        let ext = ext.unwrap_or(&default);
+        ExtenderSettingData {
+            loaded,
+            export_defaults: create_rw_signal(config.export_default_extender_settings),
+            enable_extensions: create_rw_signal(ext.enable_extensions),
+            create_console: create_rw_signal(ext.create_console),
+            enable_logging: create_rw_signal(ext.enable_logging),
+            log_compile: create_rw_signal(ext.log_compile),
+            log_runtime: create_rw_signal(ext.log_runtime),
+            log_failed_compile: create_rw_signal(ext.log_failed_compile),
+            disable_mod_validation: create_rw_signal(ext.disable_mod_validation),
+            enable_achievements: create_rw_signal(ext.enable_achievements),
+            send_crash_reports: create_rw_signal(ext.send_crash_reports),
+            enable_debugger: create_rw_signal(ext.enable_debugger),
+            enable_lua_debugger: create_rw_signal(ext.enable_lua_debugger),
+            developer_mode: create_rw_signal(ext.developer_mode),
+            default_to_client_console: create_rw_signal(ext.default_to_client_console),
+            show_perf_warnings: create_rw_signal(ext.show_perf_warnings),
+        }
+    }
+
+    fn apply(&self, ext: &mut ScriptExtenderSettings) {
+        ext.enable_extensions = self.enable_extensions.get_untracked();
+        ext.create_console = self.create_console.get_untracked();
+        ext.enable_logging = self.enable_logging.get_untracked();
+        ext.log_compile = self.log_compile.get_untracked();
+        ext.log_runtime = self.log_runtime.get_untracked();
+        ext.log_failed_compile = self.log_failed_compile.get_untracked();
+        ext.disable_mod_validation = self.disable_mod_validation.get_untracked();
+        ext.enable_achievements = self.enable_achievements.get_untracked();
+        ext.send_crash_reports = self.send_crash_reports.get_untracked();
+        ext.enable_debugger = self.enable_debugger.get_untracked();
+        ext.enable_lua_debugger = self.enable_lua_debugger.get_untracked();
+        ext.developer_mode = self.developer_mode.get_untracked();
+        ext.default_to_client_console = self.default_to_client_console.get_untracked();
+        ext.show_perf_warnings = self.show_perf_warnings.get_untracked();
+    }
+}
+
+fn extender_settings_view(e: ExtenderSettingData) -> impl View {
+    const LABEL_WIDTH: f32 = 400.0;
+    fn chk(text: &str, signal: RwSignal<bool>) -> impl View {
+        form_item(text.to_string(), LABEL_WIDTH, move || auto_checkbox(signal))
+    }
+
+    let loaded = e.loaded;
+    container(|| {
+        scroll(|| {
+            stack(move || {
+                (
+                    label(|| {
+                        "Could not read the Script Extender settings, see the log for details."
+                            .to_string()
+                    })
+                    .style(move || Style::BASE.padding_px(10.0).apply_if(loaded, |s| s.hide())),
+                    // TODO: the remaining settings, such as the debugger port and log directory
+                    form(move || {
+                        (
+                            chk("Export Default Values", e.export_defaults),
+                            chk("Enable Extensions", e.enable_extensions),
+                            chk("Create Console", e.create_console),
+                            chk("Enable Logging", e.enable_logging),
+                            chk("Log Failed Compile", e.log_failed_compile),
+                            chk("Log Runtime", e.log_runtime),
+                            chk("Disable Mod Validation", e.disable_mod_validation),
+                            chk("Enable Achievements", e.enable_achievements),
+                            chk("Send Crash Reports", e.send_crash_reports),
+                            chk("Enable Debugger", e.enable_debugger),
+                            chk("Enable Lua Debugger", e.enable_lua_debugger),
+                            chk("Developer Mode", e.developer_mode),
+                        )
+                    }),
+                )
+            })
+            .style(|| Style::BASE.flex_col())
+        })
+        .style(|| Style::BASE.size_pct(100.0, 100.0))
+    })
+    .style(|| Style::BASE.size_pct(100.0, 100.0))
 }
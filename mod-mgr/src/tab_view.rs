@@ -45,6 +45,7 @@ pub struct TabButtonStyle {
     pub border_width: f32,
     pub border_width_focus_increase: f32,
     pub border_color: Color,
+    pub focus_border_color: Color,
 
     pub background_color: Color,
     pub active_background_color: Color,
@@ -60,6 +61,7 @@ impl Default for TabButtonStyle {
             border_width: 0.8,
             border_width_focus_increase: 0.2,
             border_color: Color::LIGHT_GRAY,
+            focus_border_color: Color::BLUE,
             background_color: Color::WHITE,
             active_background_color: Color::LIGHT_GRAY,
             active_padding_top_increase: 0.2,
@@ -72,11 +74,7 @@ pub fn horiz_tab_switcher<T, IF, I, KF, K, VF, V>(
     each_fn: IF,
     key_fn: KF,
     view_fn: VF,
-    TabSwitcherStyle {
-        separator_background,
-        button_area_background,
-        button: button_style,
-    }: TabSwitcherStyle,
+    style_fn: impl Fn() -> TabSwitcherStyle + Copy + 'static,
 ) -> impl View
 where
     T: 'static + ToString + PartialEq,
@@ -105,22 +103,24 @@ where
                         let x_string = x.to_string();
                         let idx = each_fn().into_iter().position(|y| x == y).unwrap_or(0);
                         let is_active = move || active.get() == idx;
-                        tab_button(x_string, is_active, button_style).on_click(move |_| {
-                            active.update(move |v| {
-                                *v = idx;
-                            });
+                        tab_button(x_string, is_active, move || style_fn().button).on_click(
+                            move |_| {
+                                active.update(move |v| {
+                                    *v = idx;
+                                });
 
-                            true
-                        })
+                                true
+                            },
+                        )
                     },
                 )
             })
-            .style(move || Style::BASE.background(button_area_background)),
+            .style(move || Style::BASE.background(style_fn().button_area_background)),
             container(|| empty()).style(move || {
                 Style::BASE
                     .width_pct(100.0)
                     .height_px(3.0)
-                    .background(separator_background)
+                    .background(style_fn().separator_background)
             }),
             tab(
                 move || active.get(),
@@ -136,22 +136,18 @@ where
 fn tab_button(
     text: String,
     is_active: impl Fn() -> bool + 'static + Copy,
-    TabButtonStyle {
-        font_size,
-        color,
-        active_color,
-        border_width,
-        border_width_focus_increase,
-        border_color,
-        background_color,
-        active_background_color,
-        active_padding_top_increase,
-    }: TabButtonStyle,
+    style_fn: impl Fn() -> TabButtonStyle + 'static + Copy,
 ) -> impl View {
     // TODO: different background color for if you're hovering over it? Slight shift in border?
     // TODO: slightly curved outwards border at bottom somehow
     container(move || {
         label(move || text.clone()).style(move || {
+            let TabButtonStyle {
+                font_size,
+                color,
+                active_color,
+                ..
+            } = style_fn();
             Style::BASE
                 .font_size(font_size)
                 .padding_horiz_px(10.0)
@@ -163,6 +159,15 @@ fn tab_button(
     .keyboard_navigatable()
     .style(move || {
         // TODO: intended padding logic doesn't work
+        let TabButtonStyle {
+            font_size,
+            border_width,
+            border_color,
+            background_color,
+            active_background_color,
+            active_padding_top_increase,
+            ..
+        } = style_fn();
         Style::BASE
             .border(border_width)
             .border_color(border_color)
@@ -176,9 +181,15 @@ fn tab_button(
             })
     })
     .focus_visible_style(move || {
+        let TabButtonStyle {
+            border_width,
+            border_width_focus_increase,
+            focus_border_color,
+            ..
+        } = style_fn();
         Style::BASE
             .border(border_width + border_width_focus_increase)
-            .border_color(Color::BLUE)
+            .border_color(focus_border_color)
             .flex_row()
     })
     .hover_style(|| {
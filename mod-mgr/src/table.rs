@@ -1,13 +1,13 @@
-use std::{hash::Hash, sync::Arc};
+use std::{hash::Hash, rc::Rc, sync::Arc};
 
 use floem::{
-    peniko::Color,
+    event::{Event, EventListener},
     style::Style,
     view::View,
     views::{container, list, stack, Decorators},
 };
 
-use crate::{DARK1_BG, DARK2_BG, DARK3_BG};
+use crate::theme::theme;
 
 // TODO: style structure
 // TODO: let widths be percentages
@@ -19,14 +19,37 @@ use crate::{DARK1_BG, DARK2_BG, DARK3_BG};
 /// `header_key_fn`: A way of identifying each entry. This may just be the entry itself.  
 /// `header_view_fn`: The actual view that should be displayed. Typically just a label.  
 ///
+/// `row_selected_fn`: Whether the row is selected, which highlights it.  
+/// `row_event_fn`: Called for pointer presses and releases on a row, returning whether the event
+/// was handled.  
 /// `widths_fn`: Maps a key to the width of the table column
-pub fn table<T, HF, H, WF, KHF, KH, VHF, VH, ROWSF, ROWS, U, ROWKF, ROWK, ROWVF, ROWV>(
+pub fn table<
+    T,
+    HF,
+    H,
+    WF,
+    KHF,
+    KH,
+    VHF,
+    VH,
+    ROWSF,
+    ROWS,
+    U,
+    ROWKF,
+    ROWK,
+    ROWVF,
+    ROWV,
+    ROWSELF,
+    ROWEVF,
+>(
     header_fn: HF,
     header_key_fn: KHF,
     header_view_fn: VHF,
     rows_fn: ROWSF,
     row_key_fn: ROWKF,
     row_view_fn: ROWVF,
+    row_selected_fn: ROWSELF,
+    row_event_fn: ROWEVF,
     widths_fn: WF,
 ) -> impl View
 where
@@ -45,6 +68,8 @@ where
     ROWK: Eq + Hash + 'static,
     ROWVF: Fn(&T, &U) -> ROWV + 'static + Clone,
     ROWV: View + 'static,
+    ROWSELF: Fn(&U) -> bool + 'static + Clone,
+    ROWEVF: Fn(&U, &Event) -> bool + 'static + Clone,
 {
     let header_fn = Arc::new(header_fn);
     let header_key_fn = Arc::new(header_key_fn);
@@ -68,6 +93,8 @@ where
                 move || rows_fn(),
                 move |x| row_key_fn(x),
                 move |x, y| row_view_fn(x, y),
+                row_selected_fn,
+                row_event_fn,
                 move |x| widths_fn2(x),
             ),
         )
@@ -115,22 +142,24 @@ where
 {
     container(move || header_view_fn(x)).style(move || {
         Style::BASE
-            .background(DARK1_BG)
+            .background(theme().input_bg)
             .padding_horiz_px(10.0)
             .padding_vert_px(3.0)
             .border_bottom(0.8)
             .border_right(0.8)
-            .border_color(DARK3_BG)
+            .border_color(theme().border)
             .width_px(width)
     })
 }
 
-fn table_rows<T, HF, H, WF, KHF, KH, ROWSF, ROWS, U, ROWKF, ROWK, ROWVF, ROWV>(
+fn table_rows<T, HF, H, WF, KHF, KH, ROWSF, ROWS, U, ROWKF, ROWK, ROWVF, ROWV, ROWSELF, ROWEVF>(
     header_fn: HF,
     header_key_fn: KHF,
     rows_fn: ROWSF,
     row_key_fn: ROWKF,
     row_view_fn: ROWVF,
+    row_selected_fn: ROWSELF,
+    row_event_fn: ROWEVF,
     widths_fn: WF,
 ) -> impl View
 where
@@ -147,6 +176,8 @@ where
     ROWK: Eq + Hash + 'static,
     ROWVF: Fn(&T, &U) -> ROWV + 'static + Clone,
     ROWV: View + 'static,
+    ROWSELF: Fn(&U) -> bool + 'static + Clone,
+    ROWEVF: Fn(&U, &Event) -> bool + 'static + Clone,
 {
     // A list of lists.
     // The outer list is for each row in the table.
@@ -161,6 +192,18 @@ where
             let header_fn = header_fn.clone();
             let widths_fn = widths_fn.clone();
             let header_key_fn = header_key_fn.clone();
+            let x = Rc::new(x);
+            let selected = {
+                let x = x.clone();
+                let row_selected_fn = row_selected_fn.clone();
+                move || row_selected_fn(&x)
+            };
+            let on_pointer_down = {
+                let x = x.clone();
+                let row_event_fn = row_event_fn.clone();
+                move |event: &Event| row_event_fn(&x, event)
+            };
+            let on_pointer_up = on_pointer_down.clone();
             // TODO(minor): Does this really need a container?
             container(move || {
                 let row_view_fn = row_view_fn.clone();
@@ -172,16 +215,30 @@ where
                         let row_view_fn = row_view_fn.clone();
                         let widths_fn = widths_fn.clone();
                         let width = widths_fn(&y);
-                        table_row_entry(move |x, y| row_view_fn(x, y), &y, &x, width)
+                        table_row_entry(
+                            move |x, y| row_view_fn(x, y),
+                            &y,
+                            &*x,
+                            width,
+                            selected.clone(),
+                        )
                     },
                 )
             })
+            .on_event(EventListener::PointerDown, on_pointer_down)
+            .on_event(EventListener::PointerUp, on_pointer_up)
         },
     )
     .base_style(|| Style::BASE.flex_col())
 }
 
-fn table_row_entry<T, U, VHF, V>(row_view_fn: VHF, x: &T, y: &U, width: f32) -> impl View
+fn table_row_entry<T, U, VHF, V>(
+    row_view_fn: VHF,
+    x: &T,
+    y: &U,
+    width: f32,
+    selected: impl Fn() -> bool + 'static,
+) -> impl View
 where
     T: 'static,
     U: 'static,
@@ -190,12 +247,16 @@ where
 {
     container(move || row_view_fn(&x, &y)).style(move || {
         Style::BASE
-            .background(DARK2_BG)
+            .background(if selected() {
+                theme().selected_bg
+            } else {
+                theme().main_bg
+            })
             .padding_horiz_px(10.0)
             .padding_vert_px(3.0)
             .border_bottom(0.8)
             .border_right(0.8)
-            .border_color(DARK3_BG)
+            .border_color(theme().border)
             .width_px(width)
     })
 }
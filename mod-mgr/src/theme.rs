@@ -0,0 +1,154 @@
+//! The colors and font sizes of the views, which can be switched while running.
+//! Styles read the theme with [`theme`], which tracks it, so they update when it changes.
+
+use std::cell::Cell;
+
+use floem::{
+    peniko::Color,
+    reactive::{create_rw_signal, RwSignal},
+};
+use mod_mgr_lib::settings::ThemeKind;
+
+use crate::tab_view::{TabButtonStyle, TabSwitcherStyle};
+
+/// Which of the icon variants to use. The icons are hand-modified, as floem can't tint them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconTint {
+    /// For dark backgrounds
+    Light,
+    /// For light backgrounds
+    Dark,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Headers/footers
+    pub header_bg: Color,
+    /// Inputs
+    pub input_bg: Color,
+    /// Main background
+    pub main_bg: Color,
+    /// Selected option background
+    pub selected_bg: Color,
+    pub border: Color,
+    /// Border of the focused view
+    pub focus_border: Color,
+    pub text: Color,
+    /// Search matches
+    pub highlight_bg: Color,
+    /// Text for problems, such as conflicting key bindings
+    pub warning_text: Color,
+
+    /// Forms, tables and the details panel
+    pub font_size: f32,
+    /// Buttons and the mod lists
+    pub large_font_size: f32,
+    /// Tabs and titles
+    pub heading_font_size: f32,
+
+    pub icons: IconTint,
+}
+impl Theme {
+    pub fn preset(kind: ThemeKind) -> &'static Theme {
+        match kind {
+            ThemeKind::Dark => &DARK,
+            ThemeKind::Light => &LIGHT,
+            ThemeKind::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+
+    pub fn tab_switcher_style(&self) -> TabSwitcherStyle {
+        TabSwitcherStyle {
+            separator_background: self.selected_bg,
+            button_area_background: self.header_bg,
+            button: TabButtonStyle {
+                font_size: self.heading_font_size,
+                background_color: self.main_bg,
+                active_background_color: self.selected_bg,
+                color: self.text,
+                active_color: self.text,
+                border_color: self.border,
+                focus_border_color: self.focus_border,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+const DARK: Theme = Theme {
+    header_bg: Color::rgb8(42, 43, 52),
+    input_bg: Color::rgb8(51, 51, 64),
+    main_bg: Color::rgb8(61, 61, 76),
+    selected_bg: Color::rgb8(76, 79, 98),
+    border: Color::rgb8(76, 79, 98),
+    focus_border: Color::BLUE,
+    text: Color::rgb8(209, 209, 212),
+    highlight_bg: Color::rgb8(110, 96, 44),
+    warning_text: Color::rgb8(235, 140, 110),
+    font_size: 12.0,
+    large_font_size: 14.0,
+    heading_font_size: 16.0,
+    icons: IconTint::Light,
+};
+
+const LIGHT: Theme = Theme {
+    header_bg: Color::rgb8(225, 226, 232),
+    input_bg: Color::rgb8(250, 250, 252),
+    main_bg: Color::rgb8(240, 240, 244),
+    selected_bg: Color::rgb8(196, 208, 232),
+    border: Color::rgb8(180, 182, 194),
+    focus_border: Color::BLUE,
+    text: Color::rgb8(32, 33, 40),
+    highlight_bg: Color::rgb8(250, 220, 120),
+    warning_text: Color::rgb8(178, 50, 20),
+    font_size: 12.0,
+    large_font_size: 14.0,
+    heading_font_size: 16.0,
+    icons: IconTint::Dark,
+};
+
+/// Pure black and white, with larger text
+const HIGH_CONTRAST: Theme = Theme {
+    header_bg: Color::BLACK,
+    input_bg: Color::BLACK,
+    main_bg: Color::BLACK,
+    selected_bg: Color::rgb8(0, 60, 140),
+    border: Color::WHITE,
+    focus_border: Color::YELLOW,
+    text: Color::WHITE,
+    highlight_bg: Color::rgb8(150, 110, 0),
+    warning_text: Color::YELLOW,
+    font_size: 14.0,
+    large_font_size: 16.0,
+    heading_font_size: 18.0,
+    icons: IconTint::Light,
+};
+
+thread_local! {
+    static THEME: Cell<Option<RwSignal<ThemeKind>>> = const { Cell::new(None) };
+}
+
+/// The signal is created on first use, as the views are all made on the main thread
+fn theme_signal() -> RwSignal<ThemeKind> {
+    THEME.with(|theme| match theme.get() {
+        Some(signal) => signal,
+        None => {
+            let signal = create_rw_signal(ThemeKind::default());
+            theme.set(Some(signal));
+            signal
+        }
+    })
+}
+
+/// The theme in use.
+/// Note: this will track the signals required
+pub fn theme() -> &'static Theme {
+    Theme::preset(theme_signal().get())
+}
+
+pub fn set_theme(kind: ThemeKind) {
+    let signal = theme_signal();
+    if signal.get_untracked() != kind {
+        signal.set(kind);
+    }
+}
@@ -1,9 +1,10 @@
 //! https://github.com/LaughingLeader/BG3ModManager/blob/master/DivinityModManagerCore/Models/DivinityModData.cs  
 //! Though this is split up into multiple files in a different manner.
 
-use std::{borrow::Cow, path::PathBuf};
+use std::path::PathBuf;
 
 use mod_mgr_lib::{
+    extender::{DivinityExtenderModStatus, ExtenderInstall, ScriptExtenderConfig},
     mod_data::{DivinityModDependencyData, ModData},
     MAIN_CAMPAIGN_UUID,
 };
@@ -70,84 +71,39 @@ impl UIModData {
         }
     }
 
-    pub fn can_add_to_load_order(&self) -> bool {
-        self.data.mod_type != "Adventure"
+    /// Whether this is a mod that the game always loads, and so it can't be placed in the load
+    /// order.
+    pub fn is_override_mod(&self) -> bool {
+        self.data.is_force_loaded
+            && !self.data.is_force_loaded_merged_mod
             && !self.data.is_larian_mod
-            && (!self.data.is_force_loaded || self.data.is_force_loaded_merged_mod)
     }
-}
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub enum DivinityExtenderModStatus {
-    #[default]
-    None,
-    Supports,
-    Required,
-    RequiredOld,
-    RequiredMissing,
-    RequiredDisabled,
-}
-impl DivinityExtenderModStatus {
-    pub fn tooltip(self, data: &ScriptExtenderConfig) -> Cow<'static, str> {
-        use DivinityExtenderModStatus::*;
-        match self {
-            None => Cow::Borrowed(""),
-            Supports => {
-                if data.required_extension_version.is_some() {
-                    Cow::Owned(format!(
-                        "Supports Script Extender v{} or higher\n",
-                        data.required_extension_version.unwrap()
-                    ))
-                } else {
-                    Cow::Borrowed("Supports the Script Extender\n")
-                }
-            }
-            Required | RequiredMissing | RequiredDisabled | RequiredOld => {
-                let prefix = if self == RequiredMissing {
-                    "[MISSING] "
-                } else if self == RequiredDisabled {
-                    "[EXTENSIONS DISABLED] "
-                } else if self == RequiredOld {
-                    "[OLD] "
-                } else {
-                    unreachable!()
-                };
-
-                let req = if let Some(version) = data.required_extension_version {
-                    Cow::Owned(format!("Requires Script Extender v{version} or higher"))
-                } else {
-                    Cow::Borrowed("Requires the Script Extender")
-                };
+    /// Whether the mod is shown in the active and inactive mod lists, and so is part of the order
+    /// the user edits.
+    pub fn is_listed(&self) -> bool {
+        !self.is_override_mod() && !self.data.is_hidden
+    }
 
-                let extra = if self == RequiredDisabled {
-                    " (Enable Extensions in the Script Extender config)"
-                } else if self == RequiredOld {
-                    " (Update by running the game)"
-                } else {
-                    ""
-                };
+    /// Set the mod's Script Extender config, from its `ScriptExtender/Config.json`.
+    pub fn set_extender_config(&mut self, config: Option<ScriptExtenderConfig>) {
+        self.extender_data = config.unwrap_or_default();
+        self.has_script_extender_settings = self.extender_data.has_any_settings();
+    }
 
-                Cow::Owned(format!("{}{}{}\n", prefix, req, extra))
-            }
-        }
+    pub fn update_extender_status(&mut self, install: &ExtenderInstall, extensions_enabled: bool) {
+        self.current_extender_version = install.version();
+        self.extender_status =
+            DivinityExtenderModStatus::compute(&self.extender_data, install, extensions_enabled);
     }
-}
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct ScriptExtenderConfig {
-    pub required_extension_version: Option<u32>,
-    pub feature_flags: Vec<String>,
-}
-impl ScriptExtenderConfig {
-    pub fn has_any_settings(&self) -> bool {
-        self.required_extension_version.is_some() || !self.feature_flags.is_empty()
+    pub fn can_add_to_load_order(&self) -> bool {
+        self.data.mod_type != "Adventure"
+            && !self.data.is_larian_mod
+            && (!self.data.is_force_loaded || self.data.is_force_loaded_merged_mod)
     }
 }
 
 pub fn load_workshop_mods() {
     todo!()
 }
-
-pub fn check_for_mod_updates() {
-    todo!()
-}
@@ -1,39 +1,80 @@
+use std::{cell::Cell, ops::Range};
+
 use floem::{
     cosmic_text::Weight,
-    peniko::Color,
-    reactive::RwSignal,
+    event::EventListener,
+    reactive::{create_rw_signal, RwSignal},
     style::{CursorStyle, Style},
     view::View,
     view_tuple::ViewTuple,
-    views::{checkbox, container, label, stack, svg, text_input, Container, Decorators},
+    views::{checkbox, container, label, list, stack, svg, text_input, Container, Decorators},
 };
+use mod_mgr_lib::mod_filter::split_highlights;
+
+use crate::theme::{theme, IconTint};
 
-use crate::{DARK2_BG, DARK3_BG, DARK_TEXT};
+/// The variant of the icon that fits the theme.
+/// Note: this will track the signals required
+fn themed_icon(light: &str, dark: &str) -> String {
+    match theme().icons {
+        IconTint::Light => light.to_string(),
+        IconTint::Dark => dark.to_string(),
+    }
+}
 
 pub fn save_icon() -> String {
-    include_str!("../assets/document-save-light.svg").to_string()
+    themed_icon(
+        include_str!("../assets/document-save-light.svg"),
+        include_str!("../assets/document-save-dark.svg"),
+    )
 }
 
 pub fn save_as_icon() -> String {
-    include_str!("../assets/document-save-as-light.svg").to_string()
+    themed_icon(
+        include_str!("../assets/document-save-as-light.svg"),
+        include_str!("../assets/document-save-as-dark.svg"),
+    )
 }
 
 pub fn forward_icon() -> String {
-    include_str!("../assets/forward-light.svg").to_string()
+    themed_icon(
+        include_str!("../assets/forward-light.svg"),
+        include_str!("../assets/forward-dark.svg"),
+    )
 }
 
 pub fn hard_disk_icon() -> String {
-    include_str!("../assets/drive-harddisk-light.svg").to_string()
+    themed_icon(
+        include_str!("../assets/drive-harddisk-light.svg"),
+        include_str!("../assets/drive-harddisk-dark.svg"),
+    )
 }
 
 pub fn settings_icon() -> String {
-    include_str!("../assets/settings-light.svg").to_string()
+    themed_icon(
+        include_str!("../assets/settings-light.svg"),
+        include_str!("../assets/settings-dark.svg"),
+    )
+}
+
+pub fn ok_icon() -> String {
+    themed_icon(
+        include_str!("../assets/emblem-ok-light.svg"),
+        include_str!("../assets/emblem-ok-dark.svg"),
+    )
+}
+
+pub fn important_icon() -> String {
+    themed_icon(
+        include_str!("../assets/emblem-important-light.svg"),
+        include_str!("../assets/emblem-important-dark.svg"),
+    )
 }
 
 /// Checkbox that automatically applies the signal on click
 pub fn auto_checkbox(signal: RwSignal<bool>) -> impl View {
     checkbox(signal.read_only())
-        .focus_visible_style(|| Style::BASE.border_color(Color::BLUE).border(0.5))
+        .focus_visible_style(|| Style::BASE.border_color(theme().focus_border).border(0.5))
         .on_click(move |_| {
             signal.update(|value| *value = !*value);
             true
@@ -48,7 +89,7 @@ pub fn form<VT: ViewTuple + 'static>(children: impl FnOnce() -> VT) -> impl View
             // .margin_px(10.0)
             .padding_px(10.0)
             .width_pct(100.0)
-            .font_size(12.0)
+            .font_size(theme().font_size)
     })
 }
 
@@ -86,8 +127,47 @@ pub fn form_item<V: View + 'static>(
     })
 }
 
+/// Show the text just above the view while the pointer is over it, like a tooltip. Nothing is
+/// shown if it is empty.
+pub fn with_tooltip<V: View + 'static>(
+    text: &'static str,
+    view_fn: impl FnOnce() -> V,
+) -> impl View {
+    let hovered = create_rw_signal(false);
+    stack(move || {
+        (
+            view_fn(),
+            // Drawn over whatever is above the view rather than taking up space, so showing it
+            // doesn't move the views below
+            label(move || text.to_string()).style(move || {
+                Style::BASE
+                    .absolute()
+                    .inset_bottom_pct(100.0)
+                    .inset_left_px(10.0)
+                    .max_width_pct(90.0)
+                    .padding_horiz_px(6.0)
+                    .padding_vert_px(3.0)
+                    .border(1.0)
+                    .border_color(theme().border)
+                    .background(theme().input_bg)
+                    .font_size(theme().font_size)
+                    .color(theme().text)
+                    .apply_if(text.is_empty() || !hovered.get(), |s| s.hide())
+            }),
+        )
+    })
+    .style(|| Style::BASE.flex_col().width_pct(100.0))
+    .on_event(EventListener::PointerEnter, move |_| {
+        hovered.set(true);
+        false
+    })
+    .on_event(EventListener::PointerLeave, move |_| {
+        hovered.set(false);
+        false
+    })
+}
+
 // TODO: make border nicer
-// TODO: theming
 // TODO: tooltip
 // TODO: center text correctly
 // TODO: that weird background text that I forget the name of
@@ -98,38 +178,96 @@ pub fn simple_form_input(
     height: f32,
 ) -> impl View {
     form_item(text.to_string(), width, move || {
-        // TODO: A bit more padding might be nice.
-        text_input(signal)
-            .style(move || {
-                Style::BASE
-                    .border(0.3)
-                    .height_px(height)
-                    .inset_px(2.0)
-                    .border_color(DARK_TEXT)
-            })
-            .keyboard_navigatable()
+        form_text_input(signal, height)
     })
 }
 
+/// The text input of [`simple_form_input`], for when the input itself is needed, such as to focus
+/// it.
+thread_local! {
+    static TEXT_INPUT_FOCUSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether one of the text inputs has focus, in which case keys are for typing rather than for
+/// the key bindings.
+pub fn is_text_input_focused() -> bool {
+    TEXT_INPUT_FOCUSED.with(Cell::get)
+}
+
+pub fn form_text_input(signal: RwSignal<String>, height: f32) -> impl View {
+    // TODO: A bit more padding might be nice.
+    text_input(signal)
+        .on_event(EventListener::FocusGained, |_| {
+            TEXT_INPUT_FOCUSED.with(|focused| focused.set(true));
+            false
+        })
+        .on_event(EventListener::FocusLost, |_| {
+            TEXT_INPUT_FOCUSED.with(|focused| focused.set(false));
+            false
+        })
+        .style(move || {
+            Style::BASE
+                .border(0.3)
+                .height_px(height)
+                .inset_px(2.0)
+                .border_color(theme().text)
+        })
+        .keyboard_navigatable()
+}
+
+/// Text with parts of it highlighted, such as search matches.  
+/// `ranges_fn` gives the byte ranges of the text to highlight. Both are rerun when the signals they
+/// read change.
+pub fn highlighted_text(
+    text_fn: impl Fn() -> String + 'static,
+    ranges_fn: impl Fn(&str) -> Vec<Range<usize>> + 'static,
+) -> impl View {
+    list(
+        move || {
+            let text = text_fn();
+            split_highlights(&text, &ranges_fn(&text))
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (part, highlighted))| (idx, part.to_string(), highlighted))
+                .collect::<Vec<_>>()
+        },
+        Clone::clone,
+        |(_, part, highlighted)| {
+            label(move || part.clone()).style(move || {
+                Style::BASE.apply_if(highlighted, |s| s.background(theme().highlight_bg))
+            })
+        },
+    )
+    .style(|| Style::BASE.flex_row())
+}
+
 // TODO: tooltip
 /// A simple button view. Intended to look relatively native.  
 /// The callback should return true if the event was handled and it should not be propagated further.
 pub fn button(text: impl Into<String>, on_click: impl Fn() -> bool + 'static) -> impl View {
     let text = text.into();
-    label(move || text.clone())
+    dyn_button(move || text.clone(), on_click)
+}
+
+/// A [`button`] whose text can change, such as one that cycles through options.
+pub fn dyn_button(
+    text_fn: impl Fn() -> String + 'static,
+    on_click: impl Fn() -> bool + 'static,
+) -> impl View {
+    label(text_fn)
         .on_click(move |_| on_click())
         .base_style(|| {
             Style::BASE
                 .flex_col()
                 .padding_horiz_px(6.0)
                 .padding_vert_px(1.0)
-                .font_size(14.0)
-                .background(DARK2_BG)
-                .color(DARK_TEXT)
+                .font_size(theme().large_font_size)
+                .background(theme().main_bg)
+                .color(theme().text)
                 .justify_center()
                 .min_width_px(80.0)
                 .border(0.6)
-                .border_color(DARK3_BG)
+                .border_color(theme().border)
         })
         .hover_style(|| {
             // TODO: change background color?
@@ -138,9 +276,12 @@ pub fn button(text: impl Into<String>, on_click: impl Fn() -> bool + 'static) ->
 }
 
 // TODO: tooltip
-pub fn svg_button(svg_v: impl Into<String>, on_click: impl Fn() -> bool + 'static) -> impl View {
-    let svg_v = svg_v.into();
-    svg(move || svg_v.clone())
+/// `icon_fn` is rerun when the theme changes, such as one of the `_icon` functions.
+pub fn svg_button(
+    icon_fn: impl Fn() -> String + 'static,
+    on_click: impl Fn() -> bool + 'static,
+) -> impl View {
+    svg(icon_fn)
         .on_click(move |_| on_click())
         .base_style(|| Style::BASE.min_size_px(32.0, 32.0))
         .hover_style(|| {